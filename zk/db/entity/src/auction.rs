@@ -1,6 +1,6 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.0
 
-use super::sea_orm_active_enums::Status;
+use super::sea_orm_active_enums::{ AuctionOutcome, AuctionType, Status };
 use sea_orm::entity::prelude::*;
 use serde::{ Deserialize, Serialize };
 
@@ -18,10 +18,37 @@ pub struct Model {
     pub status: Option<Status>,
     pub created_at: DateTime,
     pub updated_at: DateTime,
+    pub winner: Option<String>,
+    pub winning_bid_id: Option<i32>,
+    pub settled_at: Option<DateTime>,
+    pub auction_type: Option<AuctionType>,
+    pub reveal_end_time: Option<DateTime>,
+    pub floor_price: Option<i32>,
+    pub reserve_price: Option<i32>,
+    pub buy_now_price: Option<i32>,
+    pub min_increment: Option<i32>,
+    pub outcome: Option<AuctionOutcome>,
+    pub relisted_from: Option<i32>,
+    pub soft_close_window: Option<i32>,
+    pub soft_close_extension: Option<i32>,
+    pub max_end_time: Option<DateTime>,
+    // set by the host from `current_bid`, clients leave it out
+    #[serde(default)]
+    pub opening_price: i32,
+    // set by settlement; too large to list with the auction
+    #[serde(default, skip_serializing)]
+    pub settlement_receipt: Option<Json>,
+    // set by sealed commitments, clients leave it out
+    #[serde(default)]
+    pub commitment_root: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::auction_extension::Entity")]
+    AuctionExtension,
+    #[sea_orm(has_many = "super::auction_transition::Entity")]
+    AuctionTransition,
     #[sea_orm(has_many = "super::bid::Entity")]
     Bid,
     #[sea_orm(
@@ -34,8 +61,24 @@ pub enum Relation {
     Car,
     #[sea_orm(has_many = "super::comment::Entity")]
     Comment,
+    #[sea_orm(has_many = "super::proxy_bid::Entity")]
+    ProxyBid,
     #[sea_orm(has_many = "super::saved_auction::Entity")]
     SavedAuction,
+    #[sea_orm(has_many = "super::sealed_bid::Entity")]
+    SealedBid,
+}
+
+impl Related<super::auction_extension::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::AuctionExtension.def()
+    }
+}
+
+impl Related<super::auction_transition::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::AuctionTransition.def()
+    }
 }
 
 impl Related<super::bid::Entity> for Entity {
@@ -56,10 +99,22 @@ impl Related<super::comment::Entity> for Entity {
     }
 }
 
+impl Related<super::proxy_bid::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ProxyBid.def()
+    }
+}
+
 impl Related<super::saved_auction::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::SavedAuction.def()
     }
 }
 
+impl Related<super::sealed_bid::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SealedBid.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
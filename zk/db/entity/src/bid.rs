@@ -11,8 +11,15 @@ pub struct Model {
     pub auction_id: i32,
     pub bidder_id: String,
     pub amount: i32,
+    pub nonce: Option<i64>,
+    pub deadline: Option<i64>,
+    pub signature: Option<String>,
+    pub proxy: bool,
     pub created_at: DateTime,
     pub updated_at: DateTime,
+    pub retracted_at: Option<DateTime>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub retraction_reason: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
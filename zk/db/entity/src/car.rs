@@ -50,6 +50,10 @@ pub struct Model {
 pub enum Relation {
     #[sea_orm(has_many = "super::auction::Entity")]
     Auction,
+    #[sea_orm(has_many = "super::car_ownership::Entity")]
+    CarOwnership,
+    #[sea_orm(has_many = "super::offer::Entity")]
+    Offer,
 }
 
 impl Related<super::auction::Entity> for Entity {
@@ -58,4 +62,16 @@ impl Related<super::auction::Entity> for Entity {
     }
 }
 
+impl Related<super::car_ownership::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::CarOwnership.def()
+    }
+}
+
+impl Related<super::offer::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Offer.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -5,6 +5,17 @@ pub mod bid;
 pub mod car;
 pub mod comment;
 pub mod saved_auction;
+pub mod refresh_token;
+pub mod user_role;
+pub mod login_audit;
+pub mod sealed_bid;
+pub mod auction_extension;
+pub mod proxy_bid;
+pub mod auction_transition;
+pub mod car_ownership;
+pub mod offer;
+pub mod state_sequence;
+pub mod idempotency_key;
 pub mod sea_orm_active_enums;
 
 // Re-export commonly used types
@@ -13,7 +24,27 @@ pub use auction::Entity as Auction;
 pub use bid::Entity as Bid;
 pub use comment::Entity as Comment;
 pub use saved_auction::Entity as SavedAuction;
-pub use sea_orm_active_enums::Status;
+pub use refresh_token::Entity as RefreshToken;
+pub use user_role::Entity as UserRole;
+pub use login_audit::Entity as LoginAudit;
+pub use sealed_bid::Entity as SealedBid;
+pub use auction_extension::Entity as AuctionExtension;
+pub use proxy_bid::Entity as ProxyBid;
+pub use auction_transition::Entity as AuctionTransition;
+pub use car_ownership::Entity as CarOwnership;
+pub use offer::Entity as Offer;
+pub use state_sequence::Entity as StateSequence;
+pub use idempotency_key::Entity as IdempotencyKey;
+pub use sea_orm_active_enums::{
+    AuctionOutcome,
+    AuctionType,
+    LoginAttestation,
+    LoginStatus,
+    OfferStatus,
+    Role,
+    SequenceStatus,
+    Status,
+};
 
 // Re-export model types
 pub use car::Model as CarModel;
@@ -21,3 +52,14 @@ pub use auction::Model as AuctionModel;
 pub use bid::Model as BidModel;
 pub use comment::Model as CommentModel;
 pub use saved_auction::Model as SavedAuctionModel;
+pub use refresh_token::Model as RefreshTokenModel;
+pub use user_role::Model as UserRoleModel;
+pub use login_audit::Model as LoginAuditModel;
+pub use sealed_bid::Model as SealedBidModel;
+pub use auction_extension::Model as AuctionExtensionModel;
+pub use proxy_bid::Model as ProxyBidModel;
+pub use auction_transition::Model as AuctionTransitionModel;
+pub use car_ownership::Model as CarOwnershipModel;
+pub use offer::Model as OfferModel;
+pub use state_sequence::Model as StateSequenceModel;
+pub use idempotency_key::Model as IdempotencyKeyModel;
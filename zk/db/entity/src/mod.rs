@@ -7,4 +7,15 @@ pub mod bid;
 pub mod car;
 pub mod comment;
 pub mod saved_auction;
+pub mod refresh_token;
+pub mod user_role;
+pub mod login_audit;
+pub mod sealed_bid;
+pub mod auction_extension;
+pub mod proxy_bid;
+pub mod auction_transition;
+pub mod car_ownership;
+pub mod offer;
+pub mod state_sequence;
+pub mod idempotency_key;
 pub mod sea_orm_active_enums;
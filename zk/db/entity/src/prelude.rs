@@ -5,3 +5,14 @@ pub use super::bid::Entity as Bid;
 pub use super::car::Entity as Car;
 pub use super::comment::Entity as Comment;
 pub use super::saved_auction::Entity as SavedAuction;
+pub use super::refresh_token::Entity as RefreshToken;
+pub use super::user_role::Entity as UserRole;
+pub use super::login_audit::Entity as LoginAudit;
+pub use super::sealed_bid::Entity as SealedBid;
+pub use super::auction_extension::Entity as AuctionExtension;
+pub use super::proxy_bid::Entity as ProxyBid;
+pub use super::auction_transition::Entity as AuctionTransition;
+pub use super::car_ownership::Entity as CarOwnership;
+pub use super::offer::Entity as Offer;
+pub use super::state_sequence::Entity as StateSequence;
+pub use super::idempotency_key::Entity as IdempotencyKey;
@@ -15,3 +15,95 @@ pub enum Status {
     #[sea_orm(string_value = "cancelled")]
     Cancelled,
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter, DeriveActiveEnum, Deserialize, Serialize)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "role")]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    #[sea_orm(string_value = "admin")]
+    Admin,
+    #[sea_orm(string_value = "seller")]
+    Seller,
+    #[sea_orm(string_value = "bidder")]
+    Bidder,
+    #[sea_orm(string_value = "inspector")]
+    Inspector,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Deserialize, Serialize)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "login_status")]
+#[serde(rename_all = "lowercase")]
+pub enum LoginStatus {
+    #[sea_orm(string_value = "pending")]
+    Pending,
+    #[sea_orm(string_value = "proven")]
+    Proven,
+    #[sea_orm(string_value = "rejected")]
+    Rejected,
+    #[sea_orm(string_value = "failed")]
+    Failed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Deserialize, Serialize)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "login_attestation")]
+#[serde(rename_all = "lowercase")]
+pub enum LoginAttestation {
+    #[sea_orm(string_value = "zkvm")]
+    Zkvm,
+    #[sea_orm(string_value = "eip1271")]
+    Eip1271,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Deserialize, Serialize)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "auction_type")]
+#[serde(rename_all = "lowercase")]
+pub enum AuctionType {
+    #[sea_orm(string_value = "english")]
+    English,
+    #[sea_orm(string_value = "sealed")]
+    Sealed,
+    #[sea_orm(string_value = "vickrey")]
+    Vickrey,
+    #[sea_orm(string_value = "dutch")]
+    Dutch,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Deserialize, Serialize)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "auction_outcome")]
+#[serde(rename_all = "snake_case")]
+pub enum AuctionOutcome {
+    #[sea_orm(string_value = "sold")]
+    Sold,
+    #[sea_orm(string_value = "no_bids")]
+    NoBids,
+    #[sea_orm(string_value = "reserve_not_met")]
+    ReserveNotMet,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Deserialize, Serialize)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "offer_status")]
+#[serde(rename_all = "snake_case")]
+pub enum OfferStatus {
+    #[sea_orm(string_value = "pending")]
+    Pending,
+    #[sea_orm(string_value = "accepted")]
+    Accepted,
+    #[sea_orm(string_value = "rejected")]
+    Rejected,
+    #[sea_orm(string_value = "countered")]
+    Countered,
+    #[sea_orm(string_value = "expired")]
+    Expired,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Deserialize, Serialize)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "sequence_status")]
+#[serde(rename_all = "snake_case")]
+pub enum SequenceStatus {
+    #[sea_orm(string_value = "pending")]
+    Pending,
+    #[sea_orm(string_value = "proven")]
+    Proven,
+    #[sea_orm(string_value = "failed")]
+    Failed,
+}
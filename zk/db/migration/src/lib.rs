@@ -1,12 +1,54 @@
 pub use sea_orm_migration::prelude::*;
 
 mod m20220101_000001_create_table;
+mod m20261019_000001_create_refresh_token;
+mod m20261019_000002_create_user_role;
+mod m20261019_000003_create_login_audit;
+mod m20261019_000004_add_bid_signature;
+mod m20261019_000005_add_login_attestation;
+mod m20261019_000006_add_auction_winner;
+mod m20261019_000007_create_sealed_bid;
+mod m20261019_000008_add_vickrey_dutch;
+mod m20261019_000009_add_auction_pricing;
+mod m20261019_000010_create_auction_extension;
+mod m20261019_000011_create_proxy_bid;
+mod m20261019_000012_create_auction_transition;
+mod m20261019_000013_create_car_ownership;
+mod m20261019_000014_create_offer;
+mod m20261019_000015_add_bid_retraction;
+mod m20261019_000016_sync_id_sequences;
+mod m20261019_000017_create_state_sequence;
+mod m20261019_000018_create_idempotency_key;
+mod m20261019_000019_add_settlement_receipt;
+mod m20261019_000020_add_commitment_root;
 
 pub struct Migrator;
 
 #[async_trait::async_trait]
 impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
-        vec![Box::new(m20220101_000001_create_table::Migration)]
+        vec![
+            Box::new(m20220101_000001_create_table::Migration),
+            Box::new(m20261019_000001_create_refresh_token::Migration),
+            Box::new(m20261019_000002_create_user_role::Migration),
+            Box::new(m20261019_000003_create_login_audit::Migration),
+            Box::new(m20261019_000004_add_bid_signature::Migration),
+            Box::new(m20261019_000005_add_login_attestation::Migration),
+            Box::new(m20261019_000006_add_auction_winner::Migration),
+            Box::new(m20261019_000007_create_sealed_bid::Migration),
+            Box::new(m20261019_000008_add_vickrey_dutch::Migration),
+            Box::new(m20261019_000009_add_auction_pricing::Migration),
+            Box::new(m20261019_000010_create_auction_extension::Migration),
+            Box::new(m20261019_000011_create_proxy_bid::Migration),
+            Box::new(m20261019_000012_create_auction_transition::Migration),
+            Box::new(m20261019_000013_create_car_ownership::Migration),
+            Box::new(m20261019_000014_create_offer::Migration),
+            Box::new(m20261019_000015_add_bid_retraction::Migration),
+            Box::new(m20261019_000016_sync_id_sequences::Migration),
+            Box::new(m20261019_000017_create_state_sequence::Migration),
+            Box::new(m20261019_000018_create_idempotency_key::Migration),
+            Box::new(m20261019_000019_add_settlement_receipt::Migration),
+            Box::new(m20261019_000020_add_commitment_root::Migration)
+        ]
     }
 }
@@ -77,6 +77,7 @@ pub enum SavedAuction {
 // }
 
 #[derive(DeriveIden)]
+#[allow(clippy::upper_case_acronyms)]
 pub enum Car {
     Table,
     Id,
@@ -0,0 +1,49 @@
+use sea_orm_migration::{ prelude::*, schema::* };
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+pub enum RefreshToken {
+    Table,
+    Id,
+    TokenHash, // sha256 of the token, the token itself is never stored
+    Address,
+    Username,
+    ExpiresAt,
+    RevokedAt,
+    CreatedAt,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.create_table(
+            Table::create()
+                .table(RefreshToken::Table)
+                .if_not_exists()
+                .col(pk_auto(RefreshToken::Id))
+                .col(string_uniq(RefreshToken::TokenHash))
+                .col(string(RefreshToken::Address))
+                .col(string(RefreshToken::Username))
+                .col(timestamp(RefreshToken::ExpiresAt))
+                .col(timestamp_null(RefreshToken::RevokedAt))
+                .col(timestamp(RefreshToken::CreatedAt))
+                .to_owned()
+        ).await?;
+
+        manager.create_index(
+            Index::create()
+                .name("idx_refresh_token_address")
+                .table(RefreshToken::Table)
+                .col(RefreshToken::Address)
+                .to_owned()
+        ).await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_table(Table::drop().table(RefreshToken::Table).to_owned()).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,89 @@
+use sea_orm_migration::{ prelude::*, schema::* };
+use sea_orm::{ EnumIter, Iterable };
+use sea_orm_migration::prelude::extension::postgres::Type;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+pub enum UserRole {
+    Table,
+    Id,
+    Address, // lowercase 0x address
+    Role,
+    GrantedBy, // admin address, null for roles granted at login
+    CreatedAt,
+}
+
+#[derive(Iden)]
+pub enum RoleEnumType {
+    #[iden = "role"]
+    Role,
+}
+
+#[derive(EnumIter)]
+pub enum Role {
+    Admin,
+    Seller,
+    Bidder,
+    Inspector,
+}
+
+impl Role {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Role::Admin => "admin",
+            Role::Seller => "seller",
+            Role::Bidder => "bidder",
+            Role::Inspector => "inspector",
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.create_type(
+            Type::create()
+                .as_enum("role")
+                .values(Role::iter().map(|v| v.as_str()))
+                .to_owned()
+        ).await?;
+
+        manager.create_table(
+            Table::create()
+                .table(UserRole::Table)
+                .if_not_exists()
+                .col(pk_auto(UserRole::Id))
+                .col(string(UserRole::Address))
+                .col(
+                    enumeration(
+                        UserRole::Role,
+                        RoleEnumType::Role,
+                        Role::iter().map(|v| v.as_str())
+                    )
+                )
+                .col(string_null(UserRole::GrantedBy))
+                .col(timestamp(UserRole::CreatedAt))
+                .to_owned()
+        ).await?;
+
+        // an address holds each role at most once
+        manager.create_index(
+            Index::create()
+                .name("idx_user_role_address_role")
+                .table(UserRole::Table)
+                .col(UserRole::Address)
+                .col(UserRole::Role)
+                .unique()
+                .to_owned()
+        ).await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_table(Table::drop().table(UserRole::Table).to_owned()).await?;
+        manager.drop_type(Type::drop().name("role").to_owned()).await?;
+        Ok(())
+    }
+}
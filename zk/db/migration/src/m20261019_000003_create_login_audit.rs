@@ -0,0 +1,100 @@
+use sea_orm_migration::{ prelude::*, schema::* };
+use sea_orm::{ EnumIter, Iterable };
+use sea_orm_migration::prelude::extension::postgres::Type;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+pub enum LoginAudit {
+    Table,
+    Id,
+    Address,
+    Username,
+    Nonce,
+    Message, // signed EIP-4361 message
+    Signature,
+    Status,
+    Verified, // `verified` from the proof journal
+    Receipt, // zk receipt of the verify guest, once proven
+    Error,
+    CreatedAt,
+    ProvenAt,
+}
+
+#[derive(Iden)]
+pub enum LoginStatusEnumType {
+    #[iden = "login_status"]
+    LoginStatus,
+}
+
+#[derive(EnumIter)]
+pub enum LoginStatus {
+    Pending,
+    Proven,
+    Rejected,
+    Failed,
+}
+
+impl LoginStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            LoginStatus::Pending => "pending",
+            LoginStatus::Proven => "proven",
+            LoginStatus::Rejected => "rejected",
+            LoginStatus::Failed => "failed",
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.create_type(
+            Type::create()
+                .as_enum("login_status")
+                .values(LoginStatus::iter().map(|v| v.as_str()))
+                .to_owned()
+        ).await?;
+
+        manager.create_table(
+            Table::create()
+                .table(LoginAudit::Table)
+                .if_not_exists()
+                .col(pk_auto(LoginAudit::Id))
+                .col(string(LoginAudit::Address))
+                .col(string(LoginAudit::Username))
+                .col(string_uniq(LoginAudit::Nonce))
+                .col(text(LoginAudit::Message))
+                .col(string(LoginAudit::Signature))
+                .col(
+                    enumeration(
+                        LoginAudit::Status,
+                        LoginStatusEnumType::LoginStatus,
+                        LoginStatus::iter().map(|v| v.as_str())
+                    )
+                )
+                .col(boolean_null(LoginAudit::Verified))
+                .col(json_null(LoginAudit::Receipt))
+                .col(text_null(LoginAudit::Error))
+                .col(timestamp(LoginAudit::CreatedAt))
+                .col(timestamp_null(LoginAudit::ProvenAt))
+                .to_owned()
+        ).await?;
+
+        manager.create_index(
+            Index::create()
+                .name("idx_login_audit_address")
+                .table(LoginAudit::Table)
+                .col(LoginAudit::Address)
+                .to_owned()
+        ).await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_table(Table::drop().table(LoginAudit::Table).to_owned()).await?;
+        manager.drop_type(Type::drop().name("login_status").to_owned()).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,52 @@
+use sea_orm_migration::{ prelude::*, schema::* };
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+pub enum Bid {
+    Table,
+    BidderId,
+    Nonce, // EIP-712 bid nonce, unique per bidder
+    Deadline, // unix timestamp the signature was valid until
+    Signature, // EIP-712 signature of the bidder
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // nullable, bids placed before signed bids have none
+        manager.alter_table(
+            Table::alter()
+                .table(Bid::Table)
+                .add_column(big_integer_null(Bid::Nonce))
+                .add_column(big_integer_null(Bid::Deadline))
+                .add_column(string_null(Bid::Signature))
+                .to_owned()
+        ).await?;
+
+        manager.create_index(
+            Index::create()
+                .name("idx_bid_bidder_nonce")
+                .table(Bid::Table)
+                .col(Bid::BidderId)
+                .col(Bid::Nonce)
+                .unique()
+                .to_owned()
+        ).await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_index(Index::drop().name("idx_bid_bidder_nonce").table(Bid::Table).to_owned()).await?;
+        manager.alter_table(
+            Table::alter()
+                .table(Bid::Table)
+                .drop_column(Bid::Nonce)
+                .drop_column(Bid::Deadline)
+                .drop_column(Bid::Signature)
+                .to_owned()
+        ).await?;
+        Ok(())
+    }
+}
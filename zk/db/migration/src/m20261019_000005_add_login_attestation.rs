@@ -0,0 +1,74 @@
+use sea_orm_migration::{ prelude::*, schema::* };
+use sea_orm::{ EnumIter, Iterable };
+use sea_orm_migration::prelude::extension::postgres::Type;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+pub enum LoginAudit {
+    Table,
+    Attestation, // how the login was attested
+    BlockNumber, // block `isValidSignature` was called at, for EIP-1271 logins
+}
+
+#[derive(Iden)]
+pub enum LoginAttestationEnumType {
+    #[iden = "login_attestation"]
+    LoginAttestation,
+}
+
+#[derive(EnumIter)]
+pub enum LoginAttestation {
+    Zkvm,
+    Eip1271,
+}
+
+impl LoginAttestation {
+    fn as_str(&self) -> &'static str {
+        match self {
+            LoginAttestation::Zkvm => "zkvm",
+            LoginAttestation::Eip1271 => "eip1271",
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.create_type(
+            Type::create()
+                .as_enum("login_attestation")
+                .values(LoginAttestation::iter().map(|v| v.as_str()))
+                .to_owned()
+        ).await?;
+
+        // existing records were all proven by the verify guest
+        manager.alter_table(
+            Table::alter()
+                .table(LoginAudit::Table)
+                .add_column(
+                    enumeration(
+                        LoginAudit::Attestation,
+                        LoginAttestationEnumType::LoginAttestation,
+                        LoginAttestation::iter().map(|v| v.as_str())
+                    ).default("zkvm")
+                )
+                .add_column(big_integer_null(LoginAudit::BlockNumber))
+                .to_owned()
+        ).await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.alter_table(
+            Table::alter()
+                .table(LoginAudit::Table)
+                .drop_column(LoginAudit::Attestation)
+                .drop_column(LoginAudit::BlockNumber)
+                .to_owned()
+        ).await?;
+        manager.drop_type(Type::drop().name("login_attestation").to_owned()).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,40 @@
+use sea_orm_migration::{ prelude::*, schema::* };
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+pub enum Auction {
+    Table,
+    Winner, // address of the winning bidder, set by settlement
+    WinningBidId, // bid that won the auction
+    SettledAt,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // nullable, only settled auctions have a winner
+        manager.alter_table(
+            Table::alter()
+                .table(Auction::Table)
+                .add_column(string_null(Auction::Winner))
+                .add_column(integer_null(Auction::WinningBidId))
+                .add_column(timestamp_null(Auction::SettledAt))
+                .to_owned()
+        ).await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.alter_table(
+            Table::alter()
+                .table(Auction::Table)
+                .drop_column(Auction::Winner)
+                .drop_column(Auction::WinningBidId)
+                .drop_column(Auction::SettledAt)
+                .to_owned()
+        ).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,123 @@
+use sea_orm_migration::{ prelude::*, schema::* };
+use sea_orm::{ EnumIter, Iterable };
+use sea_orm_migration::prelude::extension::postgres::Type;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+#[allow(clippy::enum_variant_names)]
+pub enum Auction {
+    Table,
+    Id,
+    AuctionType,
+    RevealEndTime, // sealed auctions take reveals from `end_time` until this
+}
+
+#[derive(DeriveIden)]
+pub enum SealedBid {
+    Table,
+    Id,
+    AuctionId,
+    Bidder,
+    Commitment, // keccak256(abi.encodePacked(uint256 amount, bytes32 salt))
+    Amount, // revealed amount, never served by the api
+    Salt,
+    RevealedAt,
+    CreatedAt,
+}
+
+#[derive(Iden)]
+pub enum AuctionTypeEnumType {
+    #[iden = "auction_type"]
+    AuctionType,
+}
+
+#[derive(EnumIter)]
+pub enum AuctionType {
+    English,
+    Sealed,
+}
+
+impl AuctionType {
+    fn as_str(&self) -> &'static str {
+        match self {
+            AuctionType::English => "english",
+            AuctionType::Sealed => "sealed",
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.create_type(
+            Type::create()
+                .as_enum("auction_type")
+                .values(AuctionType::iter().map(|v| v.as_str()))
+                .to_owned()
+        ).await?;
+
+        // existing auctions are all english
+        manager.alter_table(
+            Table::alter()
+                .table(Auction::Table)
+                .add_column(
+                    enumeration_null(
+                        Auction::AuctionType,
+                        AuctionTypeEnumType::AuctionType,
+                        AuctionType::iter().map(|v| v.as_str())
+                    ).default("english")
+                )
+                .add_column(timestamp_null(Auction::RevealEndTime))
+                .to_owned()
+        ).await?;
+
+        manager.create_table(
+            Table::create()
+                .table(SealedBid::Table)
+                .if_not_exists()
+                .col(pk_auto(SealedBid::Id))
+                .col(integer(SealedBid::AuctionId))
+                .col(string(SealedBid::Bidder))
+                .col(string(SealedBid::Commitment))
+                .col(integer_null(SealedBid::Amount))
+                .col(string_null(SealedBid::Salt))
+                .col(timestamp_null(SealedBid::RevealedAt))
+                .col(timestamp(SealedBid::CreatedAt))
+                .foreign_key(
+                    ForeignKey::create()
+                        .name("fk_sealed_bid_auction_id")
+                        .from(SealedBid::Table, SealedBid::AuctionId)
+                        .to(Auction::Table, Auction::Id)
+                        .on_delete(ForeignKeyAction::Cascade)
+                )
+                .to_owned()
+        ).await?;
+
+        // one commitment per bidder and auction
+        manager.create_index(
+            Index::create()
+                .name("idx_sealed_bid_auction_bidder")
+                .table(SealedBid::Table)
+                .col(SealedBid::AuctionId)
+                .col(SealedBid::Bidder)
+                .unique()
+                .to_owned()
+        ).await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_table(Table::drop().table(SealedBid::Table).to_owned()).await?;
+        manager.alter_table(
+            Table::alter()
+                .table(Auction::Table)
+                .drop_column(Auction::AuctionType)
+                .drop_column(Auction::RevealEndTime)
+                .to_owned()
+        ).await?;
+        manager.drop_type(Type::drop().name("auction_type").to_owned()).await?;
+        Ok(())
+    }
+}
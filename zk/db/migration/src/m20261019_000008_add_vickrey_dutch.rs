@@ -0,0 +1,38 @@
+use sea_orm_migration::{ prelude::*, schema::* };
+use sea_orm_migration::prelude::extension::postgres::Type;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+pub enum Auction {
+    Table,
+    FloorPrice, // price a dutch auction falls to at `end_time`
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for value in ["vickrey", "dutch"] {
+            manager.alter_type(
+                Type::alter().name(Alias::new("auction_type")).add_value(Alias::new(value)).to_owned()
+            ).await?;
+        }
+
+        manager.alter_table(
+            Table::alter()
+                .table(Auction::Table)
+                .add_column(integer_null(Auction::FloorPrice))
+                .to_owned()
+        ).await?;
+        Ok(())
+    }
+
+    // postgres cannot drop enum values, so `vickrey` and `dutch` stay in `auction_type`
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.alter_table(
+            Table::alter().table(Auction::Table).drop_column(Auction::FloorPrice).to_owned()
+        ).await?;
+        Ok(())
+    }
+}
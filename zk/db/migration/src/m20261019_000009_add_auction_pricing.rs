@@ -0,0 +1,85 @@
+use sea_orm_migration::{ prelude::*, schema::* };
+use sea_orm::{ EnumIter, Iterable };
+use sea_orm_migration::prelude::extension::postgres::Type;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+pub enum Auction {
+    Table,
+    ReservePrice, // lowest winning amount the seller accepts
+    BuyNowPrice, // a bid at or above this closes the auction
+    MinIncrement, // overrides BID_MIN_INCREMENT for this auction
+    Outcome, // set by settlement
+    RelistedFrom, // auction this one relists after the reserve was not met
+}
+
+#[derive(Iden)]
+pub enum AuctionOutcomeEnumType {
+    #[iden = "auction_outcome"]
+    AuctionOutcome,
+}
+
+#[derive(EnumIter)]
+pub enum AuctionOutcome {
+    Sold,
+    NoBids,
+    ReserveNotMet,
+}
+
+impl AuctionOutcome {
+    fn as_str(&self) -> &'static str {
+        match self {
+            AuctionOutcome::Sold => "sold",
+            AuctionOutcome::NoBids => "no_bids",
+            AuctionOutcome::ReserveNotMet => "reserve_not_met",
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.create_type(
+            Type::create()
+                .as_enum("auction_outcome")
+                .values(AuctionOutcome::iter().map(|v| v.as_str()))
+                .to_owned()
+        ).await?;
+
+        // all nullable, existing auctions keep the english rules and have no outcome yet
+        manager.alter_table(
+            Table::alter()
+                .table(Auction::Table)
+                .add_column(integer_null(Auction::ReservePrice))
+                .add_column(integer_null(Auction::BuyNowPrice))
+                .add_column(integer_null(Auction::MinIncrement))
+                .add_column(
+                    enumeration_null(
+                        Auction::Outcome,
+                        AuctionOutcomeEnumType::AuctionOutcome,
+                        AuctionOutcome::iter().map(|v| v.as_str())
+                    )
+                )
+                .add_column(integer_null(Auction::RelistedFrom))
+                .to_owned()
+        ).await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.alter_table(
+            Table::alter()
+                .table(Auction::Table)
+                .drop_column(Auction::ReservePrice)
+                .drop_column(Auction::BuyNowPrice)
+                .drop_column(Auction::MinIncrement)
+                .drop_column(Auction::Outcome)
+                .drop_column(Auction::RelistedFrom)
+                .to_owned()
+        ).await?;
+        manager.drop_type(Type::drop().name("auction_outcome").to_owned()).await?;
+        Ok(())
+    }
+}
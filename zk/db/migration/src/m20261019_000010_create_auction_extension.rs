@@ -0,0 +1,79 @@
+use sea_orm_migration::{ prelude::*, schema::* };
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+pub enum Auction {
+    Table,
+    Id,
+    SoftCloseWindow, // seconds before `end_time` in which a bid extends the auction
+    SoftCloseExtension, // seconds each such bid adds to `end_time`
+    MaxEndTime, // `end_time` is never extended past this
+}
+
+#[derive(DeriveIden)]
+pub enum AuctionExtension {
+    Table,
+    Id,
+    AuctionId,
+    BidId, // bid that landed in the soft-close window
+    OldEndTime,
+    NewEndTime,
+    OldAuctionRoot, // auction Merkle roots the create_bid guest moved between
+    NewAuctionRoot,
+    Receipt, // create_bid receipt proving the extension
+    CreatedAt,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // nullable, auctions without the rules never extend
+        manager.alter_table(
+            Table::alter()
+                .table(Auction::Table)
+                .add_column(integer_null(Auction::SoftCloseWindow))
+                .add_column(integer_null(Auction::SoftCloseExtension))
+                .add_column(timestamp_null(Auction::MaxEndTime))
+                .to_owned()
+        ).await?;
+
+        manager.create_table(
+            Table::create()
+                .table(AuctionExtension::Table)
+                .if_not_exists()
+                .col(pk_auto(AuctionExtension::Id))
+                .col(integer(AuctionExtension::AuctionId))
+                .col(integer(AuctionExtension::BidId))
+                .col(timestamp(AuctionExtension::OldEndTime))
+                .col(timestamp(AuctionExtension::NewEndTime))
+                .col(string(AuctionExtension::OldAuctionRoot))
+                .col(string(AuctionExtension::NewAuctionRoot))
+                .col(json_null(AuctionExtension::Receipt))
+                .col(timestamp(AuctionExtension::CreatedAt))
+                .foreign_key(
+                    ForeignKey::create()
+                        .name("fk_auction_extension_auction_id")
+                        .from(AuctionExtension::Table, AuctionExtension::AuctionId)
+                        .to(Auction::Table, Auction::Id)
+                        .on_delete(ForeignKeyAction::Cascade)
+                )
+                .to_owned()
+        ).await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_table(Table::drop().table(AuctionExtension::Table).to_owned()).await?;
+        manager.alter_table(
+            Table::alter()
+                .table(Auction::Table)
+                .drop_column(Auction::SoftCloseWindow)
+                .drop_column(Auction::SoftCloseExtension)
+                .drop_column(Auction::MaxEndTime)
+                .to_owned()
+        ).await?;
+        Ok(())
+    }
+}
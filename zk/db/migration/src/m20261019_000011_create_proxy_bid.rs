@@ -0,0 +1,88 @@
+use sea_orm_migration::{ prelude::*, schema::* };
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+pub enum Auction {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+pub enum Bid {
+    Table,
+    Proxy, // placed by the proxy engine on the bidder's behalf
+}
+
+#[derive(DeriveIden)]
+pub enum ProxyBid {
+    Table,
+    Id,
+    AuctionId,
+    Bidder,
+    MaxAmount, // private cap, never served by the api
+    Nonce, // EIP-712 authorization of `MaxAmount`
+    Deadline,
+    Signature,
+    Active,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // existing bids were all placed by hand
+        manager.alter_table(
+            Table::alter()
+                .table(Bid::Table)
+                .add_column(boolean(Bid::Proxy).default(false))
+                .to_owned()
+        ).await?;
+
+        manager.create_table(
+            Table::create()
+                .table(ProxyBid::Table)
+                .if_not_exists()
+                .col(pk_auto(ProxyBid::Id))
+                .col(integer(ProxyBid::AuctionId))
+                .col(string(ProxyBid::Bidder))
+                .col(integer(ProxyBid::MaxAmount))
+                .col(big_integer(ProxyBid::Nonce))
+                .col(big_integer(ProxyBid::Deadline))
+                .col(string(ProxyBid::Signature))
+                .col(boolean(ProxyBid::Active).default(true))
+                .col(timestamp(ProxyBid::CreatedAt))
+                .col(timestamp(ProxyBid::UpdatedAt))
+                .foreign_key(
+                    ForeignKey::create()
+                        .name("fk_proxy_bid_auction_id")
+                        .from(ProxyBid::Table, ProxyBid::AuctionId)
+                        .to(Auction::Table, Auction::Id)
+                        .on_delete(ForeignKeyAction::Cascade)
+                )
+                .to_owned()
+        ).await?;
+
+        // one cap per bidder and auction; raising it replaces the row
+        manager.create_index(
+            Index::create()
+                .name("idx_proxy_bid_auction_bidder")
+                .table(ProxyBid::Table)
+                .col(ProxyBid::AuctionId)
+                .col(ProxyBid::Bidder)
+                .unique()
+                .to_owned()
+        ).await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_table(Table::drop().table(ProxyBid::Table).to_owned()).await?;
+        manager.alter_table(
+            Table::alter().table(Bid::Table).drop_column(Bid::Proxy).to_owned()
+        ).await?;
+        Ok(())
+    }
+}
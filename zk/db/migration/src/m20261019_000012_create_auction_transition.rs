@@ -0,0 +1,75 @@
+use sea_orm_migration::{ prelude::*, schema::* };
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+pub enum Auction {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+pub enum AuctionTransition {
+    Table,
+    Id,
+    AuctionId,
+    FromStatus,
+    ToStatus,
+    Actor, // who moved the auction, SYSTEM for the scheduler
+    Sequence, // state commit queued with the transition
+    Cid, // that commit's cid, filled in once it is proven
+    CreatedAt,
+}
+
+#[derive(Iden)]
+pub enum StatusEnumType {
+    #[iden = "status"]
+    Status,
+}
+
+const STATUSES: [&str; 4] = ["pending", "active", "completed", "cancelled"];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.create_table(
+            Table::create()
+                .table(AuctionTransition::Table)
+                .if_not_exists()
+                .col(pk_auto(AuctionTransition::Id))
+                .col(integer(AuctionTransition::AuctionId))
+                .col(enumeration_null(AuctionTransition::FromStatus, StatusEnumType::Status, STATUSES))
+                .col(enumeration(AuctionTransition::ToStatus, StatusEnumType::Status, STATUSES))
+                .col(string(AuctionTransition::Actor))
+                .col(big_integer_null(AuctionTransition::Sequence))
+                .col(string_null(AuctionTransition::Cid))
+                .col(timestamp(AuctionTransition::CreatedAt))
+                .foreign_key(
+                    ForeignKey::create()
+                        .name("fk_auction_transition_auction_id")
+                        .from(AuctionTransition::Table, AuctionTransition::AuctionId)
+                        .to(Auction::Table, Auction::Id)
+                        .on_delete(ForeignKeyAction::Cascade)
+                )
+                .to_owned()
+        ).await?;
+
+        // an auction enters each status once, so a replayed transition is not recorded twice
+        manager.create_index(
+            Index::create()
+                .name("idx_auction_transition_auction_to_status")
+                .table(AuctionTransition::Table)
+                .col(AuctionTransition::AuctionId)
+                .col(AuctionTransition::ToStatus)
+                .unique()
+                .to_owned()
+        ).await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_table(Table::drop().table(AuctionTransition::Table).to_owned()).await?;
+        Ok(())
+    }
+}
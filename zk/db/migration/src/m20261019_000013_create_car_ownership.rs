@@ -0,0 +1,72 @@
+use sea_orm_migration::{ prelude::*, schema::* };
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+pub enum Car {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+pub enum CarOwnership {
+    Table,
+    Id,
+    CarId,
+    FromOwner,
+    ToOwner,
+    Action, // BUY when an auction sold the car, SELL when the owner sold it
+    AuctionId, // auction that sold the car, if any
+    Amount,
+    OldCarRoot, // car Merkle roots the transfer_car guest moved between
+    NewCarRoot,
+    Receipt, // transfer_car receipt proving the transfer
+    CreatedAt,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.create_table(
+            Table::create()
+                .table(CarOwnership::Table)
+                .if_not_exists()
+                .col(pk_auto(CarOwnership::Id))
+                .col(integer(CarOwnership::CarId))
+                .col(string(CarOwnership::FromOwner))
+                .col(string(CarOwnership::ToOwner))
+                .col(string(CarOwnership::Action))
+                .col(integer_null(CarOwnership::AuctionId))
+                .col(integer(CarOwnership::Amount))
+                .col(string(CarOwnership::OldCarRoot))
+                .col(string(CarOwnership::NewCarRoot))
+                .col(json_null(CarOwnership::Receipt))
+                .col(timestamp(CarOwnership::CreatedAt))
+                .foreign_key(
+                    ForeignKey::create()
+                        .name("fk_car_ownership_car_id")
+                        .from(CarOwnership::Table, CarOwnership::CarId)
+                        .to(Car::Table, Car::Id)
+                        .on_delete(ForeignKeyAction::Cascade)
+                )
+                .to_owned()
+        ).await?;
+
+        // an auction transfers its car once
+        manager.create_index(
+            Index::create()
+                .name("idx_car_ownership_auction_id")
+                .table(CarOwnership::Table)
+                .col(CarOwnership::AuctionId)
+                .unique()
+                .to_owned()
+        ).await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_table(Table::drop().table(CarOwnership::Table).to_owned()).await?;
+        Ok(())
+    }
+}
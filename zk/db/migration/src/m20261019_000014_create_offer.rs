@@ -0,0 +1,121 @@
+use sea_orm_migration::{ prelude::*, schema::* };
+use sea_orm::{ EnumIter, Iterable };
+use sea_orm_migration::prelude::extension::postgres::Type;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+pub enum Car {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+pub enum CarOwnership {
+    Table,
+    OfferId, // offer that sold the car, if any
+}
+
+#[derive(DeriveIden)]
+pub enum Offer {
+    Table,
+    Id,
+    CarId,
+    Buyer,
+    Seller, // the car's owner when the offer was made
+    Proposer, // buyer for an offer, seller for a counter to it
+    Amount,
+    ExpiresAt,
+    Status,
+    CounterOf, // offer this one counters
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(Iden)]
+pub enum OfferStatusEnumType {
+    #[iden = "offer_status"]
+    OfferStatus,
+}
+
+#[derive(EnumIter)]
+pub enum OfferStatus {
+    Pending,
+    Accepted,
+    Rejected,
+    Countered,
+    Expired,
+}
+
+impl OfferStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            OfferStatus::Pending => "pending",
+            OfferStatus::Accepted => "accepted",
+            OfferStatus::Rejected => "rejected",
+            OfferStatus::Countered => "countered",
+            OfferStatus::Expired => "expired",
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.create_type(
+            Type::create()
+                .as_enum("offer_status")
+                .values(OfferStatus::iter().map(|v| v.as_str()))
+                .to_owned()
+        ).await?;
+        manager.create_table(
+            Table::create()
+                .table(Offer::Table)
+                .if_not_exists()
+                .col(pk_auto(Offer::Id))
+                .col(integer(Offer::CarId))
+                .col(string(Offer::Buyer))
+                .col(string(Offer::Seller))
+                .col(string(Offer::Proposer))
+                .col(integer(Offer::Amount))
+                .col(timestamp(Offer::ExpiresAt))
+                .col(
+                    enumeration(
+                        Offer::Status,
+                        OfferStatusEnumType::OfferStatus,
+                        OfferStatus::iter().map(|v| v.as_str())
+                    )
+                )
+                .col(integer_null(Offer::CounterOf))
+                .col(timestamp(Offer::CreatedAt))
+                .col(timestamp(Offer::UpdatedAt))
+                .foreign_key(
+                    ForeignKey::create()
+                        .name("fk_offer_car_id")
+                        .from(Offer::Table, Offer::CarId)
+                        .to(Car::Table, Car::Id)
+                        .on_delete(ForeignKeyAction::Cascade)
+                )
+                .to_owned()
+        ).await?;
+
+        // an offer transfers its car once
+        manager.alter_table(
+            Table::alter()
+                .table(CarOwnership::Table)
+                .add_column(integer_null(CarOwnership::OfferId).unique_key())
+                .to_owned()
+        ).await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.alter_table(
+            Table::alter().table(CarOwnership::Table).drop_column(CarOwnership::OfferId).to_owned()
+        ).await?;
+        manager.drop_table(Table::drop().table(Offer::Table).to_owned()).await?;
+        manager.drop_type(Type::drop().name("offer_status").to_owned()).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,66 @@
+use sea_orm_migration::{ prelude::*, schema::* };
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+pub enum Bid {
+    Table,
+    RetractedAt, // set when the bidder retracts the bid, the row is kept
+    RetractionReason,
+}
+
+#[derive(DeriveIden)]
+pub enum Auction {
+    Table,
+    OpeningPrice, // `current_bid` falls back to this once every bid is retracted
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.alter_table(
+            Table::alter()
+                .table(Bid::Table)
+                .add_column(timestamp_null(Bid::RetractedAt))
+                .add_column(text_null(Bid::RetractionReason))
+                .to_owned()
+        ).await?;
+
+        // auctions without bids still hold their opening price in `current_bid`; the others
+        // fall back to their car's starting price
+        manager.alter_table(
+            Table::alter()
+                .table(Auction::Table)
+                .add_column(integer_null(Auction::OpeningPrice))
+                .to_owned()
+        ).await?;
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "UPDATE auction SET opening_price = CASE WHEN bid_count = 0 THEN current_bid \
+                 ELSE (SELECT starting_price FROM car WHERE car.id = auction.car_id) END"
+            ).await?;
+        manager.alter_table(
+            Table::alter()
+                .table(Auction::Table)
+                .modify_column(ColumnDef::new(Auction::OpeningPrice).integer().not_null())
+                .to_owned()
+        ).await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.alter_table(
+            Table::alter().table(Auction::Table).drop_column(Auction::OpeningPrice).to_owned()
+        ).await?;
+        manager.alter_table(
+            Table::alter()
+                .table(Bid::Table)
+                .drop_column(Bid::RetractedAt)
+                .drop_column(Bid::RetractionReason)
+                .to_owned()
+        ).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,32 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// Tables whose rows were inserted with ids picked by the host or the seeder.
+const TABLES: [&str; 5] = ["car", "auction", "bid", "comment", "saved_auction"];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // explicit ids never advanced the serial sequences; move each past the highest id so
+        // the database can hand them out from now on
+        for table in TABLES {
+            manager
+                .get_connection()
+                .execute_unprepared(
+                    &format!(
+                        "SELECT setval(pg_get_serial_sequence('{0}', 'id'), \
+                         COALESCE(MAX(id), 0) + 1, false) FROM {0}",
+                        table
+                    )
+                ).await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        // the sequences only moved forward, nothing to undo
+        Ok(())
+    }
+}
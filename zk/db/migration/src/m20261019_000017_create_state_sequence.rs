@@ -0,0 +1,107 @@
+use sea_orm_migration::{ prelude::*, schema::* };
+use sea_orm::{ EnumIter, Iterable };
+use sea_orm_migration::prelude::extension::postgres::Type;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+pub enum StateSequence {
+    Table,
+    Sequence, // order the state commits are proven in
+    Actor, // `Actor` the commit is made under, as json
+    Action, // `StateAction` the commit records, if any
+    Leaves, // car, auction and bid leaves snapshotted when the commit was queued
+    Status,
+    OldRoot, // new root of the previous proven commit
+    NewRoot,
+    Cid,
+    Receipt,
+    Stats,
+    Error, // why proving failed
+    CreatedAt,
+    ProvenAt,
+}
+
+#[derive(Iden)]
+pub enum SequenceStatusEnumType {
+    #[iden = "sequence_status"]
+    SequenceStatus,
+}
+
+#[derive(EnumIter)]
+pub enum SequenceStatus {
+    Pending,
+    Proven,
+    Failed,
+}
+
+impl SequenceStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            SequenceStatus::Pending => "pending",
+            SequenceStatus::Proven => "proven",
+            SequenceStatus::Failed => "failed",
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.create_type(
+            Type::create()
+                .as_enum("sequence_status")
+                .values(SequenceStatus::iter().map(|v| v.as_str()))
+                .to_owned()
+        ).await?;
+        manager.create_table(
+            Table::create()
+                .table(StateSequence::Table)
+                .if_not_exists()
+                .col(
+                    ColumnDef::new(StateSequence::Sequence)
+                        .big_integer()
+                        .not_null()
+                        .auto_increment()
+                        .primary_key()
+                )
+                .col(json(StateSequence::Actor))
+                .col(json_null(StateSequence::Action))
+                .col(json(StateSequence::Leaves))
+                .col(
+                    enumeration(
+                        StateSequence::Status,
+                        SequenceStatusEnumType::SequenceStatus,
+                        SequenceStatus::iter().map(|v| v.as_str())
+                    )
+                )
+                .col(string_null(StateSequence::OldRoot))
+                .col(string_null(StateSequence::NewRoot))
+                .col(string_null(StateSequence::Cid))
+                .col(json_null(StateSequence::Receipt))
+                .col(json_null(StateSequence::Stats))
+                .col(text_null(StateSequence::Error))
+                .col(timestamp(StateSequence::CreatedAt))
+                .col(timestamp_null(StateSequence::ProvenAt))
+                .to_owned()
+        ).await?;
+
+        // the sequencer looks up the next pending commit and the last proven one
+        manager.create_index(
+            Index::create()
+                .name("idx_state_sequence_status")
+                .table(StateSequence::Table)
+                .col(StateSequence::Status)
+                .col(StateSequence::Sequence)
+                .to_owned()
+        ).await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_table(Table::drop().table(StateSequence::Table).to_owned()).await?;
+        manager.drop_type(Type::drop().name("sequence_status").to_owned()).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,61 @@
+use sea_orm_migration::{ prelude::*, schema::* };
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+pub enum IdempotencyKey {
+    Table,
+    Id,
+    Address, // caller the key belongs to, keys are only unique per caller
+    Key,
+    Method,
+    Path,
+    RequestHash, // sha256 of the method, path and body the key was first used with
+    ResponseStatus, // null while the first request is still running
+    LockedUntil, // lease of the running request, renewed while it runs; a lapsed one is taken over
+    ResponseBody,
+    ContentType,
+    CreatedAt,
+    CompletedAt,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.create_table(
+            Table::create()
+                .table(IdempotencyKey::Table)
+                .if_not_exists()
+                .col(pk_auto(IdempotencyKey::Id))
+                .col(string(IdempotencyKey::Address))
+                .col(string(IdempotencyKey::Key))
+                .col(string(IdempotencyKey::Method))
+                .col(string(IdempotencyKey::Path))
+                .col(string(IdempotencyKey::RequestHash))
+                .col(integer_null(IdempotencyKey::ResponseStatus))
+                .col(timestamp_null(IdempotencyKey::LockedUntil))
+                .col(text_null(IdempotencyKey::ResponseBody))
+                .col(string_null(IdempotencyKey::ContentType))
+                .col(timestamp(IdempotencyKey::CreatedAt))
+                .col(timestamp_null(IdempotencyKey::CompletedAt))
+                .to_owned()
+        ).await?;
+
+        manager.create_index(
+            Index::create()
+                .name("idx_idempotency_key_address_key")
+                .table(IdempotencyKey::Table)
+                .col(IdempotencyKey::Address)
+                .col(IdempotencyKey::Key)
+                .unique()
+                .to_owned()
+        ).await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_table(Table::drop().table(IdempotencyKey::Table).to_owned()).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,34 @@
+use sea_orm_migration::{ prelude::*, schema::* };
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+pub enum Auction {
+    Table,
+    SettlementReceipt, // settle_auction or settle_sealed receipt the BUY transfer verifies
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // nullable, only settled auctions have one
+        manager.alter_table(
+            Table::alter()
+                .table(Auction::Table)
+                .add_column(json_null(Auction::SettlementReceipt))
+                .to_owned()
+        ).await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.alter_table(
+            Table::alter()
+                .table(Auction::Table)
+                .drop_column(Auction::SettlementReceipt)
+                .to_owned()
+        ).await?;
+        Ok(())
+    }
+}
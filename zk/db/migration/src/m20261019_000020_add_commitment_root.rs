@@ -0,0 +1,34 @@
+use sea_orm_migration::{ prelude::*, schema::* };
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+pub enum Auction {
+    Table,
+    CommitmentRoot, // root over a sealed auction's commitments, in its auction leaf
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // nullable, set by a sealed auction's first commitment
+        manager.alter_table(
+            Table::alter()
+                .table(Auction::Table)
+                .add_column(string_null(Auction::CommitmentRoot))
+                .to_owned()
+        ).await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.alter_table(
+            Table::alter()
+                .table(Auction::Table)
+                .drop_column(Auction::CommitmentRoot)
+                .to_owned()
+        ).await?;
+        Ok(())
+    }
+}
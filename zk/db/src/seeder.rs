@@ -1,6 +1,5 @@
 use ::entity::{ auction, bid, car, comment, saved_auction, Status };
 use sea_orm::*;
-use serde_json::json;
 use chrono::{ DateTime, Utc };
 // use entity::{ car, auction, bid, comment, saved_auction, sea_orm_active_enums::Status };
 use sea_orm::ActiveValue::Set;
@@ -76,6 +75,10 @@ pub async fn seed_database(db: &DatabaseConnection) -> Result<(), DbErr> {
     update_auction_stats(db).await?;
     println!("✅ Updated auction statistics");
 
+    // Seeded ids are explicit, so move the sequences past them
+    sync_id_sequences(db).await?;
+    println!("✅ Synced id sequences");
+
     println!("🎉 Database seeding completed successfully!");
     Ok(())
 }
@@ -145,7 +148,6 @@ pub async fn seed_cars_from_json(db: &DatabaseConnection, path: &str) -> Result<
                     .with_timezone(&Utc)
                     .naive_utc()
             ),
-            ..Default::default()
         };
         model.insert(db).await?;
     }
@@ -171,6 +173,7 @@ async fn seed_auctions(db: &DatabaseConnection) -> Result<Vec<auction::Model>, D
             ),
             current_bid: Set(215000),
             bid_count: Set(5),
+            opening_price: Set(195000),
             seller: Set("0x123abc456def789ghi".to_string()),
             status: Set(Some(Status::Active)),
             created_at: Set(
@@ -303,6 +306,7 @@ async fn seed_auctions(db: &DatabaseConnection) -> Result<Vec<auction::Model>, D
             ),
             current_bid: Set(125000),
             bid_count: Set(4),
+            opening_price: Set(115000),
             seller: Set("0xdef456ghi789abc123".to_string()),
             status: Set(Some(Status::Active)),
             created_at: Set(
@@ -766,7 +770,6 @@ async fn seed_comments(db: &DatabaseConnection) -> Result<Vec<comment::Model>, D
                     .with_timezone(&Utc)
                     .naive_utc()
             ),
-            ..Default::default()
         },
         comment::ActiveModel {
             id: Set(2),
@@ -785,7 +788,6 @@ async fn seed_comments(db: &DatabaseConnection) -> Result<Vec<comment::Model>, D
                     .with_timezone(&Utc)
                     .naive_utc()
             ),
-            ..Default::default()
         },
         comment::ActiveModel {
             id: Set(3),
@@ -804,7 +806,6 @@ async fn seed_comments(db: &DatabaseConnection) -> Result<Vec<comment::Model>, D
                     .with_timezone(&Utc)
                     .naive_utc()
             ),
-            ..Default::default()
         },
         comment::ActiveModel {
             id: Set(4),
@@ -823,7 +824,6 @@ async fn seed_comments(db: &DatabaseConnection) -> Result<Vec<comment::Model>, D
                     .with_timezone(&Utc)
                     .naive_utc()
             ),
-            ..Default::default()
         },
         comment::ActiveModel {
             id: Set(5),
@@ -842,7 +842,6 @@ async fn seed_comments(db: &DatabaseConnection) -> Result<Vec<comment::Model>, D
                     .with_timezone(&Utc)
                     .naive_utc()
             ),
-            ..Default::default()
         },
         comment::ActiveModel {
             id: Set(6),
@@ -861,7 +860,6 @@ async fn seed_comments(db: &DatabaseConnection) -> Result<Vec<comment::Model>, D
                     .with_timezone(&Utc)
                     .naive_utc()
             ),
-            ..Default::default()
         }
     ];
 
@@ -886,7 +884,6 @@ async fn seed_saved_auctions(db: &DatabaseConnection) -> Result<Vec<saved_auctio
                     .with_timezone(&Utc)
                     .naive_utc()
             ),
-            ..Default::default()
         },
         saved_auction::ActiveModel {
             id: Set(2),
@@ -898,7 +895,6 @@ async fn seed_saved_auctions(db: &DatabaseConnection) -> Result<Vec<saved_auctio
                     .with_timezone(&Utc)
                     .naive_utc()
             ),
-            ..Default::default()
         },
         saved_auction::ActiveModel {
             id: Set(3),
@@ -910,7 +906,6 @@ async fn seed_saved_auctions(db: &DatabaseConnection) -> Result<Vec<saved_auctio
                     .with_timezone(&Utc)
                     .naive_utc()
             ),
-            ..Default::default()
         },
         saved_auction::ActiveModel {
             id: Set(4),
@@ -922,7 +917,6 @@ async fn seed_saved_auctions(db: &DatabaseConnection) -> Result<Vec<saved_auctio
                     .with_timezone(&Utc)
                     .naive_utc()
             ),
-            ..Default::default()
         },
         saved_auction::ActiveModel {
             id: Set(5),
@@ -934,7 +928,6 @@ async fn seed_saved_auctions(db: &DatabaseConnection) -> Result<Vec<saved_auctio
                     .with_timezone(&Utc)
                     .naive_utc()
             ),
-            ..Default::default()
         },
         saved_auction::ActiveModel {
             id: Set(6),
@@ -946,7 +939,6 @@ async fn seed_saved_auctions(db: &DatabaseConnection) -> Result<Vec<saved_auctio
                     .with_timezone(&Utc)
                     .naive_utc()
             ),
-            ..Default::default()
         },
         saved_auction::ActiveModel {
             id: Set(7),
@@ -958,7 +950,6 @@ async fn seed_saved_auctions(db: &DatabaseConnection) -> Result<Vec<saved_auctio
                     .with_timezone(&Utc)
                     .naive_utc()
             ),
-            ..Default::default()
         },
         saved_auction::ActiveModel {
             id: Set(8),
@@ -970,7 +961,6 @@ async fn seed_saved_auctions(db: &DatabaseConnection) -> Result<Vec<saved_auctio
                     .with_timezone(&Utc)
                     .naive_utc()
             ),
-            ..Default::default()
         },
         saved_auction::ActiveModel {
             id: Set(9),
@@ -982,7 +972,6 @@ async fn seed_saved_auctions(db: &DatabaseConnection) -> Result<Vec<saved_auctio
                     .with_timezone(&Utc)
                     .naive_utc()
             ),
-            ..Default::default()
         },
         saved_auction::ActiveModel {
             id: Set(10),
@@ -994,7 +983,6 @@ async fn seed_saved_auctions(db: &DatabaseConnection) -> Result<Vec<saved_auctio
                     .with_timezone(&Utc)
                     .naive_utc()
             ),
-            ..Default::default()
         }
     ];
 
@@ -1024,7 +1012,7 @@ async fn update_auction_stats(db: &DatabaseConnection) -> Result<(), DbErr> {
             .limit(1)
             .one(db).await?;
 
-        let current_bid = max_bid.map(|bid| bid.amount).unwrap_or(0);
+        let current_bid = max_bid.map(|bid| bid.amount).unwrap_or(auction.opening_price);
 
         (auction::ActiveModel {
             id: Set(auction.id),
@@ -1036,3 +1024,16 @@ async fn update_auction_stats(db: &DatabaseConnection) -> Result<(), DbErr> {
 
     Ok(())
 }
+
+async fn sync_id_sequences(db: &DatabaseConnection) -> Result<(), DbErr> {
+    for table in ["car", "auction", "bid", "comment", "saved_auction"] {
+        db.execute_unprepared(
+            &format!(
+                "SELECT setval(pg_get_serial_sequence('{0}', 'id'), \
+                 COALESCE(MAX(id), 0) + 1, false) FROM {0}",
+                table
+            )
+        ).await?;
+    }
+    Ok(())
+}
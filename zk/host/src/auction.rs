@@ -1,43 +1,216 @@
 use std::sync::Arc;
 
 use axum::Json;
-use car_auction_core::AuctionState;
+use car_auction_core::{
+    merkle::{ MerklePath, RootTransition },
+    AuctionAction,
+    AuctionLeaf,
+    AuctionState,
+    AuctionStatus,
+    StateAction,
+};
 use chrono::Utc;
 // use db::auction::get_all_auctions;
-use entity::{ auction, bid, car, AuctionModel };
+use entity::{
+    auction,
+    auction_extension,
+    auction_transition,
+    car,
+    proxy_bid,
+    sealed_bid,
+    AuctionModel,
+    AuctionOutcome,
+    AuctionType,
+    CarModel,
+    Role,
+    Status,
+};
 use methods::{ INIT_AUCTION_ELF, INIT_AUCTION_ID };
 use risc0_zkvm::{ default_prover, ExecutorEnv, Receipt };
 use sea_orm::{
     ActiveModelTrait,
-    ActiveValue::Set,
+    ActiveValue::{ NotSet, Set },
+    ColumnTrait,
+    ConnectionTrait,
     DatabaseConnection,
+    DatabaseTransaction,
     DbErr,
     EntityTrait,
+    PaginatorTrait,
     QueryFilter,
     QueryOrder,
+    QuerySelect,
+    TransactionTrait,
 };
 use serde::{ Deserialize, Serialize };
 use serde_json::{ json, Value };
 
-use crate::{ auth::USER, overall::sync_overall_state, SessionStats };
-
-pub fn get_auction_leaves(aucs: &Vec<AuctionModel>) -> Vec<String> {
-    let mut leaves = vec![];
-    // let tree: Vec<&'static str> = vec!["s", "ss"];
-    for auc in aucs {
-        let auc_record = format!(
-            "{}:{}:{}:{}:{:?}:{}:{}",
-            auc.id,
-            auc.car_id,
-            auc.start_time.and_utc().timestamp(),
-            auc.end_time.and_utc().timestamp(),
-            auc.status.clone().unwrap(),
-            auc.created_at.and_utc().timestamp(),
-            auc.updated_at.and_utc().timestamp()
+use crate::{
+    auth::AuthUser,
+    error::ApiError,
+    overall::proven_commit,
+    scheduler::record_transition,
+    sequencer::submit,
+    SessionStats,
+};
+
+pub fn auction_status(status: Option<Status>) -> AuctionStatus {
+    match status {
+        Some(Status::Active) => AuctionStatus::Active,
+        Some(Status::Completed) => AuctionStatus::Completed,
+        Some(Status::Cancelled) => AuctionStatus::Cancelled,
+        Some(Status::Pending) | None => AuctionStatus::Pending,
+    }
+}
+
+pub fn auction_type(auction_type: Option<AuctionType>) -> car_auction_core::AuctionType {
+    match auction_type {
+        Some(AuctionType::Sealed) => car_auction_core::AuctionType::Sealed,
+        Some(AuctionType::Vickrey) => car_auction_core::AuctionType::Vickrey,
+        Some(AuctionType::Dutch) => car_auction_core::AuctionType::Dutch,
+        Some(AuctionType::English) | None => car_auction_core::AuctionType::English,
+    }
+}
+
+pub fn auction_outcome(outcome: car_auction_core::AuctionOutcome) -> AuctionOutcome {
+    match outcome {
+        car_auction_core::AuctionOutcome::Sold => AuctionOutcome::Sold,
+        car_auction_core::AuctionOutcome::NoBids => AuctionOutcome::NoBids,
+        car_auction_core::AuctionOutcome::ReserveNotMet => AuctionOutcome::ReserveNotMet,
+    }
+}
+
+// Fields each auction format needs on top of the english ones.
+fn check_format(auction: &AuctionModel) -> Result<(), String> {
+    let format = auction_type(auction.auction_type);
+    if format.is_sealed() {
+        // reveals are taken after bidding closes, so the window must come after `end_time`
+        return match auction.reveal_end_time {
+            Some(reveal_end) if reveal_end > auction.end_time => Ok(()),
+            _ => Err("Sealed auctions need a reveal_end_time after end_time".to_string()),
+        };
+    }
+    if format == car_auction_core::AuctionType::Dutch {
+        // `current_bid` is the starting price the clock falls from
+        if auction.reserve_price.is_some() || auction.buy_now_price.is_some() {
+            return Err(
+                "Dutch auctions take a floor_price instead of a reserve or buy-now price".to_string()
+            );
+        }
+        return match auction.floor_price {
+            Some(floor) if floor >= 0 && floor < auction.current_bid => Ok(()),
+            _ => Err("Dutch auctions need a floor_price below current_bid".to_string()),
+        };
+    }
+    Ok(())
+}
+
+// Reserve, buy-now and increment rules; buy-now and increments only apply to open bidding.
+fn check_pricing(auction: &AuctionModel) -> Result<(), String> {
+    let format = auction_type(auction.auction_type);
+    if auction.reserve_price.is_some_and(|reserve| reserve < 0) {
+        return Err("reserve_price must not be negative".to_string());
+    }
+    if let Some(increment) = auction.min_increment {
+        if format != car_auction_core::AuctionType::English || increment <= 0 {
+            return Err(
+                "min_increment must be positive and only applies to english auctions".to_string()
+            );
+        }
+    }
+    if let Some(buy_now) = auction.buy_now_price {
+        if format != car_auction_core::AuctionType::English {
+            return Err("buy_now_price only applies to english auctions".to_string());
+        }
+        if buy_now <= auction.current_bid || auction.reserve_price.is_some_and(|r| buy_now < r) {
+            return Err("buy_now_price must be above current_bid and the reserve".to_string());
+        }
+    }
+    Ok(())
+}
+
+// Soft-close rules come as a set, only for open bidding, with a cap at or after `end_time`.
+fn check_soft_close(auction: &AuctionModel) -> Result<(), String> {
+    match (auction.soft_close_window, auction.soft_close_extension, auction.max_end_time) {
+        (None, None, None) => Ok(()),
+        (Some(window), Some(extension), Some(max_end)) if window > 0 && extension > 0 => {
+            if auction_type(auction.auction_type) != car_auction_core::AuctionType::English {
+                return Err("Soft close only applies to english auctions".to_string());
+            }
+            if max_end < auction.end_time {
+                return Err("max_end_time must not be before end_time".to_string());
+            }
+            Ok(())
+        }
+        _ => Err("Soft close needs a positive window and extension and a max_end_time".to_string()),
+    }
+}
+
+// A new auction is active if it has already started, otherwise pending until the scheduler
+// activates it at `start_time`.
+fn initial_status(auction: &AuctionModel, now: chrono::NaiveDateTime) -> Status {
+    if auction.start_time <= now { Status::Active } else { Status::Pending }
+}
+
+// No price an auction can sell at may undercut the car's listed starting price.
+fn check_starting_price(auction: &AuctionModel, car: &CarModel) -> Result<(), String> {
+    let lowest = auction.floor_price.unwrap_or(auction.current_bid);
+    if auction.current_bid < car.starting_price || lowest < car.starting_price {
+        return Err(
+            format!("Prices must be at least the car's starting_price of {}", car.starting_price)
         );
-        leaves.push(auc_record);
     }
-    leaves
+    Ok(())
+}
+
+pub fn auction_leaf(auc: &AuctionModel) -> AuctionLeaf {
+    AuctionLeaf {
+        id: auc.id,
+        car_id: auc.car_id,
+        seller: auc.seller.clone(),
+        start_time: auc.start_time.and_utc().timestamp(),
+        end_time: auc.end_time.and_utc().timestamp(),
+        current_bid: auc.current_bid as i64,
+        bid_count: auc.bid_count,
+        status: auction_status(auc.status.clone()),
+        auction_type: auction_type(auc.auction_type),
+        reveal_end_time: auc.reveal_end_time.map(|time| time.and_utc().timestamp()),
+        floor_price: auc.floor_price.map(|floor| floor as i64),
+        reserve_price: auc.reserve_price.map(|reserve| reserve as i64),
+        buy_now_price: auc.buy_now_price.map(|price| price as i64),
+        min_increment: auc.min_increment.map(|increment| increment as i64),
+        soft_close_window: auc.soft_close_window.map(|window| window as i64),
+        soft_close_extension: auc.soft_close_extension.map(|extension| extension as i64),
+        max_end_time: auc.max_end_time.map(|time| time.and_utc().timestamp()),
+        opening_price: auc.opening_price as i64,
+        commitment_root: auc.commitment_root.clone(),
+        created_at: auc.created_at.and_utc().timestamp(),
+        updated_at: auc.updated_at.and_utc().timestamp(),
+    }
+}
+
+pub fn get_auction_leaves(aucs: &[AuctionModel]) -> Vec<String> {
+    aucs.iter()
+        .map(|auc| auction_leaf(auc).encode())
+        .collect()
+}
+
+// Fails unless the auction row, as `txn` wrote it, encodes to the leaf the proof moved the
+// auction root to; otherwise the next state commit would not follow from the proof.
+pub async fn check_auction_leaf(
+    txn: &DatabaseTransaction,
+    id: i32,
+    path: &MerklePath,
+    auction_root: &RootTransition
+) -> Result<(), ApiError> {
+    let written = auction::Entity
+        ::find_by_id(id)
+        .one(txn).await?
+        .ok_or(ApiError::NotFound("Auction not found".to_string()))?;
+    if path.root(&auction_leaf(&written).encode()) != auction_root.new {
+        return Err(ApiError::Internal("Auction does not match the proven leaf".to_string()));
+    }
+    Ok(())
 }
 
 #[derive(Serialize, Deserialize)]
@@ -57,10 +230,16 @@ impl AuctionCommit {
     }
 }
 
-pub fn init_auction(leaves: Vec<String>) -> Result<AuctionCommit, String> {
-    let env = ExecutorEnv::builder().write(&leaves).unwrap().build().unwrap();
+pub fn init_auction(leaves: Vec<String>) -> Result<AuctionCommit, ApiError> {
+    let env = ExecutorEnv::builder()
+        .write(&leaves)
+        .map_err(|e| ApiError::Proving(e.to_string()))?
+        .build()
+        .map_err(|e| ApiError::Proving(e.to_string()))?;
     let prover = default_prover();
-    let prove_info = prover.prove(env, INIT_AUCTION_ELF).unwrap();
+    let prove_info = prover
+        .prove(env, INIT_AUCTION_ELF)
+        .map_err(|e| ApiError::Proving(e.to_string()))?;
     let auction_commit = AuctionCommit {
         receipt: prove_info.receipt,
         stats: SessionStats {
@@ -77,61 +256,87 @@ pub fn init_auction(leaves: Vec<String>) -> Result<AuctionCommit, String> {
 
 pub async fn init_auction_handler(axum::extract::State(
     db,
-): axum::extract::State<Arc<sea_orm::DatabaseConnection>>) -> Result<
-    Json<Value>,
-    (axum::http::StatusCode, String)
-> {
-    let aucs = get_all_auctions(&db).await.unwrap();
+): axum::extract::State<Arc<sea_orm::DatabaseConnection>>) -> Result<Json<Value>, ApiError> {
+    let aucs = get_all_auctions(&*db).await?;
     let leaves = get_auction_leaves(&aucs);
-    let result = init_auction(leaves).map_err(|e| (
-        axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-        e.to_string(),
-    ))?;
+    let result = init_auction(leaves)?;
     Ok(Json(json!(result)))
 }
 
 #[axum::debug_handler]
 pub async fn create_auction(
+    user: AuthUser,
     axum::extract::State(db): axum::extract::State<Arc<sea_orm::DatabaseConnection>>,
     Json(auction_data): Json<AuctionModel>
-) -> Result<Json<Value>, (axum::http::StatusCode, String)> {
-    let user = USER.get();
+) -> Result<Json<Value>, ApiError> {
     eprintln!("Request from user: {:?}", user.addr);
     eprintln!("Request from username: {}", user.username);
+    if auction_data.end_time <= auction_data.start_time {
+        return Err(ApiError::Validation("end_time must be after start_time".to_string()));
+    }
+    check_format(&auction_data).map_err(ApiError::Validation)?;
+    check_pricing(&auction_data).map_err(ApiError::Validation)?;
+    check_soft_close(&auction_data).map_err(ApiError::Validation)?;
+    let listed = car::Entity
+        ::find_by_id(auction_data.car_id)
+        .one(&*db).await?
+        .ok_or(ApiError::NotFound("Car not found".to_string()))?;
+    // the winner of the auction is transferred the car, so only its owner can list it
+    if !listed.owner.eq_ignore_ascii_case(&user.addr) {
+        return Err(ApiError::Forbidden("Not the car's owner".to_string()));
+    }
+    check_starting_price(&auction_data, &listed).map_err(ApiError::Validation)?;
     let now_naive: chrono::NaiveDateTime = Utc::now().naive_utc();
-    let auction_id = auction::Entity
-        ::find()
-        .order_by_desc(auction::Column::Id)
-        .one(&*db).await
-        .unwrap()
-        .unwrap();
+    // the scheduler owns the status from here on, the client's is ignored
+    let status = initial_status(&auction_data, now_naive);
     let auction_model = auction::ActiveModel {
-        id: Set(auction_id.id + 1),
-        car_id: Set(auction_data.car_id.clone()),
+        car_id: Set(auction_data.car_id),
         start_time: Set(auction_data.start_time.to_owned()),
         end_time: Set(auction_data.end_time.to_owned()),
         current_bid: Set(auction_data.current_bid.to_owned()),
+        opening_price: Set(auction_data.current_bid),
         bid_count: Set(auction_data.bid_count.to_owned()),
-        seller: Set(user.addr),
-        status: Set(auction_data.status.to_owned()),
-        created_at: Set(now_naive.clone()),
+        seller: Set(user.addr.clone()),
+        status: Set(Some(status.clone())),
+        auction_type: Set(auction_data.auction_type.or(Some(AuctionType::English))),
+        reveal_end_time: Set(auction_data.reveal_end_time),
+        floor_price: Set(auction_data.floor_price),
+        reserve_price: Set(auction_data.reserve_price),
+        buy_now_price: Set(auction_data.buy_now_price),
+        min_increment: Set(auction_data.min_increment),
+        soft_close_window: Set(auction_data.soft_close_window),
+        soft_close_extension: Set(auction_data.soft_close_extension),
+        max_end_time: Set(auction_data.max_end_time),
+        created_at: Set(now_naive),
         updated_at: Set(now_naive),
         ..Default::default()
     };
 
-    if let Some(car) = car::Entity::find_by_id(auction_data.car_id).one(&*db).await.unwrap() {
-        // 2. Convert it into an ActiveModel
-        let mut car_model: car::ActiveModel = car.into();
-
-        // 3. Update the field(s)
-        car_model.auction_id = Set(auction_id.id + 1); // example status
-
-        car_model.update(&*db).await.unwrap();
+    // the auction takes its id from the sequence, and the car stays locked until it points at it
+    let txn = db
+        .begin().await?;
+    let listed = car::Entity
+        ::find_by_id(listed.id)
+        .lock_exclusive()
+        .one(&txn).await?
+        .ok_or(ApiError::NotFound("Car not found".to_string()))?;
+    if !listed.owner.eq_ignore_ascii_case(&user.addr) {
+        return Err(ApiError::Forbidden("Not the car's owner".to_string()));
     }
-    auction_model
-        .insert(&*db).await
-        .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-    let (hash, commit) = sync_overall_state(db).await.unwrap();
+    let created = auction_model
+        .insert(&txn).await?;
+    let mut car_model: car::ActiveModel = listed.into();
+    car_model.auction_id = Set(created.id);
+    car_model.auction_status = Set(Some(status.clone()));
+    car_model
+        .update(&txn).await?;
+    let action = Some(StateAction::Auction(AuctionAction::CREATE));
+    let sequence = submit(&txn, &user.actor(), action.as_ref()).await?;
+    record_transition(&txn, created.id, None, status, &user.actor(), sequence).await?;
+    txn
+        .commit().await?;
+
+    let (hash, commit) = proven_commit(&db, sequence).await?;
     Ok(
         Json(
             json!({
@@ -145,16 +350,384 @@ pub async fn create_auction(
     )
 }
 
+#[derive(Deserialize)]
+pub struct RelistPayload {
+    pub start_time: chrono::NaiveDateTime,
+    pub end_time: chrono::NaiveDateTime,
+    pub reveal_end_time: Option<chrono::NaiveDateTime>,
+    // the rest default to the previous auction's
+    pub current_bid: Option<i32>,
+    pub reserve_price: Option<i32>,
+    pub buy_now_price: Option<i32>,
+    pub min_increment: Option<i32>,
+    // a soft-close auction needs a new cap, the old one is usually past
+    pub max_end_time: Option<chrono::NaiveDateTime>,
+}
+
+// POST /api/auctions/{id}/relist
+// Lists the car again after an auction settled without a sale (no bids or reserve not met).
+// The new auction keeps the old one's format and prices unless the request changes them.
+pub async fn relist_auction_handler(
+    user: AuthUser,
+    axum::extract::Path(id): axum::extract::Path<i32>,
+    axum::extract::State(db): axum::extract::State<Arc<sea_orm::DatabaseConnection>>,
+    Json(payload): Json<RelistPayload>
+) -> Result<Json<Value>, ApiError> {
+    let old = auction::Entity
+        ::find_by_id(id)
+        .one(&*db).await?
+        .ok_or(ApiError::NotFound("Auction not found".to_string()))?;
+    if !old.seller.eq_ignore_ascii_case(&user.addr) && !user.has_role(Role::Admin) {
+        return Err(ApiError::Forbidden("Not owner".to_string()));
+    }
+    if
+        old.status != Some(Status::Completed) ||
+        !matches!(old.outcome, Some(AuctionOutcome::NoBids | AuctionOutcome::ReserveNotMet))
+    {
+        return Err(
+            ApiError::Conflict("Only auctions settled without a sale can be relisted".to_string())
+        );
+    }
+
+    let now_naive = Utc::now().naive_utc();
+    let relist = AuctionModel {
+        start_time: payload.start_time,
+        end_time: payload.end_time,
+        reveal_end_time: payload.reveal_end_time,
+        current_bid: payload.current_bid.unwrap_or(old.current_bid),
+        reserve_price: payload.reserve_price.or(old.reserve_price),
+        buy_now_price: payload.buy_now_price.or(old.buy_now_price),
+        min_increment: payload.min_increment.or(old.min_increment),
+        max_end_time: payload.max_end_time.or(old.max_end_time),
+        bid_count: 0,
+        status: Some(Status::Pending),
+        winner: None,
+        winning_bid_id: None,
+        settled_at: None,
+        outcome: None,
+        settlement_receipt: None,
+        commitment_root: None,
+        relisted_from: Some(old.id),
+        created_at: now_naive,
+        updated_at: now_naive,
+        ..old.clone()
+    };
+    if relist.end_time <= relist.start_time {
+        return Err(ApiError::Validation("end_time must be after start_time".to_string()));
+    }
+    check_format(&relist).map_err(ApiError::Validation)?;
+    check_pricing(&relist).map_err(ApiError::Validation)?;
+    check_soft_close(&relist).map_err(ApiError::Validation)?;
+    let listed = car::Entity
+        ::find_by_id(relist.car_id)
+        .one(&*db).await?
+        .ok_or(ApiError::NotFound("Car not found".to_string()))?;
+    // the relisted auction keeps its seller, who must still own the car
+    if !listed.owner.eq_ignore_ascii_case(&relist.seller) {
+        return Err(ApiError::Forbidden("Seller no longer owns the car".to_string()));
+    }
+    check_starting_price(&relist, &listed).map_err(ApiError::Validation)?;
+    let status = initial_status(&relist, now_naive);
+    let relist = AuctionModel {
+        status: Some(status.clone()),
+        opening_price: relist.current_bid,
+        ..relist
+    };
+
+    // the old auction stays locked until its relist is written, so it is relisted once
+    let txn = db
+        .begin().await?;
+    auction::Entity
+        ::find_by_id(id)
+        .lock_exclusive()
+        .one(&txn).await?
+        .ok_or(ApiError::NotFound("Auction not found".to_string()))?;
+    let relisted = auction::Entity
+        ::find()
+        .filter(auction::Column::RelistedFrom.eq(id))
+        .one(&txn).await?;
+    if relisted.is_some() {
+        return Err(ApiError::Conflict("Auction already relisted".to_string()));
+    }
+
+    // every other field set, so the insert writes the whole row with an id from the sequence
+    let mut relist = auction::ActiveModel::from(relist).reset_all();
+    relist.id = NotSet;
+    let relist = relist
+        .insert(&txn).await?;
+    let moved = car::Entity
+        ::update_many()
+        .col_expr(car::Column::AuctionId, relist.id.into())
+        .col_expr(
+            car::Column::AuctionStatus,
+            car::Column::AuctionStatus.save_as(sea_orm::sea_query::Expr::val(status.clone()))
+        )
+        .filter(car::Column::Id.eq(relist.car_id))
+        .filter(car::Column::Owner.eq(relist.seller.clone()))
+        .exec(&txn).await?;
+    if moved.rows_affected == 0 {
+        return Err(ApiError::Forbidden("Seller no longer owns the car".to_string()));
+    }
+    let action = Some(StateAction::Auction(AuctionAction::CREATE));
+    let sequence = submit(&txn, &user.actor(), action.as_ref()).await?;
+    record_transition(&txn, relist.id, None, status, &user.actor(), sequence).await?;
+    txn
+        .commit().await?;
+
+    let (hash, commit) = proven_commit(&db, sequence).await?;
+    Ok(
+        Json(
+            json!({
+      "status": "success",
+      "message": "auction relisted",
+      "data": relist,
+      "cid": hash,
+      "receipt": commit.receipt,
+      "stats": commit.stats
+    })
+        )
+    )
+}
+
+// An auction its seller (or an admin) can still change: pending or active, with no bids or
+// sealed commitments yet.
+async fn find_editable_auction(
+    db: &DatabaseConnection,
+    id: i32,
+    user: &AuthUser
+) -> Result<AuctionModel, ApiError> {
+    let auc = auction::Entity
+        ::find_by_id(id)
+        .one(db).await?
+        .ok_or(ApiError::NotFound("Auction not found".to_string()))?;
+    if !auc.seller.eq_ignore_ascii_case(&user.addr) && !user.has_role(Role::Admin) {
+        return Err(ApiError::Forbidden("Not owner".to_string()));
+    }
+    if !matches!(auc.status, Some(Status::Pending | Status::Active)) {
+        return Err(ApiError::Conflict("Auction is closed".to_string()));
+    }
+    let commitments = sealed_bid::Entity
+        ::find()
+        .filter(sealed_bid::Column::AuctionId.eq(id))
+        .count(db).await?;
+    if auc.bid_count > 0 || commitments > 0 {
+        return Err(ApiError::Conflict("Auction cannot change after the first bid".to_string()));
+    }
+    Ok(auc)
+}
+
+#[derive(Deserialize)]
+pub struct AuctionUpdatePayload {
+    pub start_time: Option<chrono::NaiveDateTime>,
+    pub end_time: Option<chrono::NaiveDateTime>,
+    pub reveal_end_time: Option<chrono::NaiveDateTime>,
+    pub current_bid: Option<i32>,
+    pub floor_price: Option<i32>,
+    pub reserve_price: Option<i32>,
+    pub buy_now_price: Option<i32>,
+    pub min_increment: Option<i32>,
+    pub soft_close_window: Option<i32>,
+    pub soft_close_extension: Option<i32>,
+    pub max_end_time: Option<chrono::NaiveDateTime>,
+}
+
+// PUT /api/auctions/{id}
+// Changes the schedule or prices of an auction before its first bid and commits the change as
+// an auction UPDATE. The format cannot change, nor the start of an auction already running.
+pub async fn update_auction_handler(
+    user: AuthUser,
+    axum::extract::Path(id): axum::extract::Path<i32>,
+    axum::extract::State(db): axum::extract::State<Arc<sea_orm::DatabaseConnection>>,
+    Json(payload): Json<AuctionUpdatePayload>
+) -> Result<Json<Value>, ApiError> {
+    let old = find_editable_auction(&db, id, &user).await?;
+    let now_naive = Utc::now().naive_utc();
+    let mut updated = AuctionModel {
+        start_time: payload.start_time.unwrap_or(old.start_time),
+        end_time: payload.end_time.unwrap_or(old.end_time),
+        reveal_end_time: payload.reveal_end_time.or(old.reveal_end_time),
+        current_bid: payload.current_bid.unwrap_or(old.current_bid),
+        floor_price: payload.floor_price.or(old.floor_price),
+        reserve_price: payload.reserve_price.or(old.reserve_price),
+        buy_now_price: payload.buy_now_price.or(old.buy_now_price),
+        min_increment: payload.min_increment.or(old.min_increment),
+        soft_close_window: payload.soft_close_window.or(old.soft_close_window),
+        soft_close_extension: payload.soft_close_extension.or(old.soft_close_extension),
+        max_end_time: payload.max_end_time.or(old.max_end_time),
+        updated_at: now_naive,
+        ..old.clone()
+    };
+    if old.status == Some(Status::Active) && updated.start_time != old.start_time {
+        return Err(
+            ApiError::Validation("An active auction's start_time cannot change".to_string())
+        );
+    }
+    if updated.end_time <= updated.start_time || updated.end_time <= now_naive {
+        return Err(
+            ApiError::Validation("end_time must be after start_time and in the future".to_string())
+        );
+    }
+    check_format(&updated).map_err(ApiError::Validation)?;
+    check_pricing(&updated).map_err(ApiError::Validation)?;
+    check_soft_close(&updated).map_err(ApiError::Validation)?;
+    let listed = car::Entity
+        ::find_by_id(updated.car_id)
+        .one(&*db).await?
+        .ok_or(ApiError::NotFound("Car not found".to_string()))?;
+    check_starting_price(&updated, &listed).map_err(ApiError::Validation)?;
+    // a pending auction moved to a start already past opens right away
+    let status = initial_status(&updated, now_naive);
+    updated.status = Some(status.clone());
+    // without bids the auction still opens at its current bid
+    updated.opening_price = updated.current_bid;
+
+    let txn = db.begin().await?;
+    let updated = auction::Entity
+        ::update(auction::ActiveModel::from(updated).reset_all())
+        .filter(auction::Column::Status.eq(old.status.clone()))
+        .filter(auction::Column::BidCount.eq(0))
+        .exec(&txn).await
+        .map_err(|e| match e {
+            DbErr::RecordNotUpdated =>
+                ApiError::Conflict("Auction changed while updating".to_string()),
+            e => ApiError::Db(e),
+        })?;
+    if updated.status != old.status {
+        car::Entity
+            ::update_many()
+            .col_expr(
+                car::Column::AuctionStatus,
+                car::Column::AuctionStatus.save_as(sea_orm::sea_query::Expr::val(status.clone()))
+            )
+            .filter(car::Column::AuctionId.eq(id))
+            .exec(&txn).await?;
+    }
+    let action = Some(StateAction::Auction(AuctionAction::UPDATE));
+    let sequence = submit(&txn, &user.actor(), action.as_ref()).await?;
+    if updated.status != old.status {
+        record_transition(&txn, id, old.status, status, &user.actor(), sequence).await?;
+    }
+    txn.commit().await?;
+
+    let (hash, commit) = proven_commit(&db, sequence).await?;
+    Ok(
+        Json(
+            json!({
+      "status": "success",
+      "message": "auction updated",
+      "data": updated,
+      "cid": hash,
+      "receipt": commit.receipt,
+      "stats": commit.stats
+    })
+        )
+    )
+}
+
+// DELETE /api/auctions/{id}
+// Cancels an auction before its first bid. The auction and its car become `cancelled`, its
+// proxy bids stop, and the change is committed as an auction DELETE. The row stays, so the
+// auction's history remains provable.
+pub async fn cancel_auction_handler(
+    user: AuthUser,
+    axum::extract::Path(id): axum::extract::Path<i32>,
+    axum::extract::State(db): axum::extract::State<Arc<sea_orm::DatabaseConnection>>
+) -> Result<Json<Value>, ApiError> {
+    let old = find_editable_auction(&db, id, &user).await?;
+    let now_naive = Utc::now().naive_utc();
+    let txn = db.begin().await?;
+    let cancelled = auction::Entity
+        ::update_many()
+        .col_expr(
+            auction::Column::Status,
+            auction::Column::Status.save_as(sea_orm::sea_query::Expr::val(Status::Cancelled))
+        )
+        .col_expr(auction::Column::UpdatedAt, now_naive.into())
+        .filter(auction::Column::Id.eq(id))
+        .filter(auction::Column::Status.eq(old.status.clone()))
+        .filter(auction::Column::BidCount.eq(0))
+        .exec(&txn).await?;
+    if cancelled.rows_affected == 0 {
+        return Err(ApiError::Conflict("Auction changed while cancelling".to_string()));
+    }
+    car::Entity
+        ::update_many()
+        .col_expr(
+            car::Column::AuctionStatus,
+            car::Column::AuctionStatus.save_as(
+                sea_orm::sea_query::Expr::val(Status::Cancelled)
+            )
+        )
+        .filter(car::Column::AuctionId.eq(id))
+        .exec(&txn).await?;
+    proxy_bid::Entity
+        ::update_many()
+        .col_expr(proxy_bid::Column::Active, false.into())
+        .col_expr(proxy_bid::Column::UpdatedAt, now_naive.into())
+        .filter(proxy_bid::Column::AuctionId.eq(id))
+        .exec(&txn).await?;
+
+    let actor = user.actor();
+    let action = Some(StateAction::Auction(AuctionAction::DELETE));
+    let sequence = submit(&txn, &actor, action.as_ref()).await?;
+    record_transition(&txn, id, old.status, Status::Cancelled, &actor, sequence).await?;
+    txn.commit().await?;
+
+    let (hash, commit) = proven_commit(&db, sequence).await?;
+    Ok(
+        Json(
+            json!({
+      "status": "success",
+      "message": "auction cancelled",
+      "cid": hash,
+      "receipt": commit.receipt,
+      "stats": commit.stats
+    })
+        )
+    )
+}
+
+// GET /api/auctions/{id}/transitions
+// Status changes of an auction, oldest first, each with the state commit it produced.
+pub async fn get_auction_transitions_handler(
+    axum::extract::Path(id): axum::extract::Path<i32>,
+    axum::extract::State(db): axum::extract::State<Arc<sea_orm::DatabaseConnection>>
+) -> Result<Json<Value>, ApiError> {
+    let transitions = auction_transition::Entity
+        ::find()
+        .filter(auction_transition::Column::AuctionId.eq(id))
+        .order_by_asc(auction_transition::Column::Id)
+        .all(&*db).await?;
+    Ok(Json(json!({
+      "status": "success",
+      "data": transitions
+    })))
+}
+
+// GET /api/auctions/{id}/extensions
+// Soft-close extensions of an auction, oldest first, each with the create_bid receipt that
+// moved `end_time`.
+pub async fn get_auction_extensions_handler(
+    axum::extract::Path(id): axum::extract::Path<i32>,
+    axum::extract::State(db): axum::extract::State<Arc<sea_orm::DatabaseConnection>>
+) -> Result<Json<Value>, ApiError> {
+    let extensions = auction_extension::Entity
+        ::find()
+        .filter(auction_extension::Column::AuctionId.eq(id))
+        .order_by_asc(auction_extension::Column::Id)
+        .all(&*db).await?;
+    Ok(Json(json!({
+      "status": "success",
+      "data": extensions
+    })))
+}
+
 pub async fn get_auctions(axum::extract::State(
     db,
-): axum::extract::State<Arc<sea_orm::DatabaseConnection>>) -> Result<
-    Json<Value>,
-    (axum::http::StatusCode, String)
-> {
+): axum::extract::State<Arc<sea_orm::DatabaseConnection>>) -> Result<Json<Value>, ApiError> {
     let auctions = auction::Entity
         ::find()
-        .all(&*db).await
-        .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .all(&*db).await?;
     Ok(Json(json!({
     "status": "success",
     "data": auctions
@@ -164,79 +737,52 @@ pub async fn get_auctions(axum::extract::State(
 pub async fn get_auction_by_id(
     axum::extract::Path(id): axum::extract::Path<i32>,
     axum::extract::State(db): axum::extract::State<Arc<sea_orm::DatabaseConnection>>
-) -> Result<Json<Value>, (axum::http::StatusCode, String)> {
+) -> Result<Json<Value>, ApiError> {
     let auctions = auction::Entity
         ::find_by_id(id)
-        .one(&*db).await
-        .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .one(&*db).await?;
 
     match auctions {
         Some(auctions) => Ok(Json(json!({
       "status": "success",
       "data": auctions
     }))),
-        None => Err((axum::http::StatusCode::NOT_FOUND, "Auction not found".to_string())),
+        None => Err(ApiError::NotFound("Auction not found".to_string())),
     }
 }
-pub async fn complete_bid_by_id(
+// GET /api/auctions/{id}/dutch-price
+// Current clock price of a Dutch auction; the first signed bid at or above it buys the car.
+pub async fn get_dutch_price_handler(
     axum::extract::Path(id): axum::extract::Path<i32>,
     axum::extract::State(db): axum::extract::State<Arc<sea_orm::DatabaseConnection>>
-) -> Result<Json<Value>, (axum::http::StatusCode, String)> {
-    let user = USER.get();
-    let bid = bid::Entity
+) -> Result<Json<Value>, ApiError> {
+    let auc = auction::Entity
         ::find_by_id(id)
-        .one(&*db).await
-        .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
-        .unwrap()
-        .unwrap();
-
-    let highest_bids = bid::Entity
-        ::find()
-        .order_by_desc(bid::Column::Amount)
-        .one(&*db).await
-        .unwrap()
-        .unwrap();
-
-    if highest_bids.id != bid.id && highest_bids.bidder_id != user.addr {
-        return Err((
-            axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-            "Bid not the highest or the owner of the bid".to_string(),
-        ));
-    }
-    let auctions = auction::Entity
-        ::find_by_id(bid.auction_id)
-        .one(&*db).await
-        .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-
-    match auctions {
-        Some(auctions) => {
-            let now = chrono::Utc::now().timestamp();
-            if auctions.end_time.and_utc().timestamp() <= now {
-                return Err((
-                    axum::http::StatusCode::TOO_EARLY,
-                    "Auction time not yet up".to_string(),
-                ));
-            }
-
-            if auctions.seller != user.addr {
-                return Err((axum::http::StatusCode::FORBIDDEN, "Not owner".to_string()));
-            }
-
-            Ok(
-                Json(
-                    json!({
-            
-            "status": "success",
-            "data": auctions
-          })
-                )
-            )
-        }
-        None => Err((axum::http::StatusCode::NOT_FOUND, "Auction not found".to_string())),
-    }
+        .one(&*db).await?
+        .ok_or(ApiError::NotFound("Auction not found".to_string()))?;
+    let now = Utc::now().timestamp();
+    let price = auction_leaf(&auc)
+        .dutch_price(now)
+        .ok_or(ApiError::Validation("Auction is not dutch".to_string()))?;
+    Ok(
+        Json(
+            json!({
+      "status": "success",
+      "data": {
+        "auction_id": auc.id,
+        "price": price,
+        "floor_price": auc.floor_price,
+        "sold": auc.bid_count > 0,
+        "timestamp": now
+      }
+    })
+        )
+    )
 }
-pub async fn get_all_auctions(
-    db: &DatabaseConnection
+
+pub async fn get_all_auctions<C: ConnectionTrait>(
+    db: &C
 ) -> Result<Vec<::entity::auction::Model>, DbErr> {
-    ::entity::auction::Entity::find().all(db).await
+    // ordered so leaf positions, and so Merkle paths, are stable between calls
+    ::entity::auction::Entity::find().order_by_asc(auction::Column::Id).all(db).await
 }
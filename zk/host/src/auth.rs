@@ -1,7 +1,6 @@
-#[warn(unused_assignments)]
 use axum::{
-    extract::{ Request },
-    http::{ header, HeaderMap, HeaderValue, StatusCode },
+    extract::{ FromRequestParts, Request },
+    http::{ header, request::Parts, HeaderMap, HeaderValue },
     middleware::Next,
     response::{ IntoResponse, Response },
 };
@@ -9,15 +8,27 @@ use methods::{ VERIFY_ELF, VERIFY_ID };
 use risc0_zkvm::{ default_prover, ExecutorEnv, Receipt };
 use sea_orm::sqlx::types::uuid;
 use serde::{ Deserialize, Serialize };
-use ethers::{ types::Address, utils::hex::{ hex } };
+use ethers::{ types::Address, utils::hex };
 use axum::{ Json };
 use serde_json::{ json, Value };
-use jsonwebtoken::{ decode, DecodingKey, Validation };
 use k256::{ ecdsa::{ RecoveryId, Signature, VerifyingKey } };
+use std::{ sync::Arc, time::Duration };
 // use ecdsa::SigningKey;
 use sha3::{ Digest, Keccak256 };
 
-use crate::{ jwt::{ issue_token, Claims }, redis::{ get_nonce, store_nonce }, SessionStats };
+use car_auction_core::Actor;
+use entity::Role;
+
+use crate::{
+    eip1271::ContractWallets,
+    error::ApiError,
+    jwt::{ Claims, JwtKeys },
+    refresh::issue_refresh_token,
+    role::{ actor_for, login_roles },
+    nonce::NonceStore,
+    siwe::SiweConfig,
+    SessionStats,
+};
 
 #[derive(Deserialize)]
 pub struct SignaturePayload {
@@ -33,14 +44,16 @@ pub struct VerifyParams {
     pub expected_addr: String,
     pub timestamp: i64,
     pub username: String,
+    pub domain: String,
+    pub nonce: String,
 }
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct VerifyPayload {
-    message: String,
-    signature_bytes: String,
-    expected_addr: String,
-    username: String,
-    nonce: String,
+    pub message: String,
+    pub signature_bytes: String,
+    pub expected_addr: String,
+    pub username: String,
+    pub nonce: String,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -55,6 +68,10 @@ pub struct VerifyState {
     pub address: String,
     pub timestamp: i64,
     pub username: String,
+    pub domain: String,
+    pub nonce: String,
+    pub issued_at: i64,
+    pub expiration_time: i64,
 }
 
 impl VerifyCommit {
@@ -70,21 +87,21 @@ impl VerifyCommit {
 }
 pub async fn verify_signature(Json(payload): Json<SignaturePayload>) -> Result<
     Json<String>,
-    (StatusCode, String)
+    ApiError
 > {
     let sig_bytes = match
         hex::decode(payload.signature.strip_prefix("0x").unwrap_or(&payload.signature))
     {
         Ok(b) => b,
         Err(_) => {
-            return Err((StatusCode::BAD_REQUEST, "Invalid signature hex".into()));
+            return Err(ApiError::Validation("Invalid signature hex".into()));
         }
     };
 
     let signature = match ethers::types::Signature::try_from(&sig_bytes[..]) {
         Ok(sig) => sig,
         Err(_) => {
-            return Err((StatusCode::BAD_REQUEST, "Invalid signature format".into()));
+            return Err(ApiError::Validation("Invalid signature format".into()));
         }
     };
 
@@ -95,37 +112,44 @@ pub async fn verify_signature(Json(payload): Json<SignaturePayload>) -> Result<
     let recovered = match signature.recover(payload.message) {
         Ok(addr) => addr,
         Err(_) => {
-            return Err((StatusCode::UNAUTHORIZED, "Signature verification failed".into()));
+            return Err(ApiError::Unauthorized("Signature verification failed".into()));
         }
     };
 
     // Compare addresses (case-insensitive)
     let claimed = payload.address
         .parse::<Address>()
-        .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid address".into()))?;
+        .map_err(|_| ApiError::Validation("Invalid address".into()))?;
 
     if claimed != recovered {
-        return Err((StatusCode::UNAUTHORIZED, "Address mismatch".into()));
+        return Err(ApiError::Unauthorized("Address mismatch".into()));
     }
 
     // ✅ Verified! You can now issue JWT or create zk proof.
     Ok(Json(format!("Signature verified for address: {:?}", claimed)))
 }
 
-// pub struct NoncePayload {
-//     address: String,
-// }
+#[derive(Deserialize)]
+pub struct NonceQuery {
+    pub address: String,
+}
 pub async fn get_verify_handler(
-    // axum::extract::State(db): axum::extract::State<Arc<sea_orm::DatabaseConnection>>,
-    // Json(payload): Json<NoncePayload>
-) -> Result<Json<Value>, (StatusCode, String)> {
-    // convert to Vec
-    let nonce = uuid::Uuid::new_v4().to_string();
-    // let now = chrono::Utc::now().timestamp();
-    let rs = store_nonce(&nonce).await.unwrap();
+    axum::extract::State(nonces): axum::extract::State<Arc<dyn NonceStore>>,
+    axum::extract::Query(query): axum::extract::Query<NonceQuery>
+) -> Result<Json<Value>, ApiError> {
+    let address = query.address
+        .parse::<Address>()
+        .map_err(|_| ApiError::Validation("Invalid address".to_string()))?;
+    // EIP-4361 nonces are alphanumeric, so drop the uuid hyphens
+    let nonce = uuid::Uuid::new_v4().simple().to_string();
+    let config = SiweConfig::from_env();
+    let message = config.login_message(&address, &nonce).to_string();
+    nonces
+        .store(&nonce, &message, Duration::from_secs(config.ttl as u64)).await
+        .map_err(|e| ApiError::Storage(e.to_string()))?;
     Ok(Json(json!({
-      "nonce": rs.0,
-      "msg": rs.1
+      "nonce": nonce,
+      "msg": message
     })))
 }
 fn _keccak256(data: &[u8]) -> [u8; 32] {
@@ -157,7 +181,8 @@ fn _keccak256(data: &[u8]) -> [u8; 32] {
 //     Some(addr)
 // }
 
-fn _recover_ethereum_address(signature_hex: &str, message: &str) -> Result<[u8; 20], String> {
+// Native counterpart of the verify guest's recovery, for the fast-path login.
+pub fn recover_ethereum_address(signature_hex: &str, message: &str) -> Result<[u8; 20], String> {
     let signature_bytes = hex
         ::decode(signature_hex.strip_prefix("0x").unwrap_or(signature_hex))
         .map_err(|e| format!("Invalid signature hex: {}", e))?;
@@ -202,18 +227,31 @@ fn _recover_ethereum_address(signature_hex: &str, message: &str) -> Result<[u8;
     Ok(address)
 }
 pub async fn verify_signature_handler(
-    // axum::extract::State(db): axum::extract::State<Arc<sea_orm::DatabaseConnection>>,
+    axum::extract::State(nonces): axum::extract::State<Arc<dyn NonceStore>>,
     Json(payload): Json<VerifyPayload>
-) -> Result<Json<Value>, (StatusCode, String)> {
-    // convert to Vec
-    // let message = get_nonce(&payload.nonce).await;
-    // let message = payload.message.clone();
-    let message = get_nonce(&payload.nonce).await;
+) -> Result<Json<Value>, ApiError> {
+    // the verify guest can only recover EOA signatures; checked before the nonce is consumed
+    if let Some(wallets) = ContractWallets::from_env() {
+        let address = payload.expected_addr
+            .parse::<Address>()
+            .map_err(|_| ApiError::Validation("Invalid address".to_string()))?;
+        if wallets.is_contract(address).await.map_err(ApiError::Chain)? {
+            return Err(
+                ApiError::Validation(
+                    "Contract wallets sign in through POST /api/auth/login".to_string()
+                )
+            );
+        }
+    }
+    // consumes the nonce, so the same nonce and signature cannot be proven twice
+    let message = nonces
+        .take(&payload.nonce).await
+        .map_err(|e| ApiError::Storage(e.to_string()))?;
     let message = message.ok_or_else(|| {
-        (StatusCode::BAD_REQUEST, "Failed to verify nonce".to_string())
+        ApiError::Validation("Failed to verify nonce".to_string())
     })?;
     // let message = message.ok_or_else(|| {
-    //     (StatusCode::BAD_REQUEST, "Failed to verify nonce".to_string())
+    //     ApiError::Validation("Failed to verify nonce".to_string())
     // })?;
     eprintln!("Get nonce {}", message);
     // let message_bytes: [u8] = hex::decode(&message).expect("Invalid mesg");
@@ -235,20 +273,46 @@ pub async fn verify_signature_handler(
         expected_addr: payload.expected_addr.clone(),
         timestamp: now,
         username: payload.username,
+        domain: SiweConfig::from_env().domain,
+        nonce: payload.nonce,
     };
     //  let recovered_addr = recover_ethereum_address(&payload.signature_bytes, &message).map_err(|e| {
-    //      (StatusCode::BAD_REQUEST, e)
+    //      ApiError::Validation(e)
     //  })?;
     //  let eth_address = format!("0x{}", hex::encode(recovered_addr));
     //  println!("{}", eth_address);
 
     //  eprintln!("Recovered address{:?}", eth_address);
 
-    let env = ExecutorEnv::builder().write(&vec_payload).unwrap().build().unwrap();
+    let verify_commit = prove_login(&vec_payload)?;
+    // the receipt can be exchanged for a token once, until the login message expires
+    nonces
+        .store(
+            &receipt_key(&vec_payload.nonce),
+            &vec_payload.expected_addr,
+            Duration::from_secs(SiweConfig::from_env().ttl as u64)
+        ).await
+        .map_err(|e| ApiError::Storage(e.to_string()))?;
+    // eprint!("{:?}", verify_commit);
+    Ok(Json(json!(verify_commit)))
+}
+
+// Store key marking a proven login whose receipt has not been exchanged for a token yet.
+fn receipt_key(nonce: &str) -> String {
+    format!("receipt:{}", nonce)
+}
+
+// Runs the verify guest over a login. Blocking; takes as long as proving does.
+pub fn prove_login(params: &VerifyParams) -> Result<VerifyCommit, ApiError> {
+    let env = ExecutorEnv::builder()
+        .write(params)
+        .map_err(|e| ApiError::Proving(e.to_string()))?
+        .build()
+        .map_err(|e| ApiError::Proving(e.to_string()))?;
     let prover = default_prover();
-    let prove_info = prover.prove(env, VERIFY_ELF).unwrap();
+    let prove_info = prover.prove(env, VERIFY_ELF).map_err(|e| ApiError::Proving(e.to_string()))?;
     eprint!("Prove info {:?}", prove_info.stats);
-    let verify_commit = VerifyCommit {
+    Ok(VerifyCommit {
         receipt: prove_info.receipt,
         stats: SessionStats {
             segments: prove_info.stats.segments,
@@ -257,28 +321,22 @@ pub async fn verify_signature_handler(
             paging_cycles: prove_info.stats.paging_cycles,
             reserved_cycles: prove_info.stats.reserved_cycles,
         },
-    };
-    // eprint!("{:?}", verify_commit);
-    Ok(Json(json!(verify_commit)))
+    })
 }
 
 pub async fn verify_auth_handler(
-    // axum::extract::State(db): axum::extract::State<Arc<sea_orm::DatabaseConnection>>,
+    axum::extract::State(db): axum::extract::State<Arc<sea_orm::DatabaseConnection>>,
+    axum::extract::State(keys): axum::extract::State<Arc<JwtKeys>>,
+    axum::extract::State(nonces): axum::extract::State<Arc<dyn NonceStore>>,
     Json(payload): Json<VerifyCommit>
-) -> Result<Response, StatusCode> {
+) -> Result<Response, ApiError> {
     let state = payload;
-    let commit = state
-        .verify_and_get_commit()
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
-        .unwrap();
-    // .ok()
-    // .unwrap();
-    // let addr = std::str::from_utf8(&commit.address).unwrap();
-    let mut key: Option<String> = None;
+    let commit = state.verify_and_get_commit().map_err(ApiError::Unauthorized)?;
     eprintln!("Verified {:?}", commit);
-    if commit.verified {
-        key = Some(issue_token(&commit.address, &commit.username));
-    } else {
+    // the proof only counts for logins scoped to this host that have not expired yet
+    let now = chrono::Utc::now().timestamp();
+    let scoped = commit.domain == SiweConfig::from_env().domain && commit.expiration_time > now;
+    if !commit.verified || !scoped {
         return Ok(
             (
                 Json(
@@ -290,65 +348,101 @@ pub async fn verify_auth_handler(
             ).into_response()
         );
     }
+    // GETDEL, so a replayed receipt finds the entry gone and cannot mint a second session
+    let redeemed = nonces
+        .take(&receipt_key(&commit.nonce)).await
+        .map_err(|e| ApiError::Storage(e.to_string()))?;
+    if redeemed.is_none() {
+        return Err(ApiError::Unauthorized("Login proof already redeemed".to_string()));
+    }
+    let roles = login_roles(&db, &commit.address).await?;
+    let key = keys
+        .issue_token(&commit.address, &commit.username, &roles)
+        .map_err(ApiError::Internal)?;
     let mut headers = HeaderMap::new();
     headers.insert(
         axum::http::header::AUTHORIZATION,
-        HeaderValue::from_str(&format!("Bearer {}", key.unwrap()))
-            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Invalid header value".to_string()))
-            .unwrap()
+        HeaderValue::from_str(&format!("Bearer {}", key)).map_err(|_|
+            ApiError::Internal("Invalid header value".to_string())
+        )?
     );
 
-    Ok((headers, Json(json!(commit))).into_response())
+    // lets the client renew the session without a new zk login proof
+    let refresh_token = issue_refresh_token(&db, &keys, &commit.address, &commit.username).await?;
+    let mut body = json!(commit);
+    body["refresh_token"] = json!(refresh_token);
+
+    Ok((headers, Json(body)).into_response())
     // Ok(Json(json!(commit)))
 }
-use tokio::{ task_local };
-
-task_local! {
-    pub static USER: Claims;
+// The authenticated caller, taken from the access token by the `auth` middleware.
+#[derive(Clone, Debug)]
+pub struct AuthUser {
+    pub addr: String,
+    pub username: String,
+    pub roles: Vec<Role>,
+    // role the route authorized the request under, set by `role::require_roles`
+    pub acting: Option<Role>,
 }
 
-pub async fn auth(req: Request, next: Next) -> Result<Response, StatusCode> {
-    let auth_header = req
-        .headers()
-        .get(header::AUTHORIZATION)
-        .and_then(|header| header.to_str().ok())
-        .ok_or(StatusCode::UNAUTHORIZED)?;
-    let token = auth_header.strip_prefix("Bearer ").ok_or(StatusCode::UNAUTHORIZED)?;
-
-    if let Some(current_user) = authorize_current_user(token).await {
-        Ok(USER.scope(current_user, next.run(req)).await)
-    } else {
-        Err(StatusCode::UNAUTHORIZED)
+impl AuthUser {
+    pub fn has_role(&self, role: Role) -> bool {
+        self.roles.contains(&role)
     }
 
-    //     // State is setup here in the middleware
-    //     Ok(USER.scope(current_user, next.run(req)).await)
-    // } else {
-    //     Err(StatusCode::UNAUTHORIZED)
-    // }
+    // Actor committed with state changes made by this request.
+    pub fn actor(&self) -> Actor {
+        self.acting
+            .or_else(|| self.roles.first().copied())
+            .map(actor_for)
+            .unwrap_or(Actor::BIDDER)
+    }
 }
-async fn authorize_current_user(auth_token: &str) -> Option<Claims> {
-    let token_data = decode::<Claims>(
-        auth_token,
-        &DecodingKey::from_secret("your-secret".as_bytes()),
-        &Validation::default()
-    ).ok()?;
-
-    // check expiry
-    Some(token_data.claims)
+
+impl From<Claims> for AuthUser {
+    fn from(claims: Claims) -> Self {
+        AuthUser {
+            addr: claims.addr,
+            username: claims.username,
+            roles: claims.roles,
+            acting: None,
+        }
+    }
 }
 
-struct UserResponse;
+impl<S: Send + Sync> FromRequestParts<S> for AuthUser {
+    type Rejection = ApiError;
 
-impl IntoResponse for UserResponse {
-    fn into_response(self) -> Response {
-        // State is accessed here in the IntoResponse implementation
-        let current_user = USER.with(|u| Claims {
-            username: u.username.clone(),
-            addr: u.addr.clone(),
-            exp: u.exp.clone(),
-        });
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts.extensions
+            .get::<AuthUser>()
+            .cloned()
+            .ok_or(ApiError::Unauthorized("Missing access token".to_string()))
+    }
+}
 
-        (StatusCode::OK, Json(json!(current_user))).into_response()
+pub async fn auth(
+    axum::extract::State(keys): axum::extract::State<Arc<JwtKeys>>,
+    mut req: Request,
+    next: Next
+) -> Result<Response, ApiError> {
+    let auth_header = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|header| header.to_str().ok())
+        .ok_or(ApiError::Unauthorized("Missing access token".to_string()))?;
+    let token = auth_header
+        .strip_prefix("Bearer ")
+        .ok_or(ApiError::Unauthorized("Expected a Bearer token".to_string()))?;
+
+    if let Some(current_user) = authorize_current_user(&keys, token).await {
+        req.extensions_mut().insert(AuthUser::from(current_user));
+        Ok(next.run(req).await)
+    } else {
+        Err(ApiError::Unauthorized("Invalid or expired access token".to_string()))
     }
 }
+async fn authorize_current_user(keys: &JwtKeys, auth_token: &str) -> Option<Claims> {
+    // checks signature, kid and expiry
+    keys.decode(auth_token)
+}
@@ -1,42 +1,78 @@
-use std::sync::Arc;
+use std::{ env, sync::Arc };
 
 use axum::Json;
-use car_auction_core::BidState;
+use car_auction_core::{
+    eip712::BidAuthorization,
+    merkle::{ merkle_root, MerklePath },
+    Actor,
+    AuctionType,
+    BidAction,
+    BidLeaf,
+    BidParams,
+    BidState,
+    BidTransition,
+    StateAction,
+};
 use chrono::Utc;
-use entity::{ auction, bid, AuctionModel, BidModel };
-use methods::{ INIT_BID_ELF, INIT_BID_ID };
+use dotenv::dotenv;
+use entity::{
+    auction,
+    auction_extension,
+    bid,
+    car,
+    proxy_bid,
+    AuctionModel,
+    BidModel,
+    Status,
+};
+use methods::{ CREATE_BID_ELF, CREATE_BID_ID, INIT_BID_ELF, INIT_BID_ID };
 use risc0_zkvm::{ default_prover, ExecutorEnv, Receipt };
 use sea_orm::{
     ActiveModelTrait,
+    ConnectionTrait,
     DatabaseConnection,
     DbErr,
     EntityTrait,
     QueryOrder,
+    QuerySelect,
     Set,
     ColumnTrait,
     QueryFilter,
+    TransactionTrait,
 };
 use serde::{ Deserialize, Serialize };
 use serde_json::{ json, Value };
 
-use crate::{ auth::USER, overall::sync_overall_state, SessionStats };
-
-pub fn get_bid_leaves(bids: &Vec<BidModel>) -> Vec<String> {
-    let mut leaves = vec![];
-    // let tree: Vec<&'static str> = vec!["s", "ss"];
-    for bid in bids {
-        let bid_record = format!(
-            "{}:{}:{}:{}:{}:{}",
-            bid.id,
-            bid.auction_id,
-            bid.bidder_id,
-            bid.amount,
-            bid.created_at.and_utc().timestamp(),
-            bid.updated_at.and_utc().timestamp()
-        );
-        leaves.push(bid_record);
+use crate::{
+    auction::{ auction_leaf, auction_type, get_all_auctions, get_auction_leaves },
+    auth::AuthUser,
+    car::{ get_all_cars, get_car_leaves, get_car_state_leaves },
+    eip712::bid_domain,
+    error::ApiError,
+    overall::{ proven_commit, published_state },
+    proxy::run_proxy_bids,
+    sequencer::{ head_commit, lock_head, submit },
+    next_id,
+    SessionStats,
+    PROVE_ATTEMPTS,
+};
+
+pub fn bid_leaf(bid: &BidModel) -> BidLeaf {
+    BidLeaf {
+        id: bid.id,
+        auction_id: bid.auction_id,
+        bidder: bid.bidder_id.clone(),
+        amount: bid.amount as i64,
+        created_at: bid.created_at.and_utc().timestamp(),
+        updated_at: bid.updated_at.and_utc().timestamp(),
+        retracted_at: bid.retracted_at.map(|time| time.and_utc().timestamp()),
     }
-    leaves
+}
+
+pub fn get_bid_leaves(bids: &[BidModel]) -> Vec<String> {
+    bids.iter()
+        .map(|bid| bid_leaf(bid).encode())
+        .collect()
 }
 
 #[derive(Serialize, Deserialize)]
@@ -55,10 +91,16 @@ impl BidCommit {
         self.get_commit()
     }
 }
-pub fn init_bid(leaves: Vec<String>) -> Result<BidCommit, String> {
-    let env = ExecutorEnv::builder().write(&leaves).unwrap().build().unwrap();
+pub fn init_bid(leaves: Vec<String>) -> Result<BidCommit, ApiError> {
+    let env = ExecutorEnv::builder()
+        .write(&leaves)
+        .map_err(|e| ApiError::Proving(e.to_string()))?
+        .build()
+        .map_err(|e| ApiError::Proving(e.to_string()))?;
     let prover = default_prover();
-    let prove_info = prover.prove(env, INIT_BID_ELF).unwrap();
+    let prove_info = prover
+        .prove(env, INIT_BID_ELF)
+        .map_err(|e| ApiError::Proving(e.to_string()))?;
     let auction_commit = BidCommit {
         receipt: prove_info.receipt,
         stats: SessionStats {
@@ -75,58 +117,482 @@ pub fn init_bid(leaves: Vec<String>) -> Result<BidCommit, String> {
 
 pub async fn init_bid_handler(axum::extract::State(
     db,
-): axum::extract::State<Arc<sea_orm::DatabaseConnection>>) -> Result<
-    Json<Value>,
-    (axum::http::StatusCode, String)
-> {
-    let bids = get_all_bids(&db).await.unwrap();
+): axum::extract::State<Arc<sea_orm::DatabaseConnection>>) -> Result<Json<Value>, ApiError> {
+    let bids = get_all_bids(&*db).await?;
     let leaves = get_bid_leaves(&bids);
-    let result = init_bid(leaves).map_err(|e| (
-        axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-        e.to_string(),
-    ))?;
+    let result = init_bid(leaves)?;
     Ok(Json(json!(result)))
 }
 
-pub async fn create_bid(
-    axum::extract::State(db): axum::extract::State<Arc<sea_orm::DatabaseConnection>>,
-    Json(bid_data): Json<BidModel>
-) -> Result<Json<Value>, (axum::http::StatusCode, String)> {
-    let user = USER.get();
-    eprint!("{:?}", user);
-    let bid_id = bid::Entity
+#[derive(Deserialize)]
+pub struct SignedBidPayload {
+    pub auction_id: i32,
+    pub amount: i32,
+    pub nonce: u64,
+    pub deadline: u64,
+    // EIP-712 signature over (auction_id, amount, nonce, deadline), see `/api/bids/typed-data`
+    pub signature: String,
+}
+
+// A raise of a standing bid, signed like a new bid on the same auction.
+#[derive(Deserialize)]
+pub struct RaiseBidPayload {
+    pub amount: i32,
+    pub nonce: u64,
+    pub deadline: u64,
+    pub signature: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BidTransitionCommit {
+    pub receipt: Receipt,
+    pub stats: SessionStats,
+}
+impl BidTransitionCommit {
+    pub fn get_commit(&self) -> Result<BidTransition, String> {
+        let state = self.receipt.journal.decode().map_err(|e| e.to_string())?;
+        Ok(state)
+    }
+    pub fn verify_and_get_commit(&self) -> Result<BidTransition, String> {
+        self.receipt.verify(CREATE_BID_ID).map_err(|e| e.to_string())?;
+        self.get_commit()
+    }
+}
+
+// BID_MIN_INCREMENT: how much a bid must beat the current bid by.
+pub fn min_increment() -> u64 {
+    dotenv().ok();
+    env::var("BID_MIN_INCREMENT")
+        .ok()
+        .and_then(|increment| increment.parse().ok())
+        .unwrap_or(1)
+}
+
+// Proves the new bid against the current auction and bid state; fails if the signature is not
+// the bidder's or the bid is not valid for the auction.
+pub fn prove_bid(params: &BidParams) -> Result<BidTransitionCommit, ApiError> {
+    let env = ExecutorEnv::builder()
+        .write(params)
+        .map_err(|e| ApiError::Proving(e.to_string()))?
+        .build()
+        .map_err(|e| ApiError::Proving(e.to_string()))?;
+    let prover = default_prover();
+    let prove_info = prover
+        .prove(env, CREATE_BID_ELF)
+        .map_err(|e| ApiError::Proving(e.to_string()))?;
+    Ok(BidTransitionCommit {
+        receipt: prove_info.receipt,
+        stats: SessionStats {
+            segments: prove_info.stats.segments,
+            total_cycles: prove_info.stats.total_cycles,
+            user_cycles: prove_info.stats.user_cycles,
+            paging_cycles: prove_info.stats.paging_cycles,
+            reserved_cycles: prove_info.stats.reserved_cycles,
+        },
+    })
+}
+
+// EIP-712 nonces are single use across signed bids and proxy maximums.
+pub async fn nonce_used(db: &DatabaseConnection, bidder: &str, nonce: i64) -> Result<bool, DbErr> {
+    let bid = bid::Entity
+        ::find()
+        .filter(bid::Column::BidderId.eq(bidder))
+        .filter(bid::Column::Nonce.eq(nonce))
+        .one(db).await?;
+    let proxy = proxy_bid::Entity
         ::find()
-        .order_by_desc(bid::Column::Id)
-        .one(&*db).await
-        .unwrap()
-        .unwrap();
-    let now_naive: chrono::NaiveDateTime = Utc::now().naive_utc();
-    let bid_model = bid::ActiveModel {
-        id: Set(bid_id.id + 1),
-        auction_id: Set(bid_data.auction_id),
-        bidder_id: Set(user.addr),
-        amount: Set(bid_data.amount),
-        created_at: Set(now_naive.clone()),
-        updated_at: Set(now_naive),
-        ..Default::default()
+        .filter(proxy_bid::Column::Bidder.eq(bidder))
+        .filter(proxy_bid::Column::Nonce.eq(nonce))
+        .one(db).await?;
+    Ok(bid.is_some() || proxy.is_some())
+}
+
+// A bid validated against the auction as it was read, with the proof input built from it.
+struct PreparedBid {
+    params: BidParams,
+    auction: AuctionModel,
+    // leaves of the auction's bids the proof read, in id order
+    bids: Vec<String>,
+    end_time: chrono::NaiveDateTime,
+    now: chrono::NaiveDateTime,
+}
+
+// Reads and validates the auction in a short transaction that holds its row locked, and takes
+// the bid's id, or the id of the bid it `raises`. The auction is free again while the bid is
+// proven.
+async fn prepare_bid(
+    db: &DatabaseConnection,
+    bidder: &str,
+    authorization: &BidAuthorization,
+    signature: &str,
+    proxy_amount: Option<u64>,
+    raises: Option<i32>,
+    actor: &Actor
+) -> Result<PreparedBid, ApiError> {
+    let amount = i32
+        ::try_from(proxy_amount.unwrap_or(authorization.amount))
+        .map_err(|_| ApiError::Validation("Invalid bid amount".to_string()))?;
+    let now = Utc::now();
+    let now_naive: chrono::NaiveDateTime = now.naive_utc();
+    if authorization.deadline < (now.timestamp() as u64) {
+        return Err(ApiError::Validation("Bid signature expired".to_string()));
+    }
+
+    let auction_id = i32
+        ::try_from(authorization.auction_id)
+        .map_err(|_| ApiError::NotFound("Auction not found".to_string()))?;
+    let txn = db.begin().await?;
+    auction::Entity
+        ::find_by_id(auction_id)
+        .lock_exclusive()
+        .one(&txn).await?
+        .ok_or(ApiError::NotFound("Auction not found".to_string()))?;
+    // the state read from here on is the one this commit publishes
+    let head = head_commit(&txn).await?;
+
+    // the guest enforces these too; checked here to answer with a status instead of a failed proof
+    let auctions = get_all_auctions(&txn).await?;
+    let index = auctions
+        .iter()
+        .position(|auc| auc.id == auction_id)
+        .ok_or(ApiError::NotFound("Auction not found".to_string()))?;
+    let auc = &auctions[index];
+    let format = auction_type(auc.auction_type);
+    if format.is_sealed() {
+        return Err(
+            ApiError::Validation(
+                "Sealed-bid auction, commit through /api/auctions/{id}/sealed-bids".to_string()
+            )
+        );
+    }
+    if auc.status != Some(Status::Active) {
+        return Err(ApiError::Validation("Auction is not active".to_string()));
+    }
+    if now_naive < auc.start_time || now_naive > auc.end_time {
+        return Err(ApiError::Validation("Auction is not open".to_string()));
+    }
+    let car = car::Entity
+        ::find_by_id(auc.car_id)
+        .one(&txn).await?
+        .ok_or(ApiError::NotFound("Car not found".to_string()))?;
+    if amount < car.starting_price {
+        return Err(
+            ApiError::Validation(
+                format!("Bid must be at least the starting price of {}", car.starting_price)
+            )
+        );
+    }
+    let increment = min_increment();
+    let leaf = auction_leaf(auc);
+    let mut buy_now = false;
+    if format == AuctionType::Dutch {
+        // the first bid at or above the clock price buys the car
+        if auc.bid_count > 0 {
+            return Err(ApiError::Conflict("Auction already sold".to_string()));
+        }
+        let price = leaf.dutch_price(now.timestamp()).unwrap_or(auc.current_bid as i64);
+        if (amount as i64) < price {
+            return Err(ApiError::Validation(format!("Bid must be at least {}", price)));
+        }
+    } else {
+        if leaf.bought() {
+            return Err(ApiError::Conflict("Auction already bought".to_string()));
+        }
+        // a bid at the buy-now price skips the increment and closes the auction
+        buy_now = auc.buy_now_price.is_some_and(|price| amount >= price);
+        let increment = auc.min_increment.map(|increment| increment as u64).unwrap_or(increment);
+        let minimum = (auc.current_bid as u64) + increment;
+        if !buy_now && (amount as u64) < minimum {
+            return Err(ApiError::Validation(format!("Bid must be at least {}", minimum)));
+        }
+    }
+
+    // matches the guest: a buy-now bid closes the auction, other english bids may extend it
+    let extended = if format == AuctionType::English && !buy_now {
+        leaf.soft_close_end(now.timestamp())
+    } else {
+        None
+    };
+    let end_time = match (extended, buy_now) {
+        (Some(end), _) =>
+            chrono::DateTime
+                ::from_timestamp(end, 0)
+                .ok_or(ApiError::Internal("Invalid soft-close end time".to_string()))?
+                .naive_utc(),
+        (None, true) => now_naive,
+        (None, false) => auc.end_time,
+    };
+
+    let bids = get_all_bids(&txn).await?;
+    let raises = match raises {
+        Some(id) => {
+            if format != AuctionType::English {
+                return Err(ApiError::Validation("Only english bids can be raised".to_string()));
+            }
+            let raised = bids
+                .iter()
+                .find(|bid| bid.id == id && bid.auction_id == auction_id)
+                .ok_or(ApiError::NotFound("Bid not found".to_string()))?;
+            if !raised.bidder_id.eq_ignore_ascii_case(bidder) {
+                return Err(ApiError::Forbidden("Not your bid".to_string()));
+            }
+            if raised.retracted_at.is_some() {
+                return Err(ApiError::Conflict("Bid was retracted".to_string()));
+            }
+            Some(bid_leaf(raised))
+        }
+        None => None,
+    };
+    // the proof commits the bid's id, so a new one is taken from the sequence before proving
+    let bid_id = match &raises {
+        Some(raised) => raised.id,
+        None => next_id(&txn, "bid").await?,
     };
+    // cars lead the car state, in the order `get_all_cars` returns them
+    let car_index = get_all_cars(&txn).await?
+        .iter()
+        .position(|listed| listed.id == car.id)
+        .ok_or(ApiError::NotFound("Car not found".to_string()))?;
+    let car_leaves = get_car_state_leaves(&txn).await?;
+    txn.commit().await?;
+    let published = published_state(db, head).await?;
+    let auc_leaves = get_auction_leaves(&auctions);
+    let params = BidParams {
+        published,
+        auction: leaf,
+        auction_path: MerklePath::new(&auc_leaves, index).ok_or(
+            ApiError::Internal("Auction is not in the auction state".to_string())
+        )?,
+        auction_root: merkle_root(&auc_leaves),
+        car: car_leaves[car_index].clone(),
+        car_path: MerklePath::new(&car_leaves, car_index).ok_or(
+            ApiError::Internal("Car is not in the car state".to_string())
+        )?,
+        min_increment: increment,
+        bid_leaves: get_bid_leaves(&bids),
+        bid_id,
+        bidder: bidder.to_string(),
+        bid: authorization.clone(),
+        proxy_amount,
+        raises,
+        signature: signature.to_string(),
+        domain: bid_domain(),
+        timestamp: now.timestamp(),
+        actor: actor.clone(),
+    };
+    let auction_bids: Vec<BidModel> = bids
+        .into_iter()
+        .filter(|bid| bid.auction_id == auction_id)
+        .collect();
+    Ok(PreparedBid {
+        params,
+        auction: auc.clone(),
+        bids: get_bid_leaves(&auction_bids),
+        end_time,
+        now: now_naive,
+    })
+}
+
+// Moves the auction and stores a proven bid in one transaction, with its state commit. A raise
+// updates the bid it replaces. The proof decided on the auction's leaf, its car's leaf and its
+// bids, so nothing is written when any of those changed while it ran, and the caller proves the
+// bid again; changes to other auctions do not count. Returns the commit's sequence number.
+async fn apply_bid(
+    db: &DatabaseConnection,
+    prepared: &PreparedBid,
+    bid_commit: &BidTransitionCommit,
+    transition: &BidTransition
+) -> Result<Option<i64>, ApiError> {
+    let params = &prepared.params;
+    let auc = &prepared.auction;
+    let now_naive = prepared.now;
+    let proxy = params.proxy_amount.is_some();
+    let txn = db.begin().await?;
+    // the row first, as every writer locks it before queuing, then the submit lock, so nothing
+    // is queued between the checks below and this commit
+    let current = auction::Entity
+        ::find_by_id(auc.id)
+        .lock_exclusive()
+        .one(&txn).await?
+        .ok_or(ApiError::NotFound("Auction not found".to_string()))?;
+    lock_head(&txn).await?;
+    let car = car::Entity
+        ::find_by_id(current.car_id)
+        .one(&txn).await?
+        .ok_or(ApiError::NotFound("Car not found".to_string()))?;
+    let bids = bid::Entity
+        ::find()
+        .filter(bid::Column::AuctionId.eq(auc.id))
+        .order_by_asc(bid::Column::Id)
+        .all(&txn).await?;
+    if
+        auction_leaf(&current) != params.auction ||
+        get_car_leaves(&vec![car]).remove(0) != params.car ||
+        get_bid_leaves(&bids) != prepared.bids
+    {
+        return Ok(None);
+    }
 
-    bid_model
-        .insert(&*db).await
-        .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-    if let Some(auc) = auction::Entity::find_by_id(bid_data.auction_id).one(&*db).await.unwrap() {
-        // 2. Convert it into an ActiveModel
-        let mut auc_model: auction::ActiveModel = auc.clone().into();
+    let raise = params.raises.is_some();
+    let bid_count = if raise { auc.bid_count } else { auc.bid_count + 1 };
+    auction::Entity
+        ::update_many()
+        .col_expr(auction::Column::CurrentBid, (transition.amount as i32).into())
+        .col_expr(auction::Column::BidCount, bid_count.into())
+        .col_expr(auction::Column::EndTime, prepared.end_time.into())
+        .col_expr(auction::Column::UpdatedAt, now_naive.into())
+        .filter(auction::Column::Id.eq(auc.id))
+        .exec(&txn).await?;
 
-        // 3. Update the field(s)
-        auc_model.current_bid = Set(bid_data.amount); // example status
-        auc_model.bid_count = Set(auc.bid_count + 1);
+    if raise {
+        bid::Entity
+            ::update_many()
+            .col_expr(bid::Column::Amount, (transition.amount as i32).into())
+            .col_expr(bid::Column::Nonce, Some(params.bid.nonce as i64).into())
+            .col_expr(bid::Column::Deadline, Some(params.bid.deadline as i64).into())
+            .col_expr(bid::Column::Signature, Some(params.signature.clone()).into())
+            // raised by the bidder's own signature, even if a proxy placed it
+            .col_expr(bid::Column::Proxy, false.into())
+            .col_expr(bid::Column::UpdatedAt, now_naive.into())
+            .filter(bid::Column::Id.eq(params.bid_id))
+            .exec(&txn).await?;
+    } else {
+        let bid_model = bid::ActiveModel {
+            id: Set(params.bid_id),
+            auction_id: Set(auc.id),
+            bidder_id: Set(params.bidder.clone()),
+            amount: Set(transition.amount as i32),
+            // a proxy bid's authorization stays with its private maximum in `proxy_bid`
+            nonce: Set((!proxy).then_some(params.bid.nonce as i64)),
+            deadline: Set(Some(params.bid.deadline as i64)),
+            signature: Set((!proxy).then(|| params.signature.clone())),
+            proxy: Set(proxy),
+            created_at: Set(now_naive),
+            updated_at: Set(now_naive),
+            retracted_at: Set(None),
+            retraction_reason: Set(None),
+        };
 
-        // 4. Save the updated model
-        auc_model.update(&*db).await.unwrap();
+        bid_model
+            .insert(&txn).await?;
     }
 
-    let (hash, commit) = sync_overall_state(db).await.unwrap();
+    // the receipt proves why `end_time` moved
+    if let Some(extension) = &transition.extension {
+        auction_extension::ActiveModel {
+            auction_id: Set(extension.auction_id),
+            bid_id: Set(extension.bid_id),
+            old_end_time: Set(auc.end_time),
+            new_end_time: Set(prepared.end_time),
+            old_auction_root: Set(transition.auction_root.old.to_string()),
+            new_auction_root: Set(transition.auction_root.new.to_string()),
+            receipt: Set(serde_json::to_value(&bid_commit.receipt).ok()),
+            created_at: Set(now_naive),
+            ..Default::default()
+        }
+            .insert(&txn).await?;
+    }
+    let action = Some(StateAction::Bid(if raise { BidAction::UPDATE } else { BidAction::CREATE }));
+    let sequence = submit(&txn, &params.actor, action.as_ref()).await?;
+    txn.commit().await?;
+    Ok(Some(sequence))
+}
+
+// Validates, proves and stores a bid. Shared by signed bids and the proxy engine, which passes
+// the bidder's signed maximum with the `proxy_amount` to place under it. The auction is only
+// locked while it is read and while the bid is written, not during the proof; a bid whose
+// auction or bid tree moved in between is proven again, up to `PROVE_ATTEMPTS` times. The bid
+// is queued as a state commit with its writes; callers wait for the returned sequence number
+// to be proven. A bidder raising their own english bid passes its id as `raises`.
+pub async fn place_bid(
+    db: &DatabaseConnection,
+    bidder: &str,
+    authorization: &BidAuthorization,
+    signature: &str,
+    proxy_amount: Option<u64>,
+    raises: Option<i32>,
+    actor: Actor
+) -> Result<(BidTransitionCommit, BidTransition, i64), ApiError> {
+    for _ in 0..PROVE_ATTEMPTS {
+        let prepared = prepare_bid(
+            db,
+            bidder,
+            authorization,
+            signature,
+            proxy_amount,
+            raises,
+            &actor
+        ).await?;
+        let params = prepared.params.clone();
+        let bid_commit = tokio::task
+            ::spawn_blocking(move || prove_bid(&params)).await
+            .map_err(|e| ApiError::Internal(e.to_string()))??;
+        let transition = bid_commit
+            .get_commit()
+            .map_err(ApiError::Proving)?;
+        if let Some(sequence) = apply_bid(db, &prepared, &bid_commit, &transition).await? {
+            return Ok((bid_commit, transition, sequence));
+        }
+    }
+    Err(ApiError::Conflict("Auction kept changing while the bid was proven".to_string()))
+}
+
+// Checks a signed bid natively, so a bad signature fails fast instead of failing the proof.
+async fn authorize_bid(
+    db: &DatabaseConnection,
+    user: &AuthUser,
+    bid_data: &SignedBidPayload
+) -> Result<BidAuthorization, ApiError> {
+    if bid_data.amount <= 0 {
+        return Err(ApiError::Validation("Invalid bid amount".to_string()));
+    }
+    let nonce = i64
+        ::try_from(bid_data.nonce)
+        .map_err(|_| ApiError::Validation("Invalid bid nonce".to_string()))?;
+    let now = Utc::now();
+    if bid_data.deadline < (now.timestamp() as u64) {
+        return Err(ApiError::Validation("Bid signature expired".to_string()));
+    }
+
+    let domain = bid_domain();
+    let authorization = BidAuthorization {
+        auction_id: bid_data.auction_id as u64,
+        amount: bid_data.amount as u64,
+        nonce: bid_data.nonce,
+        deadline: bid_data.deadline,
+    };
+    let signer = authorization
+        .recover_signer(&domain, &bid_data.signature)
+        .map_err(|e| ApiError::Validation(e.to_string()))?;
+    if !signer.eq_ignore_ascii_case(&user.addr) {
+        return Err(ApiError::Unauthorized("Bid not signed by the bidder".to_string()));
+    }
+    let used = nonce_used(db, &user.addr, nonce).await?;
+    if used {
+        return Err(ApiError::Conflict("Bid nonce already used".to_string()));
+    }
+    Ok(authorization)
+}
+
+pub async fn create_bid(
+    user: AuthUser,
+    axum::extract::State(db): axum::extract::State<Arc<sea_orm::DatabaseConnection>>,
+    Json(bid_data): Json<SignedBidPayload>
+) -> Result<Json<Value>, ApiError> {
+    let authorization = authorize_bid(&db, &user, &bid_data).await?;
+    let (bid_commit, transition, sequence) = place_bid(
+        &db,
+        &user.addr,
+        &authorization,
+        &bid_data.signature,
+        None,
+        None,
+        user.actor()
+    ).await?;
+    // outbid proxies answer in the background
+    tokio::spawn(run_proxy_bids(db.clone(), bid_data.auction_id));
+
+    let (hash, commit) = proven_commit(&db, sequence).await?;
     Ok(
         Json(
             json!({
@@ -134,21 +600,86 @@ pub async fn create_bid(
     "message": "bid created succesfully",
     "cid": hash,
     "receipt": commit.receipt,
-    "stats": commit.stats
+    "stats": commit.stats,
+    "bid_receipt": bid_commit.receipt,
+    "bid_stats": bid_commit.stats,
+    "auction_root": transition.auction_root,
+    "bid_root": transition.bid_root,
+    "buy_now": transition.buy_now,
+    "extension": transition.extension
   })
         )
     )
 }
+
+// PUT /api/bids/{id}
+// Raises one of the caller's standing english bids, with a new signature over the bid's
+// auction and the new amount. The raise must beat the current bid like a new bid does, keeps the
+// bid's id and place, and is committed as a bid UPDATE.
+pub async fn update_bid_handler(
+    user: AuthUser,
+    axum::extract::Path(id): axum::extract::Path<i32>,
+    axum::extract::State(db): axum::extract::State<Arc<sea_orm::DatabaseConnection>>,
+    Json(payload): Json<RaiseBidPayload>
+) -> Result<Json<Value>, ApiError> {
+    let bid = bid::Entity
+        ::find_by_id(id)
+        .one(&*db).await?
+        .ok_or(ApiError::NotFound("Bid not found".to_string()))?;
+    if !bid.bidder_id.eq_ignore_ascii_case(&user.addr) {
+        return Err(ApiError::Forbidden("Not your bid".to_string()));
+    }
+    if bid.retracted_at.is_some() {
+        return Err(ApiError::Conflict("Bid was retracted".to_string()));
+    }
+    if payload.amount <= bid.amount {
+        return Err(ApiError::Validation("Raise must be above the bid".to_string()));
+    }
+    let bid_data = SignedBidPayload {
+        auction_id: bid.auction_id,
+        amount: payload.amount,
+        nonce: payload.nonce,
+        deadline: payload.deadline,
+        signature: payload.signature,
+    };
+    let authorization = authorize_bid(&db, &user, &bid_data).await?;
+    let (bid_commit, transition, sequence) = place_bid(
+        &db,
+        &user.addr,
+        &authorization,
+        &bid_data.signature,
+        None,
+        Some(bid.id),
+        user.actor()
+    ).await?;
+    tokio::spawn(run_proxy_bids(db.clone(), bid.auction_id));
+
+    let (hash, commit) = proven_commit(&db, sequence).await?;
+    Ok(
+        Json(
+            json!({
+    "status": "success",
+    "message": "bid raised",
+    "cid": hash,
+    "receipt": commit.receipt,
+    "stats": commit.stats,
+    "bid_receipt": bid_commit.receipt,
+    "bid_stats": bid_commit.stats,
+    "auction_root": transition.auction_root,
+    "bid_root": transition.bid_root,
+    "buy_now": transition.buy_now,
+    "extension": transition.extension
+  })
+        )
+    )
+}
+
 pub async fn get_bids(axum::extract::State(
     db,
-): axum::extract::State<Arc<sea_orm::DatabaseConnection>>) -> Result<
-    Json<Value>,
-    (axum::http::StatusCode, String)
-> {
+): axum::extract::State<Arc<sea_orm::DatabaseConnection>>) -> Result<Json<Value>, ApiError> {
     let bids = bid::Entity
         ::find()
-        .all(&*db).await
-        .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .all(&*db).await?;
     Ok(Json(json!({
     "status": "success",
     "data": bids
@@ -158,37 +689,35 @@ pub async fn get_bids(axum::extract::State(
 pub async fn get_bid_by_id(
     axum::extract::Path(id): axum::extract::Path<i32>,
     axum::extract::State(db): axum::extract::State<Arc<sea_orm::DatabaseConnection>>
-) -> Result<Json<Value>, (axum::http::StatusCode, String)> {
+) -> Result<Json<Value>, ApiError> {
     let bid = bid::Entity
         ::find_by_id(id)
-        .one(&*db).await
-        .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .one(&*db).await?;
     match bid {
         Some(bid) => Ok(Json(json!({
     "status": "success",
     "data": bid
   }))),
-        None => Err((axum::http::StatusCode::NOT_FOUND, "Bid not found".to_string())),
+        None => Err(ApiError::NotFound("Bid not found".to_string())),
     }
 }
 pub async fn get_bid_by_auction_id(
     axum::extract::Path(id): axum::extract::Path<i32>,
     axum::extract::State(db): axum::extract::State<Arc<sea_orm::DatabaseConnection>>
-) -> Result<Json<Value>, (axum::http::StatusCode, String)> {
+) -> Result<Json<Value>, ApiError> {
     let bid: Vec<BidModel> = bid::Entity
         ::find()
         .filter(bid::Column::AuctionId.eq(id))
-        .all(&*db).await
-        .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .all(&*db).await?;
     // match bid {
     // Some(bid) =>
     Ok(Json(json!({
     "status": "success",
     "data": bid
   })))
-    //     None => Err((axum::http::StatusCode::NOT_FOUND, "Bid not found".to_string())),
+    //     None => Err(ApiError::NotFound("Bid not found".to_string())),
     // }
 }
-pub async fn get_all_bids(db: &DatabaseConnection) -> Result<Vec<::entity::bid::Model>, DbErr> {
-    ::entity::bid::Entity::find().all(db).await
+pub async fn get_all_bids<C: ConnectionTrait>(db: &C) -> Result<Vec<::entity::bid::Model>, DbErr> {
+    ::entity::bid::Entity::find().order_by_asc(bid::Column::Id).all(db).await
 }
@@ -1,24 +1,39 @@
-use axum::{ http::StatusCode, Json };
-use car_auction_core::CarState;
+use axum::Json;
+use car_auction_core::{ CarAction, CarState, StateAction };
 use chrono::Utc;
 // use db::car::get_all_cars;
-use entity::{ car, CarModel };
-use ethers::types::Address;
+use entity::{ auction, car, CarModel, Role, Status };
 use methods::{ INIT_CAR_ELF, INIT_CAR_ID };
 use risc0_zkvm::{ default_prover, ExecutorEnv, Receipt };
-use sea_orm::{ ActiveModelTrait, DatabaseConnection, DbErr, EntityTrait, QueryOrder };
+use sea_orm::{
+    ActiveModelTrait,
+    ColumnTrait,
+    ConnectionTrait,
+    DbErr,
+    EntityTrait,
+    QueryFilter,
+    QueryOrder,
+    TransactionTrait,
+};
 use serde::{ Deserialize, Serialize };
 use serde_json::{ Value, json };
 use std::sync::Arc;
 
-use crate::{ auth::USER, jwt::Claims, SessionStats };
+use crate::{
+    auth::AuthUser,
+    error::ApiError,
+    offer::{ get_all_offers, get_offer_leaves },
+    overall::proven_commit,
+    sequencer::submit,
+    SessionStats,
+};
 
 pub fn get_car_leaves(cars: &Vec<CarModel>) -> Vec<String> {
     let mut leaves = vec![];
     // let tree: Vec<&'static str> = vec!["s", "ss"];
     for car in cars {
         let car_record = format!(
-            "{}:{}:{}:{}:{}:{}:{}:{}:{}:{}:{}:{}:{}:{}:{:?}:{}:{}:{}:{:?}:{}:{}",
+            "{}:{}:{}:{}:{}:{}:{}:{}:{}:{}:{}:{}:{}:{}:{:?}:{}:{}:{}:{:?}:{}:{}:{}:{}",
             car.id,
             car.make,
             car.model,
@@ -39,7 +54,10 @@ pub fn get_car_leaves(cars: &Vec<CarModel>) -> Vec<String> {
             car.current_price,
             car.auction_status.clone(),
             car.created_at.and_utc().timestamp(),
-            car.updated_at.and_utc().timestamp()
+            car.updated_at.and_utc().timestamp(),
+            car.token_id,
+            // last, so the transfer_car guest can move it without parsing the rest
+            car.owner
         );
         leaves.push(car_record);
     }
@@ -63,10 +81,16 @@ impl CarCommit {
     }
 }
 
-pub fn init_car(leaves: Vec<String>) -> Result<CarCommit, String> {
-    let env = ExecutorEnv::builder().write(&leaves).unwrap().build().unwrap();
+pub fn init_car(leaves: Vec<String>) -> Result<CarCommit, ApiError> {
+    let env = ExecutorEnv::builder()
+        .write(&leaves)
+        .map_err(|e| ApiError::Proving(e.to_string()))?
+        .build()
+        .map_err(|e| ApiError::Proving(e.to_string()))?;
     let prover = default_prover();
-    let prove_info = prover.prove(env, INIT_CAR_ELF).unwrap();
+    let prove_info = prover
+        .prove(env, INIT_CAR_ELF)
+        .map_err(|e| ApiError::Proving(e.to_string()))?;
     let car_commit = CarCommit {
         receipt: prove_info.receipt,
         stats: SessionStats {
@@ -83,52 +107,28 @@ pub fn init_car(leaves: Vec<String>) -> Result<CarCommit, String> {
 
 pub async fn init_car_handler(axum::extract::State(
     db,
-): axum::extract::State<Arc<sea_orm::DatabaseConnection>>) -> Result<
-    Json<Value>,
-    (axum::http::StatusCode, String)
-> {
-    let cars = get_all_cars(&db).await.unwrap();
-    let leaves = get_car_leaves(&cars);
-    let result = init_car(leaves).map_err(|e| (
-        axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-        e.to_string(),
-    ))?;
+): axum::extract::State<Arc<sea_orm::DatabaseConnection>>) -> Result<Json<Value>, ApiError> {
+    let leaves = get_car_state_leaves(&*db).await?;
+    let result = init_car(leaves)?;
     Ok(Json(json!(result)))
 }
 
 #[axum::debug_handler]
 pub async fn create_car(
+    user: AuthUser,
     axum::extract::State(db): axum::extract::State<Arc<sea_orm::DatabaseConnection>>,
     Json(car_data): Json<CarModel>
-) -> Result<Json<Value>, (axum::http::StatusCode, String)> {
-    // Access the user from the task_local
-
-    let current_user = USER.with(|user| Claims {
-        username: user.username.clone(),
-        addr: user.addr
-            .clone()
-            .parse::<Address>()
-            .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid address".to_string()))
-            .unwrap()
-            .to_string(),
-        exp: user.exp,
-    });
-
-    // You can now use the user's data.
+) -> Result<Json<Value>, ApiError> {
+    // The user's data comes from the access token.
     // For example, let's print the user's address and set it as the car owner.
-    eprintln!("Request from user: {:?}", current_user.addr);
-    eprintln!("Request from username: {}", current_user.username);
+    eprintln!("Request from user: {:?}", user.addr);
+    eprintln!("Request from username: {}", user.username);
 
     use sea_orm::ActiveValue::Set;
-    let car_id = car::Entity
-        ::find()
-        .order_by_desc(car::Column::Id)
-        .one(&*db).await
-        .unwrap()
-        .unwrap();
+    let actor = user.actor();
     let now_naive: chrono::NaiveDateTime = Utc::now().naive_utc();
+    // the id comes from the table's sequence
     let car_model = car::ActiveModel {
-        id: Set(car_id.id + 1),
         make: Set(car_data.make.to_owned()),
         model: Set(car_data.model.to_owned()),
         year: Set(car_data.year),
@@ -159,57 +159,192 @@ pub async fn create_car(
         highlight: Set(car_data.highlight.clone()), // Option<Vec<String>>
         token_id: Set(car_data.token_id),
         // Set the owner to the address from the JWT
-        owner: Set(current_user.addr),
-        created_at: Set(now_naive.clone()),
+        owner: Set(user.addr),
+        created_at: Set(now_naive),
         updated_at: Set(now_naive),
         ..Default::default()
     };
 
+    let txn = db.begin().await?;
     car_model
-        .insert(&*db).await
-        .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .insert(&txn).await?;
+    let action = Some(StateAction::Car(CarAction::CREATE));
+    let sequence = submit(&txn, &actor, action.as_ref()).await?;
+    txn.commit().await?;
 
-    Ok(Json(json!({
+    let (hash, commit) = proven_commit(&db, sequence).await?;
+    Ok(
+        Json(
+            json!({
+      "status": "success",
+      "message": "Car inserted successfully",
+      "cid": hash,
+      "receipt": commit.receipt,
+      "stats": commit.stats
+    })
+        )
+    )
+}
+// Listing fields of a car. Ownership, token and auction fields only change through their own
+// flows.
+#[derive(Deserialize)]
+pub struct CarUpdatePayload {
+    pub make: Option<String>,
+    pub model: Option<String>,
+    pub year: Option<i32>,
+    pub color: Option<String>,
+    pub mileage: Option<i32>,
+    pub vin: Option<String>,
+    pub transmission: Option<String>,
+    pub fuel_type: Option<String>,
+    pub engine_size: Option<String>,
+    pub exterior_color: Option<String>,
+    pub interior_color: Option<String>,
+    pub odometer: Option<i32>,
+    pub description: Option<String>,
+    pub image_url: Option<Vec<String>>,
+    pub starting_price: Option<i32>,
+    pub summary: Option<String>,
+    pub report: Option<Value>,
+    pub included: Option<Value>,
+    pub features: Option<Value>,
+    pub vehicale_overview: Option<String>,
+    pub location: Option<String>,
+    pub seller_type: Option<String>,
+    pub lot: Option<String>,
+    pub highlight: Option<Vec<String>>,
+}
+
+// PUT /api/cars/{id}
+// Edits a car's listing (owner or admin) and commits the change as a car UPDATE. A car whose
+// auction has bids cannot change, and its starting price is fixed while it is in an auction.
+pub async fn update_car_handler(
+    user: AuthUser,
+    axum::extract::Path(id): axum::extract::Path<i32>,
+    axum::extract::State(db): axum::extract::State<Arc<sea_orm::DatabaseConnection>>,
+    Json(payload): Json<CarUpdatePayload>
+) -> Result<Json<Value>, ApiError> {
+    let car = car::Entity
+        ::find_by_id(id)
+        .one(&*db).await?
+        .ok_or(ApiError::NotFound("Car not found".to_string()))?;
+    if !car.owner.eq_ignore_ascii_case(&user.addr) && !user.has_role(Role::Admin) {
+        return Err(ApiError::Forbidden("Not owner".to_string()));
+    }
+    let listed = auction::Entity
+        ::find_by_id(car.auction_id)
+        .one(&*db).await?
+        .filter(|auc| matches!(auc.status, Some(Status::Pending | Status::Active)));
+    if let Some(auc) = &listed {
+        if auc.bid_count > 0 {
+            return Err(ApiError::Conflict("Car cannot change after the first bid".to_string()));
+        }
+        if payload.starting_price.is_some_and(|price| price != car.starting_price) {
+            return Err(
+                ApiError::Conflict(
+                    "Starting price cannot change while the car is in an auction".to_string()
+                )
+            );
+        }
+    }
+
+    let updated = CarModel {
+        make: payload.make.unwrap_or(car.make.clone()),
+        model: payload.model.unwrap_or(car.model.clone()),
+        year: payload.year.unwrap_or(car.year),
+        color: payload.color.unwrap_or(car.color.clone()),
+        mileage: payload.mileage.unwrap_or(car.mileage),
+        vin: payload.vin.unwrap_or(car.vin.clone()),
+        transmission: payload.transmission.unwrap_or(car.transmission.clone()),
+        fuel_type: payload.fuel_type.unwrap_or(car.fuel_type.clone()),
+        engine_size: payload.engine_size.unwrap_or(car.engine_size.clone()),
+        exterior_color: payload.exterior_color.unwrap_or(car.exterior_color.clone()),
+        interior_color: payload.interior_color.unwrap_or(car.interior_color.clone()),
+        odometer: payload.odometer.unwrap_or(car.odometer),
+        description: payload.description.unwrap_or(car.description.clone()),
+        image_url: payload.image_url.or(car.image_url.clone()),
+        starting_price: payload.starting_price.unwrap_or(car.starting_price),
+        summary: payload.summary.unwrap_or(car.summary.clone()),
+        report: payload.report.unwrap_or(car.report.clone()),
+        included: payload.included.unwrap_or(car.included.clone()),
+        features: payload.features.unwrap_or(car.features.clone()),
+        vehicale_overview: payload.vehicale_overview.unwrap_or(car.vehicale_overview.clone()),
+        location: payload.location.unwrap_or(car.location.clone()),
+        seller_type: payload.seller_type.unwrap_or(car.seller_type.clone()),
+        lot: payload.lot.unwrap_or(car.lot.clone()),
+        highlight: payload.highlight.or(car.highlight.clone()),
+        updated_at: Utc::now().naive_utc(),
+        ..car.clone()
+    };
+    // only over the row as read, so an owner change or a new auction in between is not undone
+    let txn = db.begin().await?;
+    let updated = car::Entity
+        ::update(car::ActiveModel::from(updated).reset_all())
+        .filter(car::Column::Owner.eq(car.owner.clone()))
+        .filter(car::Column::AuctionId.eq(car.auction_id))
+        .exec(&txn).await
+        .map_err(|e| match e {
+            DbErr::RecordNotUpdated =>
+                ApiError::Conflict("Car changed while updating".to_string()),
+            e => ApiError::Db(e),
+        })?;
+
+    let action = Some(StateAction::Car(CarAction::UPDATE));
+    let sequence = submit(&txn, &user.actor(), action.as_ref()).await?;
+    txn.commit().await?;
+
+    let (hash, commit) = proven_commit(&db, sequence).await?;
+    Ok(
+        Json(
+            json!({
       "status": "success",
-      "message": "Car inserted successfully"
-  })))
+      "message": "car updated",
+      "data": updated,
+      "cid": hash,
+      "receipt": commit.receipt,
+      "stats": commit.stats
+    })
+        )
+    )
 }
+
 // Handler to get a car by ID
 pub async fn get_car_by_id(
     axum::extract::Path(id): axum::extract::Path<i32>,
     axum::extract::State(db): axum::extract::State<Arc<sea_orm::DatabaseConnection>>
-) -> Result<Json<Value>, (axum::http::StatusCode, String)> {
+) -> Result<Json<Value>, ApiError> {
     let car = car::Entity
         ::find_by_id(id)
-        .one(&*db).await
-        .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .one(&*db).await?;
 
     match car {
         Some(car) => Ok(Json(json!({
         "status": "success",
         "data": car
     }))),
-        None => Err((axum::http::StatusCode::NOT_FOUND, "Car not found".to_string())),
+        None => Err(ApiError::NotFound("Car not found".to_string())),
     }
 }
 
 // Handler to get all cars
 pub async fn get_all_cars_handler(axum::extract::State(
     db,
-): axum::extract::State<Arc<sea_orm::DatabaseConnection>>) -> Result<
-    Json<Value>,
-    (axum::http::StatusCode, String)
-> {
-    let cars = get_all_cars(&*db).await.map_err(|e| (
-        axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-        e.to_string(),
-    ))?;
+): axum::extract::State<Arc<sea_orm::DatabaseConnection>>) -> Result<Json<Value>, ApiError> {
+    let cars = get_all_cars(&*db).await?;
 
     Ok(Json(json!({
     "status": "success",
     "data": cars
 })))
 }
-pub async fn get_all_cars(db: &DatabaseConnection) -> Result<Vec<::entity::car::Model>, DbErr> {
-    ::entity::car::Entity::find().all(db).await
+pub async fn get_all_cars<C: ConnectionTrait>(db: &C) -> Result<Vec<::entity::car::Model>, DbErr> {
+    // ordered so leaf positions, and so Merkle paths, are stable between calls
+    ::entity::car::Entity::find().order_by_asc(car::Column::Id).all(db).await
+}
+
+// Leaves of the car state: every car, then every offer made on them.
+pub async fn get_car_state_leaves<C: ConnectionTrait>(db: &C) -> Result<Vec<String>, DbErr> {
+    let cars = get_all_cars(db).await?;
+    let offers = get_all_offers(db).await?;
+    Ok([get_car_leaves(&cars), get_offer_leaves(&offers)].concat())
 }
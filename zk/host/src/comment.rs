@@ -1,25 +1,21 @@
 use ::entity::{ comment, CommentModel };
-use sea_orm::{ ActiveModelTrait, ColumnTrait, QueryFilter, QueryOrder, Set };
+use sea_orm::{ ActiveModelTrait, ColumnTrait, QueryFilter, Set };
 use axum::{ response::{ Json } };
 use sea_orm::{ EntityTrait };
 use serde_json::{ Value, json };
 use std::sync::Arc;
 
-use crate::auth::USER;
+use crate::auth::AuthUser;
+use entity::Role;
+use serde::Deserialize;
+use crate::error::ApiError;
 
 pub async fn create_comment(
+    user: AuthUser,
     axum::extract::State(db): axum::extract::State<Arc<sea_orm::DatabaseConnection>>,
     Json(com_data): Json<CommentModel>
-) -> Result<Json<Value>, (axum::http::StatusCode, String)> {
-    let com_id = comment::Entity
-        ::find()
-        .order_by_desc(comment::Column::Id)
-        .one(&*db).await
-        .unwrap()
-        .unwrap();
-    let user = USER.get();
+) -> Result<Json<Value>, ApiError> {
     let com_model = comment::ActiveModel {
-        id: Set(com_id.id + 1),
         auction_id: Set(com_data.auction_id),
         user: Set(user.addr),
         content: Set(com_data.content),
@@ -29,22 +25,81 @@ pub async fn create_comment(
     };
 
     com_model
-        .insert(&*db).await
-        .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .insert(&*db).await?;
     Ok(Json(json!({
     "status": "success",
     "message": "Comment created succesfully"
   })))
 }
+#[derive(Deserialize)]
+pub struct CommentUpdatePayload {
+    pub content: String,
+}
+
+// The comment, if the caller wrote it. Admins may also moderate it.
+async fn find_own_comment(
+    db: &sea_orm::DatabaseConnection,
+    id: i32,
+    user: &AuthUser
+) -> Result<CommentModel, ApiError> {
+    let com = comment::Entity
+        ::find_by_id(id)
+        .one(db).await?
+        .ok_or(ApiError::NotFound("Comment not found".to_string()))?;
+    if !com.user.eq_ignore_ascii_case(&user.addr) && !user.has_role(Role::Admin) {
+        return Err(ApiError::Forbidden("Not the author".to_string()));
+    }
+    Ok(com)
+}
+
+// PUT /api/comments/{id}
+// Comments are not part of the committed state, so edits need no proof.
+pub async fn update_comment(
+    user: AuthUser,
+    axum::extract::Path(id): axum::extract::Path<i32>,
+    axum::extract::State(db): axum::extract::State<Arc<sea_orm::DatabaseConnection>>,
+    Json(payload): Json<CommentUpdatePayload>
+) -> Result<Json<Value>, ApiError> {
+    if payload.content.trim().is_empty() {
+        return Err(ApiError::Validation("Comment is empty".to_string()));
+    }
+    let com = find_own_comment(&db, id, &user).await?;
+    let mut com: comment::ActiveModel = com.into();
+    com.content = Set(payload.content);
+    com.updated_at = Set(chrono::Utc::now().naive_utc());
+    let com = com
+        .update(&*db).await?;
+    Ok(Json(json!({
+    "status": "success",
+    "message": "Comment updated",
+    "data": com
+  })))
+}
+
+// DELETE /api/comments/{id}
+pub async fn delete_comment(
+    user: AuthUser,
+    axum::extract::Path(id): axum::extract::Path<i32>,
+    axum::extract::State(db): axum::extract::State<Arc<sea_orm::DatabaseConnection>>
+) -> Result<Json<Value>, ApiError> {
+    find_own_comment(&db, id, &user).await?;
+    comment::Entity
+        ::delete_by_id(id)
+        .exec(&*db).await?;
+    Ok(Json(json!({
+    "status": "success",
+    "message": "Comment deleted"
+  })))
+}
+
 pub async fn get_comments(
     axum::extract::State(db): axum::extract::State<Arc<sea_orm::DatabaseConnection>>,
     axum::extract::Path(id): axum::extract::Path<u32>
-) -> Result<Json<Value>, (axum::http::StatusCode, String)> {
+) -> Result<Json<Value>, ApiError> {
     let comments = comment::Entity
         ::find()
         .filter(comment::Column::AuctionId.eq(id)) // Convert u32 to i32 for the filter
-        .all(&*db).await
-        .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .all(&*db).await?;
     Ok(Json(json!({
     "status": "success",
     "data": comments
@@ -54,17 +109,17 @@ pub async fn get_comments(
 // pub async fn get_bid_by_id(
 //     axum::extract::Path(id): axum::extract::Path<i32>,
 //     axum::extract::State(db): axum::extract::State<Arc<sea_orm::DatabaseConnection>>
-// ) -> Result<Json<Value>, (axum::http::StatusCode, String)> {
+// ) -> Result<Json<Value>, ApiError> {
 //     let bid = bid::Entity
 //         ::find_by_id(id)
 //         .one(&*db).await
-//         .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+//?;
 //     match bid {
 //         Some(bid) => Ok(Json(json!({
 //     "status": "success",
 //     "data": bid
 //   }))),
-//         None => Err((axum::http::StatusCode::NOT_FOUND, "Bid not found".to_string())),
+//         None => Err(ApiError::NotFound("Bid not found".to_string())),
 //     }
 // }
 // pub async fn get_all_bids(db: &DatabaseConnection) -> Result<Vec<::entity::bid::Model>, DbErr> {
@@ -0,0 +1,68 @@
+use std::{ env, sync::Arc };
+
+use dotenv::dotenv;
+use ethers::{
+    contract::abigen,
+    providers::{ Http, Middleware, Provider },
+    types::{ Address, Bytes },
+    utils::hash_message,
+};
+
+abigen!(
+    Erc1271,
+    r#"[
+        function isValidSignature(bytes32 hash, bytes signature) external view returns (bytes4)
+    ]"#
+);
+
+// bytes4(keccak256("isValidSignature(bytes32,bytes)"))
+const MAGIC_VALUE: [u8; 4] = [0x16, 0x26, 0xba, 0x7e];
+
+// Signature checks for contract wallets (Safe and other EIP-1271 accounts) through ETH_RPC_URL.
+// Contract wallets cannot be recovered with ecrecover, so their logins are checked on chain
+// instead of in the verify guest.
+pub struct ContractWallets {
+    provider: Arc<Provider<Http>>,
+}
+
+impl ContractWallets {
+    // None when ETH_RPC_URL is unset, in which case every address is treated as an EOA.
+    pub fn from_env() -> Option<Self> {
+        dotenv().ok();
+        let url = env::var("ETH_RPC_URL").ok()?;
+        match Provider::<Http>::try_from(url) {
+            Ok(provider) => Some(ContractWallets { provider: Arc::new(provider) }),
+            Err(e) => {
+                eprintln!("Invalid ETH_RPC_URL: {}", e);
+                None
+            }
+        }
+    }
+
+    pub async fn is_contract(&self, address: Address) -> Result<bool, String> {
+        let code = self.provider.get_code(address, None).await.map_err(|e| e.to_string())?;
+        Ok(!code.is_empty())
+    }
+
+    // Asks the wallet whether `signature` is valid for the EIP-191 hash of `message`.
+    // Returns the block the check was made at.
+    pub async fn verify_message(
+        &self,
+        address: Address,
+        message: &str,
+        signature: &str
+    ) -> Result<u64, String> {
+        let signature: Bytes = signature.parse().map_err(|_| "Invalid signature hex".to_string())?;
+        let block = self.provider.get_block_number().await.map_err(|e| e.to_string())?;
+        let wallet = Erc1271::new(address, self.provider.clone());
+        let result = wallet
+            .is_valid_signature(hash_message(message).to_fixed_bytes(), signature)
+            .block(block)
+            .call().await
+            .map_err(|e| e.to_string())?;
+        if result != MAGIC_VALUE {
+            return Err("Contract wallet rejected the signature".to_string());
+        }
+        Ok(block.as_u64())
+    }
+}
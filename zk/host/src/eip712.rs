@@ -0,0 +1,61 @@
+use std::env;
+
+use axum::Json;
+use car_auction_core::eip712::Eip712Domain;
+use dotenv::dotenv;
+use serde_json::{ json, Value };
+
+// EIP-712 domain bids are signed under. Read from EIP712_NAME, EIP712_VERSION, EIP712_CHAIN_ID
+// (defaults to SIWE_CHAIN_ID) and EIP712_VERIFYING_CONTRACT, so a bid signature cannot be
+// replayed against another deployment.
+pub fn bid_domain() -> Eip712Domain {
+    dotenv().ok();
+    let chain_id = env
+        ::var("EIP712_CHAIN_ID")
+        .or_else(|_| env::var("SIWE_CHAIN_ID"))
+        .ok()
+        .and_then(|id| id.parse().ok())
+        .unwrap_or(1);
+    Eip712Domain {
+        name: env::var("EIP712_NAME").unwrap_or_else(|_| "Zero Car Auction".to_string()),
+        version: env::var("EIP712_VERSION").unwrap_or_else(|_| "1".to_string()),
+        chain_id,
+        verifying_contract: env
+            ::var("EIP712_VERIFYING_CONTRACT")
+            .unwrap_or_else(|_| format!("0x{}", "0".repeat(40))),
+    }
+}
+
+// GET /api/bids/typed-data
+// Domain and types for `eth_signTypedData_v4`; the client adds the bid as `message`.
+pub async fn get_bid_typed_data_handler() -> Json<Value> {
+    let domain = bid_domain();
+    Json(
+        json!({
+      "status": "success",
+      "data": {
+        "domain": {
+          "name": domain.name,
+          "version": domain.version,
+          "chainId": domain.chain_id,
+          "verifyingContract": domain.verifying_contract
+        },
+        "types": {
+          "EIP712Domain": [
+            { "name": "name", "type": "string" },
+            { "name": "version", "type": "string" },
+            { "name": "chainId", "type": "uint256" },
+            { "name": "verifyingContract", "type": "address" }
+          ],
+          "Bid": [
+            { "name": "auctionId", "type": "uint256" },
+            { "name": "amount", "type": "uint256" },
+            { "name": "nonce", "type": "uint256" },
+            { "name": "deadline", "type": "uint256" }
+          ]
+        },
+        "primaryType": "Bid"
+      }
+    })
+    )
+}
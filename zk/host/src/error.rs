@@ -0,0 +1,143 @@
+use axum::{ http::{ header, StatusCode }, response::{ IntoResponse, Response }, Json };
+use sea_orm::DbErr;
+use serde_json::json;
+
+pub const PROBLEM_JSON: &str = "application/problem+json";
+
+// Errors returned by the API. Each one answers with an RFC 7807 problem+json body:
+//   { "type": "/problems/not_found", "title": "Not found", "status": 404,
+//     "detail": "Auction not found", "code": "not_found" }
+// `code` is stable, so clients branch on it rather than on `detail`.
+#[derive(Debug)]
+pub enum ApiError {
+    // database errors; a missing record is a 404, anything else a 500 whose detail is not exposed
+    Db(DbErr),
+    // the prover or a receipt failed
+    Proving(String),
+    // missing, invalid or expired token or signature
+    Unauthorized(String),
+    // authenticated, but not allowed to do this
+    Forbidden(String),
+    // the request itself is invalid
+    Validation(String),
+    NotFound(String),
+    // the request is valid but the resource is not in a state that allows it
+    Conflict(String),
+    TooEarly(String),
+    RateLimited(String),
+    // IPFS pinning or the nonce store failed
+    Storage(String),
+    // the Ethereum node failed
+    Chain(String),
+    Internal(String),
+}
+
+impl ApiError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ApiError::Db(DbErr::RecordNotFound(_)) => StatusCode::NOT_FOUND,
+            ApiError::Db(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            ApiError::Proving(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            ApiError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            ApiError::Forbidden(_) => StatusCode::FORBIDDEN,
+            ApiError::Validation(_) => StatusCode::BAD_REQUEST,
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::Conflict(_) => StatusCode::CONFLICT,
+            ApiError::TooEarly(_) => StatusCode::TOO_EARLY,
+            ApiError::RateLimited(_) => StatusCode::TOO_MANY_REQUESTS,
+            ApiError::Storage(_) => StatusCode::SERVICE_UNAVAILABLE,
+            ApiError::Chain(_) => StatusCode::BAD_GATEWAY,
+            ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            ApiError::Db(DbErr::RecordNotFound(_)) => "not_found",
+            ApiError::Db(_) => "database_error",
+            ApiError::Proving(_) => "proving_failed",
+            ApiError::Unauthorized(_) => "unauthorized",
+            ApiError::Forbidden(_) => "forbidden",
+            ApiError::Validation(_) => "invalid_request",
+            ApiError::NotFound(_) => "not_found",
+            ApiError::Conflict(_) => "conflict",
+            ApiError::TooEarly(_) => "too_early",
+            ApiError::RateLimited(_) => "rate_limited",
+            ApiError::Storage(_) => "storage_unavailable",
+            ApiError::Chain(_) => "chain_unavailable",
+            ApiError::Internal(_) => "internal_error",
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            ApiError::Db(DbErr::RecordNotFound(_)) => "Not found",
+            ApiError::Db(_) => "Database error",
+            ApiError::Proving(_) => "Proving failed",
+            ApiError::Unauthorized(_) => "Unauthorized",
+            ApiError::Forbidden(_) => "Forbidden",
+            ApiError::Validation(_) => "Invalid request",
+            ApiError::NotFound(_) => "Not found",
+            ApiError::Conflict(_) => "Conflict",
+            ApiError::TooEarly(_) => "Too early",
+            ApiError::RateLimited(_) => "Too many requests",
+            ApiError::Storage(_) => "Storage unavailable",
+            ApiError::Chain(_) => "Chain unavailable",
+            ApiError::Internal(_) => "Internal error",
+        }
+    }
+
+    pub fn detail(&self) -> String {
+        match self {
+            ApiError::Db(DbErr::RecordNotFound(detail)) => detail.clone(),
+            ApiError::Db(_) => "The database request failed".to_string(),
+            | ApiError::Proving(detail)
+            | ApiError::Unauthorized(detail)
+            | ApiError::Forbidden(detail)
+            | ApiError::Validation(detail)
+            | ApiError::NotFound(detail)
+            | ApiError::Conflict(detail)
+            | ApiError::TooEarly(detail)
+            | ApiError::RateLimited(detail)
+            | ApiError::Storage(detail)
+            | ApiError::Chain(detail)
+            | ApiError::Internal(detail) => detail.clone(),
+        }
+    }
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ApiError::Db(e) => write!(f, "{}: {}", self.code(), e),
+            _ => write!(f, "{}: {}", self.code(), self.detail()),
+        }
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+impl From<DbErr> for ApiError {
+    fn from(e: DbErr) -> Self {
+        ApiError::Db(e)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = self.status();
+        if status.is_server_error() {
+            eprintln!("{}", self);
+        }
+        let code = self.code();
+        let problem =
+            json!({
+            "type": format!("/problems/{}", code),
+            "title": self.title(),
+            "status": status.as_u16(),
+            "detail": self.detail(),
+            "code": code
+        });
+        (status, [(header::CONTENT_TYPE, PROBLEM_JSON)], Json(problem)).into_response()
+    }
+}
@@ -0,0 +1,313 @@
+use std::{ env, sync::Arc };
+
+use axum::{
+    body::{ to_bytes, Body },
+    extract::Request,
+    http::{ header, HeaderValue, Method, StatusCode },
+    middleware::Next,
+    response::{ IntoResponse, Response },
+};
+use chrono::{ Duration, Utc };
+use dotenv::dotenv;
+use entity::{ idempotency_key, IdempotencyKeyModel };
+use sea_orm::{
+    ActiveModelTrait,
+    ColumnTrait,
+    DatabaseConnection,
+    DbErr,
+    EntityTrait,
+    QueryFilter,
+    Set,
+    SqlErr,
+};
+use sha2::{ Digest, Sha256 };
+
+use crate::auth::AuthUser;
+use crate::error::ApiError;
+
+pub const IDEMPOTENCY_KEY: &str = "idempotency-key";
+// set on responses that were stored by an earlier request with the same key
+pub const IDEMPOTENT_REPLAYED: &str = "idempotent-replayed";
+
+const MAX_KEY_LEN: usize = 255;
+// same as axum's default body limit for extractors
+const MAX_BODY: usize = 2 * 1024 * 1024;
+// A running request holds its key for LOCK_LEASE and renews it every HEARTBEAT. A key whose
+// lease lapsed belongs to a request that died with its host, and a retry takes it over.
+const LOCK_LEASE: Duration = Duration::seconds(60);
+const HEARTBEAT: std::time::Duration = std::time::Duration::from_secs(20);
+
+// IDEMPOTENCY_TTL: seconds a key is kept, after which it can be used for a new request.
+pub fn idempotency_ttl() -> Duration {
+    dotenv().ok();
+    let seconds = env
+        ::var("IDEMPOTENCY_TTL")
+        .ok()
+        .and_then(|seconds| seconds.parse().ok())
+        .unwrap_or(86400);
+    Duration::seconds(seconds)
+}
+
+fn request_hash(method: &Method, path: &str, body: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(method.as_str().as_bytes());
+    hasher.update(b" ");
+    hasher.update(path.as_bytes());
+    hasher.update(b"\n");
+    hasher.update(body);
+    ethers::utils::hex::encode(hasher.finalize())
+}
+
+// Claims the key for this request, or takes over one left by the same request whose lease
+// lapsed. Returns the row already holding it otherwise.
+async fn claim(
+    db: &DatabaseConnection,
+    address: &str,
+    key: &str,
+    method: &Method,
+    path: &str,
+    hash: &str
+) -> Result<Result<IdempotencyKeyModel, IdempotencyKeyModel>, DbErr> {
+    let now = Utc::now().naive_utc();
+    // expired keys are free again
+    idempotency_key::Entity
+        ::delete_many()
+        .filter(idempotency_key::Column::Address.eq(address))
+        .filter(idempotency_key::Column::Key.eq(key))
+        .filter(idempotency_key::Column::CreatedAt.lt(now - idempotency_ttl()))
+        .exec(db).await?;
+
+    let claimed = idempotency_key::ActiveModel {
+        address: Set(address.to_string()),
+        key: Set(key.to_string()),
+        method: Set(method.to_string()),
+        path: Set(path.to_string()),
+        request_hash: Set(hash.to_string()),
+        locked_until: Set(Some(now + LOCK_LEASE)),
+        created_at: Set(now),
+        ..Default::default()
+    }.insert(db).await;
+    match claimed {
+        Ok(claimed) => Ok(Ok(claimed)),
+        // the unique (address, key) index decides between concurrent retries
+        Err(e) if matches!(e.sql_err(), Some(SqlErr::UniqueConstraintViolation(_))) => {
+            let held = idempotency_key::Entity
+                ::find()
+                .filter(idempotency_key::Column::Address.eq(address))
+                .filter(idempotency_key::Column::Key.eq(key))
+                .one(db).await?
+                .ok_or(DbErr::RecordNotFound("Idempotency key not found".to_string()))?;
+            let lapsed = held.response_status.is_none() &&
+                held.request_hash == hash &&
+                held.locked_until.is_none_or(|until| until < now);
+            if !lapsed {
+                return Ok(Err(held));
+            }
+            // only one retry renews a lapsed lease, the others keep waiting for it
+            let taken = idempotency_key::Entity
+                ::update_many()
+                .col_expr(idempotency_key::Column::LockedUntil, Some(now + LOCK_LEASE).into())
+                .filter(idempotency_key::Column::Id.eq(held.id))
+                .filter(idempotency_key::Column::ResponseStatus.is_null())
+                .filter(
+                    idempotency_key::Column::LockedUntil
+                        .is_null()
+                        .or(idempotency_key::Column::LockedUntil.lt(now))
+                )
+                .exec(db).await?;
+            if taken.rows_affected == 1 {
+                Ok(Ok(IdempotencyKeyModel { locked_until: Some(now + LOCK_LEASE), ..held }))
+            } else {
+                Ok(Err(held))
+            }
+        }
+        Err(e) => Err(e),
+    }
+}
+
+// Renews the lease on a claimed key until the request holding it finishes.
+async fn heartbeat(db: Arc<DatabaseConnection>, id: i32) {
+    loop {
+        tokio::time::sleep(HEARTBEAT).await;
+        let renewed = idempotency_key::Entity
+            ::update_many()
+            .col_expr(
+                idempotency_key::Column::LockedUntil,
+                Some(Utc::now().naive_utc() + LOCK_LEASE).into()
+            )
+            .filter(idempotency_key::Column::Id.eq(id))
+            .filter(idempotency_key::Column::ResponseStatus.is_null())
+            .exec(&*db).await;
+        if let Err(e) = renewed {
+            eprintln!("Failed to renew idempotency key {}: {}", id, e);
+        }
+    }
+}
+
+// Responses that running the request again would not change. Anything else, such as a 409 from
+// a race, a 429, or a server error, may succeed on a retry.
+fn is_final(status: StatusCode) -> bool {
+    status.is_success() ||
+        matches!(
+            status,
+            StatusCode::BAD_REQUEST |
+                StatusCode::FORBIDDEN |
+                StatusCode::NOT_FOUND |
+                StatusCode::UNPROCESSABLE_ENTITY
+        )
+}
+
+// Stores the response the key answers with from now on. Only final responses are stored: for
+// any other the key is released so a retry runs the request again.
+async fn record(db: &DatabaseConnection, claimed: IdempotencyKeyModel, res: Response) -> Response {
+    if !is_final(res.status()) {
+        release(db, claimed.id).await;
+        return res;
+    }
+    let (parts, body) = res.into_parts();
+    let bytes = match to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            release(db, claimed.id).await;
+            return ApiError::Internal(e.to_string()).into_response();
+        }
+    };
+    let mut stored: idempotency_key::ActiveModel = claimed.into();
+    stored.response_status = Set(Some(parts.status.as_u16() as i32));
+    stored.locked_until = Set(None);
+    stored.response_body = Set(Some(String::from_utf8_lossy(&bytes).into_owned()));
+    stored.content_type = Set(
+        parts.headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+    );
+    stored.completed_at = Set(Some(Utc::now().naive_utc()));
+    if let Err(e) = stored.update(db).await {
+        eprintln!("Failed to store idempotent response: {}", e);
+    }
+    Response::from_parts(parts, Body::from(bytes))
+}
+
+async fn release(db: &DatabaseConnection, id: i32) {
+    if let Err(e) = idempotency_key::Entity::delete_by_id(id).exec(db).await {
+        eprintln!("Failed to release idempotency key {}: {}", id, e);
+    }
+}
+
+fn replay(held: IdempotencyKeyModel) -> Response {
+    let status = held.response_status
+        .and_then(|status| StatusCode::from_u16(status as u16).ok())
+        .unwrap_or(StatusCode::OK);
+    let mut res = (status, held.response_body.unwrap_or_default()).into_response();
+    if let Some(content_type) = held.content_type.and_then(|c| HeaderValue::from_str(&c).ok()) {
+        res.headers_mut().insert(header::CONTENT_TYPE, content_type);
+    }
+    res.headers_mut().insert(IDEMPOTENT_REPLAYED, HeaderValue::from_static("true"));
+    res
+}
+
+// Route guard for protected POST routes, used after `auth`. A request carrying an
+// `Idempotency-Key` header runs once per caller and key: retries with the same key and payload
+// get the stored response, and the same key with a different payload is a conflict. Requests
+// without the header are passed through.
+pub async fn idempotent(
+    axum::extract::State(db): axum::extract::State<Arc<DatabaseConnection>>,
+    req: Request,
+    next: Next
+) -> Result<Response, ApiError> {
+    if req.method() != Method::POST {
+        return Ok(next.run(req).await);
+    }
+    let Some(key) = req.headers().get(IDEMPOTENCY_KEY) else {
+        return Ok(next.run(req).await);
+    };
+    let key = key
+        .to_str()
+        .ok()
+        .filter(|key| !key.is_empty() && key.len() <= MAX_KEY_LEN)
+        .map(str::to_string)
+        .ok_or(
+            ApiError::Validation(
+                format!("Idempotency-Key must be 1 to {} visible characters", MAX_KEY_LEN)
+            )
+        )?;
+    let user = req
+        .extensions()
+        .get::<AuthUser>()
+        .cloned()
+        .ok_or(ApiError::Unauthorized("Unauthorized".to_string()))?;
+
+    let (parts, body) = req.into_parts();
+    let bytes = to_bytes(body, MAX_BODY).await.map_err(|e| ApiError::Validation(e.to_string()))?;
+    let path = parts.uri.path().to_string();
+    let hash = request_hash(&parts.method, &path, &bytes);
+    let address = user.addr.to_lowercase();
+
+    let claimed = match claim(&db, &address, &key, &parts.method, &path, &hash).await? {
+        Ok(claimed) => claimed,
+        Err(held) if held.request_hash != hash => {
+            return Err(
+                ApiError::Conflict(
+                    "Idempotency-Key was already used for a different request".to_string()
+                )
+            );
+        }
+        Err(held) if held.response_status.is_none() => {
+            let mut res = ApiError::Conflict(
+                "A request with this Idempotency-Key is still being processed".to_string()
+            ).into_response();
+            res.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from_static("5"));
+            return Ok(res);
+        }
+        Err(held) => {
+            return Ok(replay(held));
+        }
+    };
+
+    // Proving takes minutes and clients give up waiting, so the request runs and stores its
+    // response in its own task, which finishes even if the client disconnects.
+    let req = Request::from_parts(parts, Body::from(bytes));
+    let task = tokio::spawn(async move {
+        let lease = tokio::spawn(heartbeat(db.clone(), claimed.id));
+        let res = tokio::spawn(next.run(req)).await;
+        lease.abort();
+        match res {
+            Ok(res) => record(&db, claimed, res).await,
+            Err(e) => {
+                release(&db, claimed.id).await;
+                ApiError::Internal(e.to_string()).into_response()
+            }
+        }
+    });
+    task.await.map_err(|e| ApiError::Internal(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_final_responses_are_kept() {
+        for status in [
+            StatusCode::OK,
+            StatusCode::CREATED,
+            StatusCode::BAD_REQUEST,
+            StatusCode::FORBIDDEN,
+            StatusCode::NOT_FOUND,
+            StatusCode::UNPROCESSABLE_ENTITY,
+        ] {
+            assert!(is_final(status), "{}", status);
+        }
+        for status in [
+            StatusCode::UNAUTHORIZED,
+            StatusCode::CONFLICT,
+            StatusCode::TOO_EARLY,
+            StatusCode::TOO_MANY_REQUESTS,
+            StatusCode::INTERNAL_SERVER_ERROR,
+            StatusCode::BAD_GATEWAY,
+        ] {
+            assert!(!is_final(status), "{}", status);
+        }
+    }
+}
@@ -1,4 +1,32 @@
-use jsonwebtoken::{ encode, Header, EncodingKey };
+use std::{ env, fs };
+
+use base64::{ engine::general_purpose::URL_SAFE_NO_PAD, Engine };
+use dotenv::dotenv;
+use jsonwebtoken::{
+    decode,
+    decode_header,
+    encode,
+    jwk::{
+        AlgorithmParameters,
+        CommonParameters,
+        EllipticCurve,
+        EllipticCurveKeyParameters,
+        EllipticCurveKeyType,
+        Jwk,
+        JwkSet,
+        KeyAlgorithm,
+        OctetKeyPairParameters,
+        OctetKeyPairType,
+        PublicKeyUse,
+    },
+    Algorithm,
+    DecodingKey,
+    EncodingKey,
+    Header,
+    Validation,
+};
+use p256::{ elliptic_curve::sec1::ToEncodedPoint, pkcs8::{ DecodePrivateKey, DecodePublicKey } };
+use entity::Role;
 use serde::{ Serialize, Deserialize };
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -6,21 +34,216 @@ pub struct Claims {
     pub addr: String, // Ethereum address
     pub exp: usize, // Expiry time (unix timestamp)
     pub username: String,
+    #[serde(default)]
+    pub roles: Vec<Role>, // granted roles at issue time
+}
+
+// One signing key. `encoding` is only present for the current key; previous keys are kept
+// to verify tokens issued before a rotation until they expire.
+pub struct JwtKey {
+    pub kid: String,
+    pub alg: Algorithm,
+    encoding: Option<EncodingKey>,
+    decoding: DecodingKey,
+    // public JWK for asymmetric keys, published on the JWKS endpoint
+    jwk: Option<Jwk>,
+}
+
+pub struct JwtKeys {
+    pub current: JwtKey,
+    pub previous: Option<JwtKey>,
+    pub access_ttl: i64,
+    pub refresh_ttl: i64,
+}
+
+fn parse_algorithm(value: &str) -> Result<Algorithm, String> {
+    match value {
+        "HS256" => Ok(Algorithm::HS256),
+        "ES256" => Ok(Algorithm::ES256),
+        "EdDSA" => Ok(Algorithm::EdDSA),
+        other => Err(format!("Unsupported JWT algorithm: {}", other)),
+    }
+}
+
+fn read_pem(path: &str) -> Result<String, String> {
+    fs::read_to_string(path).map_err(|e| format!("Failed to read key {}: {}", path, e))
 }
 
-pub fn issue_token(address: &str, username: &str) -> String {
-    let expiration = chrono::Utc
-        ::now()
-        .checked_add_signed(chrono::Duration::minutes(15))
-        .unwrap()
-        .timestamp() as usize;
+fn public_jwk(kid: &str, alg: Algorithm, algorithm: AlgorithmParameters) -> Jwk {
+    Jwk {
+        common: CommonParameters {
+            public_key_use: Some(PublicKeyUse::Signature),
+            key_algorithm: Some(
+                if alg == Algorithm::ES256 {
+                    KeyAlgorithm::ES256
+                } else {
+                    KeyAlgorithm::EdDSA
+                }
+            ),
+            key_id: Some(kid.to_string()),
+            ..Default::default()
+        },
+        algorithm,
+    }
+}
 
-    let claims = Claims {
-        addr: address.to_string(),
-        exp: expiration,
-        username: username.to_string(),
+// Public JWK from either a PKCS#8 private key or an SPKI public key PEM.
+fn ec_jwk(kid: &str, pem: &str) -> Result<Jwk, String> {
+    let public_key = match p256::SecretKey::from_pkcs8_pem(pem) {
+        Ok(secret) => secret.public_key(),
+        Err(_) =>
+            p256::PublicKey
+                ::from_public_key_pem(pem)
+                .map_err(|e| format!("Invalid ES256 key for {}: {}", kid, e))?,
     };
+    let point = public_key.to_encoded_point(false);
+    let x = point.x().ok_or("Invalid ES256 public key")?;
+    let y = point.y().ok_or("Invalid ES256 public key")?;
+    Ok(
+        public_jwk(
+            kid,
+            Algorithm::ES256,
+            AlgorithmParameters::EllipticCurve(EllipticCurveKeyParameters {
+                key_type: EllipticCurveKeyType::EC,
+                curve: EllipticCurve::P256,
+                x: URL_SAFE_NO_PAD.encode(x),
+                y: URL_SAFE_NO_PAD.encode(y),
+            })
+        )
+    )
+}
+
+fn ed_jwk(kid: &str, pem: &str) -> Result<Jwk, String> {
+    let public_key = match ed25519_dalek::SigningKey::from_pkcs8_pem(pem) {
+        Ok(secret) => secret.verifying_key(),
+        Err(_) =>
+            ed25519_dalek::VerifyingKey
+                ::from_public_key_pem(pem)
+                .map_err(|e| format!("Invalid EdDSA key for {}: {}", kid, e))?,
+    };
+    Ok(
+        public_jwk(
+            kid,
+            Algorithm::EdDSA,
+            AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+                key_type: OctetKeyPairType::OctetKeyPair,
+                curve: EllipticCurve::Ed25519,
+                x: URL_SAFE_NO_PAD.encode(public_key.as_bytes()),
+            })
+        )
+    )
+}
+
+impl JwtKey {
+    // Loads `{prefix}_KID`, `{prefix}_ALGORITHM` and either `{prefix}_SECRET` (HS256) or
+    // `{prefix}_KEY_PATH` (ES256/EdDSA PEM; a public key is enough for a previous key).
+    fn from_env(prefix: &str, default_alg: Algorithm, signing: bool) -> Result<Self, String> {
+        let kid = env::var(format!("{}_KID", prefix)).unwrap_or_else(|_| "default".to_string());
+        let alg = match env::var(format!("{}_ALGORITHM", prefix)) {
+            Ok(alg) => parse_algorithm(&alg)?,
+            Err(_) => default_alg,
+        };
+
+        if alg == Algorithm::HS256 {
+            let secret = env
+                ::var(format!("{}_SECRET", prefix))
+                .ok()
+                .filter(|secret| !secret.is_empty())
+                .ok_or(format!("{}_SECRET is required for HS256", prefix))?;
+            return Ok(JwtKey {
+                kid,
+                alg,
+                encoding: signing.then(|| EncodingKey::from_secret(secret.as_bytes())),
+                decoding: DecodingKey::from_secret(secret.as_bytes()),
+                jwk: None,
+            });
+        }
+
+        let path = env
+            ::var(format!("{}_KEY_PATH", prefix))
+            .map_err(|_| format!("{}_KEY_PATH is required for {:?}", prefix, alg))?;
+        let pem = read_pem(&path)?;
+        let jwk = if alg == Algorithm::ES256 { ec_jwk(&kid, &pem)? } else { ed_jwk(&kid, &pem)? };
+        let decoding = DecodingKey::from_jwk(&jwk).map_err(|e| e.to_string())?;
+        let encoding = if signing {
+            let key = if alg == Algorithm::ES256 {
+                EncodingKey::from_ec_pem(pem.as_bytes())
+            } else {
+                EncodingKey::from_ed_pem(pem.as_bytes())
+            };
+            Some(key.map_err(|e| format!("{}_KEY_PATH must be a PKCS#8 private key: {}", prefix, e))?)
+        } else {
+            None
+        };
+        Ok(JwtKey { kid, alg, encoding, decoding, jwk: Some(jwk) })
+    }
+}
+
+impl JwtKeys {
+    // JWT_* configures the signing key, JWT_PREVIOUS_* the key it replaced during a rotation.
+    pub fn from_env() -> Result<Self, String> {
+        dotenv().ok();
+        let current = JwtKey::from_env("JWT", Algorithm::HS256, true)?;
+        let previous = if env::var("JWT_PREVIOUS_KID").is_ok() {
+            Some(JwtKey::from_env("JWT_PREVIOUS", current.alg, false)?)
+        } else {
+            None
+        };
+        if previous.as_ref().is_some_and(|p| p.kid == current.kid) {
+            return Err("JWT_PREVIOUS_KID must differ from JWT_KID".to_string());
+        }
+        let access_ttl = env
+            ::var("JWT_ACCESS_TTL_SECS")
+            .ok()
+            .and_then(|ttl| ttl.parse().ok())
+            .unwrap_or(15 * 60);
+        let refresh_ttl = env
+            ::var("JWT_REFRESH_TTL_SECS")
+            .ok()
+            .and_then(|ttl| ttl.parse().ok())
+            .unwrap_or(30 * 24 * 60 * 60);
+        Ok(JwtKeys { current, previous, access_ttl, refresh_ttl })
+    }
+
+    pub fn issue_token(
+        &self,
+        address: &str,
+        username: &str,
+        roles: &[Role]
+    ) -> Result<String, String> {
+        let expiration = (chrono::Utc::now().timestamp() + self.access_ttl) as usize;
+
+        let claims = Claims {
+            addr: address.to_string(),
+            exp: expiration,
+            username: username.to_string(),
+            roles: roles.to_vec(),
+        };
+
+        let mut header = Header::new(self.current.alg);
+        header.kid = Some(self.current.kid.clone());
+        let key = self.current.encoding.as_ref().ok_or("Current JWT key cannot sign")?;
+        encode(&header, &claims, key).map_err(|e| e.to_string())
+    }
+
+    // Accepts tokens signed by the current or the previous key, selected by `kid`.
+    pub fn decode(&self, token: &str) -> Option<Claims> {
+        let header = decode_header(token).ok()?;
+        let key = match header.kid.as_deref() {
+            Some(kid) if kid == self.current.kid => &self.current,
+            Some(kid) => self.previous.as_ref().filter(|p| p.kid == kid)?,
+            None => &self.current,
+        };
+        let token_data = decode::<Claims>(token, &key.decoding, &Validation::new(key.alg)).ok()?;
+        Some(token_data.claims)
+    }
 
-    let key = EncodingKey::from_secret("your-secret".as_ref());
-    encode(&Header::default(), &claims, &key).unwrap()
+    pub fn jwks(&self) -> JwkSet {
+        let keys = [Some(&self.current), self.previous.as_ref()]
+            .into_iter()
+            .flatten()
+            .filter_map(|key| key.jwk.clone())
+            .collect();
+        JwkSet { keys }
+    }
 }
@@ -2,6 +2,9 @@ use serde::{ Deserialize, Serialize };
 use pinata_sdk::PinataApi;
 use dotenv::dotenv;
 use std::env;
+use std::sync::Arc;
+use axum::extract::FromRef;
+use sea_orm::{ ConnectionTrait, DatabaseConnection, DbErr, Statement };
 pub mod car;
 pub mod bid;
 pub mod auction;
@@ -11,6 +14,69 @@ pub mod jwt;
 pub mod redis;
 pub mod comment;
 pub mod saved_auction;
+pub mod nft;
+pub mod price;
+pub mod siwe;
+pub mod nonce;
+pub mod refresh;
+pub mod role;
+pub mod login;
+pub mod eip712;
+pub mod eip1271;
+pub mod settlement;
+pub mod sealed;
+pub mod proxy;
+pub mod scheduler;
+pub mod transfer;
+pub mod offer;
+pub mod retraction;
+pub mod sequencer;
+pub mod idempotency;
+pub mod error;
+// Shared router state. Handlers extract only the part they need, e.g. `State<Arc<DatabaseConnection>>`.
+#[derive(Clone)]
+pub struct AppState {
+    pub db: Arc<DatabaseConnection>,
+    pub nonces: Arc<dyn nonce::NonceStore>,
+    pub keys: Arc<jwt::JwtKeys>,
+}
+
+impl FromRef<AppState> for Arc<DatabaseConnection> {
+    fn from_ref(state: &AppState) -> Self {
+        state.db.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<dyn nonce::NonceStore> {
+    fn from_ref(state: &AppState) -> Self {
+        state.nonces.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<jwt::JwtKeys> {
+    fn from_ref(state: &AppState) -> Self {
+        state.keys.clone()
+    }
+}
+
+// Times a bid or retraction is proven again when its auction changed while the proof ran,
+// before the request gives up with 409.
+pub const PROVE_ATTEMPTS: usize = 3;
+
+// Takes the next id from a table's serial sequence. For rows whose id goes into a proof before
+// the row is inserted; the sequence never hands the same id out twice, even across hosts.
+pub async fn next_id<C: ConnectionTrait>(db: &C, table: &str) -> Result<i32, DbErr> {
+    let row = db
+        .query_one(
+            Statement::from_string(
+                db.get_database_backend(),
+                format!("SELECT nextval(pg_get_serial_sequence('{}', 'id'))::integer AS id", table)
+            )
+        ).await?
+        .ok_or(DbErr::RecordNotFound(format!("No id sequence for {}", table)))?;
+    row.try_get("", "id")
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Copy)]
 pub struct SessionStats {
     /// Count of segments in this proof request
@@ -29,18 +95,30 @@ pub struct SessionStats {
     pub reserved_cycles: u64,
 }
 
-use pinata_sdk::{ ApiError, PinByJson };
+use pinata_sdk::PinByJson;
 // use std::collections::HashMap;
 
-use crate::overall::OverallCommit;
+use crate::{ error::ApiError, overall::OverallCommit };
 
-pub async fn sync_state(overall: &OverallCommit) -> Result<String, ApiError> {
+pub async fn pin_json<T: Serialize>(data: T) -> Result<String, ApiError> {
     dotenv().ok();
-    let api_key = env::var("PINATA_API_KEY").unwrap();
-    let secret_key = env::var("PINATA_SECRET_KEY").unwrap();
+    let api_key = env
+        ::var("PINATA_API_KEY")
+        .map_err(|_| ApiError::Storage("PINATA_API_KEY is not set".to_string()))?;
+    let secret_key = env
+        ::var("PINATA_SECRET_KEY")
+        .map_err(|_| ApiError::Storage("PINATA_SECRET_KEY is not set".to_string()))?;
 
-    let api: PinataApi = PinataApi::new(api_key, secret_key).unwrap();
+    let api: PinataApi = PinataApi::new(api_key, secret_key).map_err(|e|
+        ApiError::Storage(e.to_string())
+    )?;
+    let pinned_object = api
+        .pin_json(PinByJson::new(data)).await
+        .map_err(|e| ApiError::Storage(e.to_string()))?;
+    Ok(pinned_object.ipfs_hash)
+}
 
+pub async fn sync_state(overall: &OverallCommit) -> Result<String, ApiError> {
     // HashMap derives serde::Serialize
     // let mut json_data = HashMap::new();
     // json_data.insert("receipt", overall.receipt);
@@ -57,12 +135,5 @@ pub async fn sync_state(overall: &OverallCommit) -> Result<String, ApiError> {
         stats,
     };
 
-    let result = api.pin_json(PinByJson::new(new_overall)).await;
-
-    if let Ok(pinned_object) = result {
-        let hash: String = pinned_object.ipfs_hash;
-        return Ok(hash);
-    } else {
-        return Err(ApiError::GenericError("Failed while interacting with the api".to_string()));
-    }
+    pin_json(new_overall).await
 }
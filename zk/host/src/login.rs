@@ -0,0 +1,224 @@
+use std::sync::Arc;
+
+use axum::{
+    http::{ HeaderMap, HeaderValue },
+    response::{ IntoResponse, Response },
+    Json,
+};
+use car_auction_core::siwe::SiweMessage;
+use chrono::Utc;
+use entity::{ login_audit, LoginAttestation, LoginAuditModel, LoginStatus, Role };
+use ethers::{ types::Address, utils::hex };
+use sea_orm::{
+    ActiveModelTrait,
+    ColumnTrait,
+    DatabaseConnection,
+    EntityTrait,
+    QueryFilter,
+    QueryOrder,
+    Set,
+};
+use serde_json::{ json, Value };
+
+use crate::{
+    auth::{ prove_login, recover_ethereum_address, AuthUser, VerifyParams, VerifyPayload },
+    eip1271::ContractWallets,
+    error::ApiError,
+    jwt::JwtKeys,
+    nonce::NonceStore,
+    refresh::issue_refresh_token,
+    role::login_roles,
+    siwe::SiweConfig,
+};
+
+// The checks the verify guest makes, run natively: the message is a live EIP-4361 login for
+// this domain and nonce, and (for EOAs) the signature recovers to the claimed address.
+fn verify_message(params: &VerifyParams) -> Result<(), String> {
+    let message = SiweMessage::parse(&params.message).map_err(|e| e.to_string())?;
+    message
+        .verify(&params.domain, &params.nonce, &params.expected_addr, params.timestamp)
+        .map_err(|e| e.to_string())
+}
+
+fn verify_signer(params: &VerifyParams) -> Result<(), String> {
+    let recovered = recover_ethereum_address(&params.signature_bytes, &params.message)?;
+    let address = format!("0x{}", hex::encode(recovered));
+    if !address.eq_ignore_ascii_case(&params.expected_addr) {
+        return Err("Address mismatch".to_string());
+    }
+    Ok(())
+}
+
+// Contract wallets are asked through EIP-1271 at the current block; returns that block.
+// None means the address is an EOA (or no ETH_RPC_URL is set) and the signature was recovered.
+async fn verify_wallet(params: &VerifyParams) -> Result<Option<u64>, ApiError> {
+    if let Some(wallets) = ContractWallets::from_env() {
+        let address = params.expected_addr
+            .parse::<Address>()
+            .map_err(|_| ApiError::Validation("Invalid address".to_string()))?;
+        if wallets.is_contract(address).await.map_err(ApiError::Chain)? {
+            let block = wallets
+                .verify_message(address, &params.message, &params.signature_bytes).await
+                .map_err(ApiError::Unauthorized)?;
+            return Ok(Some(block));
+        }
+    }
+    verify_signer(params).map_err(ApiError::Unauthorized)?;
+    Ok(None)
+}
+
+// Proves the login and attaches the receipt to its audit record.
+async fn attest_login(db: Arc<DatabaseConnection>, audit: LoginAuditModel, params: VerifyParams) {
+    let proof = tokio::task
+        ::spawn_blocking(move || prove_login(&params)).await
+        .map_err(|e| e.to_string())
+        .and_then(|result| result.map_err(|e| e.to_string()));
+
+    let mut record: login_audit::ActiveModel = audit.into();
+    match proof.and_then(|commit| Ok((commit.verify_and_get_commit()?, commit))) {
+        Ok((state, commit)) => {
+            record.status = Set(
+                if state.verified {
+                    LoginStatus::Proven
+                } else {
+                    LoginStatus::Rejected
+                }
+            );
+            record.verified = Set(Some(state.verified));
+            record.receipt = Set(serde_json::to_value(&commit).ok());
+            record.proven_at = Set(Some(Utc::now().naive_utc()));
+        }
+        Err(e) => {
+            record.status = Set(LoginStatus::Failed);
+            record.error = Set(Some(e));
+        }
+    }
+    if let Err(e) = record.update(&*db).await {
+        eprintln!("Failed to store login attestation: {}", e);
+    }
+}
+
+// POST /api/auth/login
+// Fast-path login: verifies the signature natively and returns the session right away.
+// The zk proof of the login is generated in the background and attached to the audit record.
+// Contract wallet logins are attested by their EIP-1271 check instead.
+pub async fn login_handler(
+    axum::extract::State(db): axum::extract::State<Arc<DatabaseConnection>>,
+    axum::extract::State(nonces): axum::extract::State<Arc<dyn NonceStore>>,
+    axum::extract::State(keys): axum::extract::State<Arc<JwtKeys>>,
+    Json(payload): Json<VerifyPayload>
+) -> Result<Response, ApiError> {
+    // consumed like in `verify_signature_handler`, so a login can only be used once
+    let message = nonces
+        .take(&payload.nonce).await
+        .map_err(|e| ApiError::Storage(e.to_string()))?
+        .ok_or_else(|| ApiError::Validation("Failed to verify nonce".to_string()))?;
+
+    let params = VerifyParams {
+        message,
+        signature_bytes: payload.signature_bytes,
+        expected_addr: payload.expected_addr.to_lowercase(),
+        timestamp: Utc::now().timestamp(),
+        username: payload.username,
+        domain: SiweConfig::from_env().domain,
+        nonce: payload.nonce,
+    };
+    verify_message(&params).map_err(ApiError::Unauthorized)?;
+    let block_number = verify_wallet(&params).await?;
+
+    let now = Utc::now().naive_utc();
+    // an EIP-1271 check is final when made, so only EOA logins wait for a zk proof
+    let (attestation, status, verified, proven_at) = match block_number {
+        Some(_) => (LoginAttestation::Eip1271, LoginStatus::Proven, Some(true), Some(now)),
+        None => (LoginAttestation::Zkvm, LoginStatus::Pending, None, None),
+    };
+    let audit = login_audit::ActiveModel {
+        address: Set(params.expected_addr.clone()),
+        username: Set(params.username.clone()),
+        nonce: Set(params.nonce.clone()),
+        message: Set(params.message.clone()),
+        signature: Set(params.signature_bytes.clone()),
+        status: Set(status),
+        verified: Set(verified),
+        attestation: Set(attestation),
+        block_number: Set(block_number.map(|block| block as i64)),
+        created_at: Set(now),
+        proven_at: Set(proven_at),
+        ..Default::default()
+    }
+        .insert(&*db).await?;
+
+    let roles = login_roles(&db, &params.expected_addr).await?;
+    let access_token = keys
+        .issue_token(&params.expected_addr, &params.username, &roles)
+        .map_err(ApiError::Internal)?;
+    let refresh_token = issue_refresh_token(
+        &db,
+        &keys,
+        &params.expected_addr,
+        &params.username
+    ).await?;
+
+    let body =
+        json!({
+      "status": "success",
+      "address": audit.address,
+      "username": audit.username,
+      "login_id": audit.id,
+      "attestation": audit.attestation,
+      "attestation_status": audit.status,
+      "refresh_token": refresh_token
+    });
+    if audit.attestation == LoginAttestation::Zkvm {
+        tokio::spawn(attest_login(db.clone(), audit, params));
+    }
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        axum::http::header::AUTHORIZATION,
+        HeaderValue::from_str(&format!("Bearer {}", access_token)).map_err(|_|
+            ApiError::Internal("Invalid header value".to_string())
+        )?
+    );
+    Ok((headers, Json(body)).into_response())
+}
+
+// GET /api/auth/logins
+// The caller's login records, newest first, without receipts.
+pub async fn get_logins_handler(
+    user: AuthUser,
+    axum::extract::State(db): axum::extract::State<Arc<DatabaseConnection>>
+) -> Result<Json<Value>, ApiError> {
+    let logins: Vec<LoginAuditModel> = login_audit::Entity
+        ::find()
+        .filter(login_audit::Column::Address.eq(user.addr.to_lowercase()))
+        .order_by_desc(login_audit::Column::Id)
+        .all(&*db).await?
+        .into_iter()
+        .map(|login| LoginAuditModel { receipt: None, ..login })
+        .collect();
+    Ok(Json(json!({
+      "status": "success",
+      "data": logins
+    })))
+}
+
+// GET /api/auth/logins/{id}
+// One login record with its receipt, for its own address or an admin.
+pub async fn get_login_handler(
+    user: AuthUser,
+    axum::extract::Path(id): axum::extract::Path<i32>,
+    axum::extract::State(db): axum::extract::State<Arc<DatabaseConnection>>
+) -> Result<Json<Value>, ApiError> {
+    let login = login_audit::Entity
+        ::find_by_id(id)
+        .one(&*db).await?
+        .ok_or(ApiError::NotFound("Login not found".to_string()))?;
+    if !login.address.eq_ignore_ascii_case(&user.addr) && !user.has_role(Role::Admin) {
+        return Err(ApiError::NotFound("Login not found".to_string()));
+    }
+    Ok(Json(json!({
+      "status": "success",
+      "data": login
+    })))
+}
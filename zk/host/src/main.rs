@@ -2,8 +2,7 @@ use std::process;
 use std::env;
 use std::sync::Arc;
 
-use axum::http::header::ACCESS_CONTROL_ALLOW_HEADERS;
-use axum::routing::{ get, post };
+use axum::routing::{ get, post, put };
 use axum::{ middleware, Router };
 // use db::comment::{ create_comment, get_comments };
 // use db::saved_auction::{ create_saved_auction, get_saved_auctions, get_saved_auctions_by_user };
@@ -11,22 +10,61 @@ use dotenv::dotenv;
 // These constants represent the RISC-V ELF and the image ID generated by risc0-build.
 // The ELF is used for proving and the ID is used for verification.
 use db::{ establish_connection };
-use host::auction::complete_bid_by_id;
 use host::bid::get_bid_by_auction_id;
 use host::overall::get_overall_state_handler;
 use host::overall::sync_state_handler;
-use host::sync_state;
-use tower_http::cors::ExposeHeaders;
+use host::AppState;
+use host::nonce::nonce_store_from_env;
+use host::login::{ get_login_handler, get_logins_handler, login_handler };
+use host::jwt::JwtKeys;
+use host::refresh::{ jwks_handler, refresh_token_handler, revoke_token_handler };
+use host::role::{
+    get_roles_handler,
+    grant_role_handler,
+    require_roles,
+    revoke_role_handler,
+    ADMINS,
+    BIDDERS,
+    SELLERS,
+};
 use tower_http::cors::{ Any, CorsLayer };
 
+use host::nft::{ get_nft_metadata, pin_nft_metadata };
+use host::price::get_nft_price_handler;
+use host::eip712::get_bid_typed_data_handler;
+use host::settlement::settle_auction_handler;
+use host::sealed::{ commit_sealed_bid_handler, get_sealed_bids_handler, reveal_sealed_bid_handler };
+use host::proxy::{ cancel_proxy_bid_handler, register_proxy_bid_handler };
+use host::idempotency::idempotent;
+use host::scheduler::run_scheduler;
+use host::sequencer::{ get_state_sequence_handler, run_sequencer };
+use host::transfer::get_car_owners_handler;
+use host::retraction::{ cancel_bid_handler, retract_bid_handler };
+use host::offer::{
+    accept_offer_handler,
+    counter_offer_handler,
+    create_offer_handler,
+    get_car_offers_handler,
+    reject_offer_handler,
+};
 use host::saved_auction::create_saved_auction;
 use host::saved_auction::get_saved_auctions;
 use host::saved_auction::get_saved_auctions_by_user;
 use host::{
-    car::{ get_all_cars_handler, create_car, get_car_by_id },
-    auction::{ get_auction_by_id, get_auctions, create_auction },
-    bid::{ get_bid_by_id, get_bids, create_bid },
-    comment::{ create_comment, get_comments },
+    car::{ get_all_cars_handler, create_car, get_car_by_id, update_car_handler },
+    auction::{
+        get_auction_by_id,
+        get_auctions,
+        create_auction,
+        get_auction_extensions_handler,
+        get_auction_transitions_handler,
+        get_dutch_price_handler,
+        relist_auction_handler,
+        update_auction_handler,
+        cancel_auction_handler,
+    },
+    bid::{ get_bid_by_id, get_bids, create_bid, update_bid_handler },
+    comment::{ create_comment, get_comments, update_comment, delete_comment },
 };
 
 use host::{
@@ -48,6 +86,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     });
     let db = establish_connection(&database_url).await?;
     let db = Arc::new(db);
+    let nonces = nonce_store_from_env().await?;
+    let keys = JwtKeys::from_env().unwrap_or_else(|e| {
+        eprintln!("Error: invalid JWT key configuration: {}", e);
+        process::exit(1);
+    });
+    // activates and settles auctions on their own schedule
+    tokio::spawn(run_scheduler(db.clone()));
+    tokio::spawn(run_sequencer(db.clone()));
+    let state = AppState { db, nonces, keys: Arc::new(keys) };
     // let cars = get_all_cars(&db).await.unwrap();
     // get_car_merkle_hash(cars);
     // let leaves = get_car_leaves(&cars);
@@ -59,29 +106,85 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .expose_headers(Any); // Expose
 
     // Create the router with our endpoints
+    // Each route declares the roles it needs; routes without `require_roles` only need a valid token.
+    let sellers = middleware::from_fn_with_state(SELLERS, require_roles);
+    let bidders = middleware::from_fn_with_state(BIDDERS, require_roles);
+    let admins = middleware::from_fn_with_state(ADMINS, require_roles);
     let protected_routes = Router::new()
-        .route("/api/cars", post(create_car))
-        .route("/api/auctions", post(create_auction))
-        .route("/api/bids", post(create_bid))
+        .route("/api/cars", post(create_car).route_layer(sellers.clone()))
+        .route("/api/auctions", post(create_auction).route_layer(sellers.clone()))
+        .route("/api/bids", post(create_bid).route_layer(bidders.clone()))
+        .route(
+            "/api/auctions/{id}/sealed-bids",
+            post(commit_sealed_bid_handler).route_layer(bidders.clone())
+        )
+        .route(
+            "/api/auctions/{id}/sealed-bids/reveal",
+            post(reveal_sealed_bid_handler).route_layer(bidders.clone())
+        )
+        .route(
+            "/api/auctions/{id}/proxy-bid",
+            post(register_proxy_bid_handler)
+                .delete(cancel_proxy_bid_handler)
+                .route_layer(bidders.clone())
+        )
+        .route("/api/cars/{id}/offers", post(create_offer_handler).route_layer(bidders.clone()))
+        .route("/api/bids/{id}/retract", post(retract_bid_handler).route_layer(bidders.clone()))
+        .route(
+            "/api/bids/{id}",
+            put(update_bid_handler).delete(cancel_bid_handler).route_layer(bidders)
+        )
+        // answered by whoever did not propose the offer, owner or buyer
+        .route("/api/offers/{id}/accept", post(accept_offer_handler))
+        .route("/api/offers/{id}/reject", post(reject_offer_handler))
+        .route("/api/offers/{id}/counter", post(counter_offer_handler))
+        .route("/api/cars/{id}", put(update_car_handler)) // owner or admin
+        .route(
+            "/api/auctions/{id}",
+            put(update_auction_handler).delete(cancel_auction_handler).route_layer(sellers.clone())
+        )
         .route("/api/comment", post(create_comment))
+        .route("/api/comments/{id}", put(update_comment).delete(delete_comment))
         .route("/api/save_auction", post(create_saved_auction)) // save auction
-        .route("/api/bids/{id}/complete", post(complete_bid_by_id)) // save auction
-        .route_layer(middleware::from_fn(auth))
-        .with_state(db.clone());
+        .route("/api/auctions/{id}/settle", post(settle_auction_handler).route_layer(sellers.clone())) // settle auction
+        .route("/api/auctions/{id}/relist", post(relist_auction_handler).route_layer(sellers)) // relist unsold auction
+        .route("/api/nft/{token_id}/metadata/pin", post(pin_nft_metadata))
+        .route("/api/auth/logins", get(get_logins_handler))
+        .route("/api/auth/logins/{id}", get(get_login_handler))
+        // roles
+        .route("/api/admin/roles/{address}", get(get_roles_handler).route_layer(admins.clone()))
+        .route(
+            "/api/admin/roles",
+            post(grant_role_handler).delete(revoke_role_handler).route_layer(admins)
+        )
+        // POSTs with an Idempotency-Key run once per caller and key
+        .route_layer(middleware::from_fn_with_state(state.clone(), idempotent))
+        .route_layer(middleware::from_fn_with_state(state.clone(), auth))
+        .with_state(state.clone());
 
     let app = Router::new()
         .route("/api/cars/{id}", get(get_car_by_id))
         .route("/api/cars", get(get_all_cars_handler))
+        .route("/api/cars/{id}/owners", get(get_car_owners_handler)) // chain of custody
+        .route("/api/cars/{id}/offers", get(get_car_offers_handler))
         .route("/api/auctions", get(get_auctions))
         .route("/api/auctions/{id}", get(get_auction_by_id))
         .route("/api/bids", get(get_bids))
         .route("/api/bids/{id}", get(get_bid_by_id))
         .route("/api/bids/a/{id}", get(get_bid_by_auction_id))
+        .route("/api/bids/typed-data", get(get_bid_typed_data_handler)) // EIP-712 bid signing
+        .route("/api/auctions/{id}/sealed-bids", get(get_sealed_bids_handler)) // commitments only
+        .route("/api/auctions/{id}/dutch-price", get(get_dutch_price_handler)) // dutch clock price
+        .route("/api/auctions/{id}/extensions", get(get_auction_extensions_handler)) // soft-close history
+        .route("/api/auctions/{id}/transitions", get(get_auction_transitions_handler)) // status history
         //comment
         .route("/api/comments/{id}", get(get_comments))
         // save
         .route("/api/saved_auctions/{user}", get(get_saved_auctions_by_user)) // get user saved items
         .route("/api/auctions/saved/{id}", get(get_saved_auctions)) // get all saved by auction_id
+        // nft
+        .route("/api/nft/{token_id}/metadata", get(get_nft_metadata)) // ZeroNFT tokenURI
+        .route("/api/price/{token_id}", get(get_nft_price_handler)) // CarOracle price feed
         //init
         .route("/api/auctions/init", get(init_auction_handler))
         .route("/api/cars/init", get(init_car_handler))
@@ -92,11 +195,16 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .route("/api/auth", get(get_verify_handler))
         .route("/api/auth", post(verify_signature_handler))
         .route("/api/auth/verify", post(verify_auth_handler))
+        .route("/api/auth/login", post(login_handler)) // native fast path, proven in the background
+        .route("/api/auth/refresh", post(refresh_token_handler))
+        .route("/api/auth/revoke", post(revoke_token_handler))
+        .route("/.well-known/jwks.json", get(jwks_handler))
         .route("/api/state", get(get_overall_state_handler))
+        .route("/api/state/sequence", get(get_state_sequence_handler))
         .route("/api/sync", get(sync_state_handler))
         .merge(protected_routes)
         .layer(cors)
-        .with_state(db);
+        .with_state(state);
 
     // Now you can use the entity types directly
     // for car in cars {
@@ -0,0 +1,168 @@
+use std::env;
+use std::sync::Arc;
+
+use axum::Json;
+use car_auction_core::leaf_hash;
+use dotenv::dotenv;
+use entity::{ car, CarModel };
+use risc0_zkvm::Digest;
+use sea_orm::{ ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter };
+use serde::{ Deserialize, Serialize };
+use serde_json::{ json, Value };
+
+use crate::{
+    auth::AuthUser,
+    car::get_car_leaves,
+    error::ApiError,
+    overall::get_state_root,
+    pin_json,
+};
+
+// OpenSea-style metadata served as the ZeroNFT tokenURI.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NftMetadata {
+    pub name: String,
+    pub description: String,
+    pub image: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub external_url: Option<String>,
+    pub attributes: Vec<NftAttribute>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NftAttribute {
+    pub trait_type: String,
+    pub value: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_type: Option<String>,
+}
+
+impl NftAttribute {
+    fn new(trait_type: &str, value: impl Into<Value>) -> Self {
+        NftAttribute {
+            trait_type: trait_type.to_string(),
+            value: value.into(),
+            display_type: None,
+        }
+    }
+    fn number(trait_type: &str, value: impl Into<Value>) -> Self {
+        NftAttribute {
+            display_type: Some("number".to_string()),
+            ..NftAttribute::new(trait_type, value)
+        }
+    }
+}
+
+pub fn build_metadata(car: &CarModel, leaf: Digest, root: Digest) -> NftMetadata {
+    dotenv().ok();
+    let mut attributes = vec![
+        NftAttribute::new("Make", car.make.clone()),
+        NftAttribute::new("Model", car.model.clone()),
+        NftAttribute::number("Year", car.year),
+        NftAttribute::number("Mileage", car.mileage),
+        NftAttribute::new("Color", car.color.clone()),
+        NftAttribute::new("Transmission", car.transmission.clone()),
+        NftAttribute::new("Fuel Type", car.fuel_type.clone()),
+        NftAttribute::new("Engine Size", car.engine_size.clone()),
+        NftAttribute::new("VIN", car.vin.clone()),
+        NftAttribute::new("Lot", car.lot.clone())
+    ];
+    for highlight in car.highlight.iter().flatten() {
+        attributes.push(NftAttribute::new("Highlight", highlight.clone()));
+    }
+    // features are grouped as { "interior": [..], "exterior": [..], "mechanical": [..] }
+    if let Some(groups) = car.features.as_object() {
+        for (group, features) in groups {
+            let trait_type = format!("{} Feature", capitalize(group));
+            match features {
+                Value::Array(items) => {
+                    for item in items {
+                        attributes.push(NftAttribute::new(&trait_type, item.clone()));
+                    }
+                }
+                other => attributes.push(NftAttribute::new(&trait_type, other.clone())),
+            }
+        }
+    }
+    attributes.push(NftAttribute::new("Leaf Hash", format!("0x{}", leaf)));
+    attributes.push(NftAttribute::new("State Root", format!("0x{}", root)));
+
+    let image = car.image_url
+        .as_ref()
+        .and_then(|images| images.first().cloned())
+        .unwrap_or_default();
+    let external_url = env
+        ::var("NFT_EXTERNAL_URL")
+        .ok()
+        .map(|base| format!("{}/{}", base.trim_end_matches('/'), car.id));
+
+    NftMetadata {
+        name: format!("{} {} {}", car.year, car.make, car.model),
+        description: if car.summary.is_empty() {
+            car.description.clone()
+        } else {
+            car.summary.clone()
+        },
+        image,
+        external_url,
+        attributes,
+    }
+}
+
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().collect::<String>() + chars.as_str(),
+        None => String::new(),
+    }
+}
+
+async fn get_metadata(
+    db: &DatabaseConnection,
+    token_id: i32
+) -> Result<(CarModel, NftMetadata), ApiError> {
+    let car = car::Entity
+        ::find()
+        .filter(car::Column::TokenId.eq(token_id))
+        .one(db).await?
+        .ok_or(ApiError::NotFound("Token not found".to_string()))?;
+
+    let leaf = get_car_leaves(&vec![car.clone()]).remove(0);
+    let root = get_state_root(db).await?;
+    let metadata = build_metadata(&car, leaf_hash(&leaf), root);
+    Ok((car, metadata))
+}
+
+// GET /api/nft/{token_id}/metadata
+pub async fn get_nft_metadata(
+    axum::extract::Path(token_id): axum::extract::Path<i32>,
+    axum::extract::State(db): axum::extract::State<Arc<sea_orm::DatabaseConnection>>
+) -> Result<Json<NftMetadata>, ApiError> {
+    let (_, metadata) = get_metadata(&db, token_id).await?;
+    Ok(Json(metadata))
+}
+
+// POST /api/nft/{token_id}/metadata/pin
+// Freezes the current metadata on the storage backend so it can be used as a permanent tokenURI.
+pub async fn pin_nft_metadata(
+    user: AuthUser,
+    axum::extract::Path(token_id): axum::extract::Path<i32>,
+    axum::extract::State(db): axum::extract::State<Arc<sea_orm::DatabaseConnection>>
+) -> Result<Json<Value>, ApiError> {
+    let (car, metadata) = get_metadata(&db, token_id).await?;
+    if !car.owner.eq_ignore_ascii_case(&user.addr) {
+        return Err(ApiError::Forbidden("Not owner".to_string()));
+    }
+
+    let hash = pin_json(&metadata).await?;
+    Ok(
+        Json(
+            json!({
+      "status": "success",
+      "cid": hash,
+      "token_uri": format!("ipfs://{}", hash),
+      "data": metadata
+    })
+        )
+    )
+}
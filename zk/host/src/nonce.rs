@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::sync::{ Arc, Mutex };
+use std::time::{ Duration, Instant };
+
+use async_trait::async_trait;
+use dotenv::dotenv;
+
+use crate::redis::RedisNonceStore;
+
+#[derive(Debug)]
+pub struct NonceError(pub String);
+
+impl fmt::Display for NonceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Nonce store error: {}", self.0)
+    }
+}
+
+impl std::error::Error for NonceError {}
+
+// Login nonces. Each nonce maps to the message issued with it, expires after its ttl and
+// can be taken exactly once, so a nonce and its signature cannot be replayed.
+#[async_trait]
+pub trait NonceStore: Send + Sync {
+    async fn store(&self, nonce: &str, message: &str, ttl: Duration) -> Result<(), NonceError>;
+
+    // Atomically returns and deletes the message; None if unknown, expired or already taken.
+    async fn take(&self, nonce: &str) -> Result<Option<String>, NonceError>;
+}
+
+// For local development and single-instance deployments without Redis.
+#[derive(Default)]
+pub struct MemoryNonceStore {
+    entries: Mutex<HashMap<String, (String, Instant)>>,
+}
+
+impl MemoryNonceStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl NonceStore for MemoryNonceStore {
+    async fn store(&self, nonce: &str, message: &str, ttl: Duration) -> Result<(), NonceError> {
+        let mut entries = self.entries.lock().map_err(|e| NonceError(e.to_string()))?;
+        let now = Instant::now();
+        entries.retain(|_, (_, expires_at)| *expires_at > now);
+        entries.insert(nonce.to_string(), (message.to_string(), now + ttl));
+        Ok(())
+    }
+
+    async fn take(&self, nonce: &str) -> Result<Option<String>, NonceError> {
+        let mut entries = self.entries.lock().map_err(|e| NonceError(e.to_string()))?;
+        Ok(
+            entries
+                .remove(nonce)
+                .filter(|(_, expires_at)| *expires_at > Instant::now())
+                .map(|(message, _)| message)
+        )
+    }
+}
+
+// Uses Redis when REDIS_URL is set, otherwise falls back to the in-memory store.
+pub async fn nonce_store_from_env() -> Result<Arc<dyn NonceStore>, NonceError> {
+    dotenv().ok();
+    match env::var("REDIS_URL") {
+        Ok(redis_url) => Ok(Arc::new(RedisNonceStore::connect(&redis_url).await?)),
+        Err(_) => {
+            eprintln!("REDIS_URL is not set, keeping login nonces in memory");
+            Ok(Arc::new(MemoryNonceStore::new()))
+        }
+    }
+}
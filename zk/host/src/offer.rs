@@ -0,0 +1,346 @@
+use std::sync::Arc;
+
+use axum::Json;
+use car_auction_core::OfferLeaf;
+use chrono::{ NaiveDateTime, Utc };
+use entity::{ car, offer, OfferModel, OfferStatus, Status };
+use sea_orm::{
+    sea_query::Expr,
+    ActiveModelTrait,
+    ColumnTrait,
+    ConnectionTrait,
+    DatabaseConnection,
+    DbErr,
+    EntityTrait,
+    QueryFilter,
+    QueryOrder,
+    Set,
+    TransactionTrait,
+};
+use serde::Deserialize;
+use serde_json::{ json, Value };
+
+use crate::{
+    auth::AuthUser,
+    error::ApiError,
+    overall::proven_commit,
+    sequencer::submit,
+    transfer::{ transfer_car, Sale },
+};
+
+pub fn offer_leaf(offer: &OfferModel) -> OfferLeaf {
+    OfferLeaf {
+        id: offer.id,
+        car_id: offer.car_id,
+        buyer: offer.buyer.clone(),
+        seller: offer.seller.clone(),
+        proposer: offer.proposer.clone(),
+        amount: offer.amount as i64,
+        expires_at: offer.expires_at.and_utc().timestamp(),
+        status: offer_status(&offer.status),
+        counter_of: offer.counter_of,
+        created_at: offer.created_at.and_utc().timestamp(),
+        updated_at: offer.updated_at.and_utc().timestamp(),
+    }
+}
+
+fn offer_status(status: &OfferStatus) -> car_auction_core::OfferStatus {
+    match status {
+        OfferStatus::Pending => car_auction_core::OfferStatus::Pending,
+        OfferStatus::Accepted => car_auction_core::OfferStatus::Accepted,
+        OfferStatus::Rejected => car_auction_core::OfferStatus::Rejected,
+        OfferStatus::Countered => car_auction_core::OfferStatus::Countered,
+        OfferStatus::Expired => car_auction_core::OfferStatus::Expired,
+    }
+}
+
+// Offer leaves follow the car leaves in the car state.
+pub fn get_offer_leaves(offers: &[OfferModel]) -> Vec<String> {
+    offers
+        .iter()
+        .map(|offer| offer_leaf(offer).encode())
+        .collect()
+}
+
+pub async fn get_all_offers<C: ConnectionTrait>(db: &C) -> Result<Vec<OfferModel>, DbErr> {
+    offer::Entity::find().order_by_asc(offer::Column::Id).all(db).await
+}
+
+#[derive(Deserialize)]
+pub struct OfferPayload {
+    pub amount: i32,
+    pub expires_at: NaiveDateTime,
+}
+
+fn check_offer(payload: &OfferPayload, now: NaiveDateTime) -> Result<(), String> {
+    if payload.amount <= 0 {
+        return Err("Invalid amount".to_string());
+    }
+    if payload.expires_at <= now {
+        return Err("expires_at must be in the future".to_string());
+    }
+    Ok(())
+}
+
+// A car in a pending or active auction can only be sold by that auction.
+fn in_auction(car: &car::Model) -> bool {
+    matches!(car.auction_status, Some(Status::Pending | Status::Active))
+}
+
+// Moves an offer out of `from`. `false` when another request moved it first.
+async fn set_offer_status<C: ConnectionTrait>(
+    db: &C,
+    id: i32,
+    from: OfferStatus,
+    to: OfferStatus
+) -> Result<bool, DbErr> {
+    let updated = offer::Entity
+        ::update_many()
+        .col_expr(offer::Column::Status, offer::Column::Status.save_as(Expr::val(to)))
+        .col_expr(offer::Column::UpdatedAt, Utc::now().naive_utc().into())
+        .filter(offer::Column::Id.eq(id))
+        .filter(offer::Column::Status.eq(from))
+        .exec(db).await?;
+    Ok(updated.rows_affected > 0)
+}
+
+// A pending, unexpired offer the caller can answer: the party that did not propose it.
+async fn find_open_offer(
+    db: &DatabaseConnection,
+    id: i32,
+    user: &AuthUser
+) -> Result<OfferModel, ApiError> {
+    let offer = offer::Entity
+        ::find_by_id(id)
+        .one(db).await?
+        .ok_or(ApiError::NotFound("Offer not found".to_string()))?;
+    let counterparty = if offer.proposer.eq_ignore_ascii_case(&offer.buyer) {
+        &offer.seller
+    } else {
+        &offer.buyer
+    };
+    if !counterparty.eq_ignore_ascii_case(&user.addr) {
+        return Err(ApiError::Forbidden("Not the receiver of this offer".to_string()));
+    }
+    if offer.status != OfferStatus::Pending {
+        return Err(ApiError::Conflict("Offer is not pending".to_string()));
+    }
+    if offer.expires_at <= Utc::now().naive_utc() {
+        return Err(ApiError::Conflict("Offer expired".to_string()));
+    }
+    Ok(offer)
+}
+
+// Marks pending offers past `expires_at` expired. Returns how many expired.
+pub async fn expire_offers<C: ConnectionTrait>(db: &C) -> Result<u64, DbErr> {
+    let now = Utc::now().naive_utc();
+    let expired = offer::Entity
+        ::update_many()
+        .col_expr(
+            offer::Column::Status,
+            offer::Column::Status.save_as(Expr::val(OfferStatus::Expired))
+        )
+        .col_expr(offer::Column::UpdatedAt, now.into())
+        .filter(offer::Column::Status.eq(OfferStatus::Pending))
+        .filter(offer::Column::ExpiresAt.lte(now))
+        .exec(db).await?;
+    Ok(expired.rows_affected)
+}
+
+// POST /api/cars/{id}/offers
+// Offers to buy a car outside an auction. The owner can accept, reject or counter it until it
+// expires.
+pub async fn create_offer_handler(
+    user: AuthUser,
+    axum::extract::Path(id): axum::extract::Path<i32>,
+    axum::extract::State(db): axum::extract::State<Arc<sea_orm::DatabaseConnection>>,
+    Json(payload): Json<OfferPayload>
+) -> Result<Json<Value>, ApiError> {
+    let now = Utc::now().naive_utc();
+    check_offer(&payload, now).map_err(ApiError::Validation)?;
+    let car = car::Entity
+        ::find_by_id(id)
+        .one(&*db).await?
+        .ok_or(ApiError::NotFound("Car not found".to_string()))?;
+    if car.owner.eq_ignore_ascii_case(&user.addr) {
+        return Err(ApiError::Validation("Cannot make an offer on your own car".to_string()));
+    }
+    if in_auction(&car) {
+        return Err(ApiError::Conflict("Car is being auctioned".to_string()));
+    }
+
+    let txn = db.begin().await?;
+    let offer = offer::ActiveModel {
+        car_id: Set(id),
+        buyer: Set(user.addr.clone()),
+        seller: Set(car.owner),
+        proposer: Set(user.addr.clone()),
+        amount: Set(payload.amount),
+        expires_at: Set(payload.expires_at),
+        status: Set(OfferStatus::Pending),
+        counter_of: Set(None),
+        created_at: Set(now),
+        updated_at: Set(now),
+        ..Default::default()
+    }
+        .insert(&txn).await?;
+    let sequence = submit(&txn, &user.actor(), None).await?;
+    txn.commit().await?;
+
+    let (hash, commit) = proven_commit(&db, sequence).await?;
+    Ok(
+        Json(
+            json!({
+      "status": "success",
+      "message": "offer made",
+      "data": offer,
+      "cid": hash,
+      "receipt": commit.receipt,
+      "stats": commit.stats
+    })
+        )
+    )
+}
+
+// POST /api/offers/{id}/counter
+// Answers an offer with another amount. The offer becomes `countered` and the counter is a new
+// pending offer for the other party to answer.
+pub async fn counter_offer_handler(
+    user: AuthUser,
+    axum::extract::Path(id): axum::extract::Path<i32>,
+    axum::extract::State(db): axum::extract::State<Arc<sea_orm::DatabaseConnection>>,
+    Json(payload): Json<OfferPayload>
+) -> Result<Json<Value>, ApiError> {
+    let now = Utc::now().naive_utc();
+    check_offer(&payload, now).map_err(ApiError::Validation)?;
+    let countered = find_open_offer(&db, id, &user).await?;
+    let txn = db.begin().await?;
+    let moved = set_offer_status(&txn, id, OfferStatus::Pending, OfferStatus::Countered).await?;
+    if !moved {
+        return Err(ApiError::Conflict("Offer is not pending".to_string()));
+    }
+
+    let offer = offer::ActiveModel {
+        car_id: Set(countered.car_id),
+        buyer: Set(countered.buyer),
+        seller: Set(countered.seller),
+        proposer: Set(user.addr.clone()),
+        amount: Set(payload.amount),
+        expires_at: Set(payload.expires_at),
+        status: Set(OfferStatus::Pending),
+        counter_of: Set(Some(id)),
+        created_at: Set(now),
+        updated_at: Set(now),
+        ..Default::default()
+    }
+        .insert(&txn).await?;
+    let sequence = submit(&txn, &user.actor(), None).await?;
+    txn.commit().await?;
+
+    let (hash, commit) = proven_commit(&db, sequence).await?;
+    Ok(
+        Json(
+            json!({
+      "status": "success",
+      "message": "offer countered",
+      "data": offer,
+      "cid": hash,
+      "receipt": commit.receipt,
+      "stats": commit.stats
+    })
+        )
+    )
+}
+
+// POST /api/offers/{id}/reject
+pub async fn reject_offer_handler(
+    user: AuthUser,
+    axum::extract::Path(id): axum::extract::Path<i32>,
+    axum::extract::State(db): axum::extract::State<Arc<sea_orm::DatabaseConnection>>
+) -> Result<Json<Value>, ApiError> {
+    find_open_offer(&db, id, &user).await?;
+    let txn = db.begin().await?;
+    let moved = set_offer_status(&txn, id, OfferStatus::Pending, OfferStatus::Rejected).await?;
+    if !moved {
+        return Err(ApiError::Conflict("Offer is not pending".to_string()));
+    }
+    let sequence = submit(&txn, &user.actor(), None).await?;
+    txn.commit().await?;
+
+    let (hash, commit) = proven_commit(&db, sequence).await?;
+    Ok(
+        Json(
+            json!({
+      "status": "success",
+      "message": "offer rejected",
+      "cid": hash,
+      "receipt": commit.receipt,
+      "stats": commit.stats
+    })
+        )
+    )
+}
+
+// POST /api/offers/{id}/accept
+// Sells the car at the offer's amount: the car goes to the buyer with a SELL transfer proof,
+// like an auction sale. The proof accepts the offer and rejects the other pending offers on the
+// car, and all of it is stored in one transaction once the proof holds.
+pub async fn accept_offer_handler(
+    user: AuthUser,
+    axum::extract::Path(id): axum::extract::Path<i32>,
+    axum::extract::State(db): axum::extract::State<Arc<sea_orm::DatabaseConnection>>
+) -> Result<Json<Value>, ApiError> {
+    let offer = find_open_offer(&db, id, &user).await?;
+    let car = car::Entity
+        ::find_by_id(offer.car_id)
+        .one(&*db).await?
+        .ok_or(ApiError::NotFound("Car not found".to_string()))?;
+    if !car.owner.eq_ignore_ascii_case(&offer.seller) {
+        return Err(ApiError::Conflict("Car changed owner since the offer".to_string()));
+    }
+    if in_auction(&car) {
+        return Err(ApiError::Conflict("Car is being auctioned".to_string()));
+    }
+
+    // the proof accepts the pending offer; nothing is written until it holds
+    let (_, ownership, sequence) = transfer_car(
+        &db,
+        car.id,
+        &offer.buyer,
+        Sale::Offer(id),
+        None,
+        offer.amount,
+        user.actor()
+    ).await?;
+
+    let (hash, commit) = proven_commit(&db, sequence).await?;
+    Ok(
+        Json(
+            json!({
+      "status": "success",
+      "message": "offer accepted",
+      "transfer": ownership,
+      "cid": hash,
+      "receipt": commit.receipt,
+      "stats": commit.stats
+    })
+        )
+    )
+}
+
+// GET /api/cars/{id}/offers
+// Offers and counters on a car, oldest first.
+pub async fn get_car_offers_handler(
+    axum::extract::Path(id): axum::extract::Path<i32>,
+    axum::extract::State(db): axum::extract::State<Arc<sea_orm::DatabaseConnection>>
+) -> Result<Json<Value>, ApiError> {
+    let offers = offer::Entity
+        ::find()
+        .filter(offer::Column::CarId.eq(id))
+        .order_by_asc(offer::Column::Id)
+        .all(&*db).await?;
+    Ok(Json(json!({
+      "status": "success",
+      "data": offers
+    })))
+}
@@ -1,15 +1,30 @@
 use std::sync::Arc;
 
 use axum::Json;
-use car_auction_core::OverallState;
-use sea_orm::DatabaseConnection;
-use crate::{ auction::get_all_auctions, bid::get_all_bids, car::get_all_cars, sync_state };
-use risc0_zkvm::{ default_prover, ExecutorEnv, Receipt };
+use car_auction_core::{
+    state_root,
+    Actor,
+    AuctionState,
+    BidState,
+    CarState,
+    OverallState,
+    StateAction,
+    StateLeaves,
+};
+use sea_orm::{ DatabaseConnection, DbErr, TransactionTrait };
+use crate::{ auction::get_all_auctions, bid::get_all_bids, car::get_car_state_leaves };
+use risc0_zkvm::{ default_prover, Digest, ExecutorEnv, Receipt };
 use serde::{ Deserialize, Serialize };
 use serde_json::{ json, Value };
 use methods::{ INIT_OVERALL_ELF, INIT_OVERALL_ID };
 
-use crate::{ auction::get_auction_leaves, bid::get_bid_leaves, car::get_car_leaves, SessionStats };
+use crate::{
+    auction::get_auction_leaves,
+    bid::get_bid_leaves,
+    error::ApiError,
+    sequencer::{ read_leaves, submit, wait_for },
+    SessionStats,
+};
 #[derive(Serialize, Deserialize)]
 pub struct OverallCommit {
     pub receipt: Receipt,
@@ -20,6 +35,11 @@ pub struct OverallParams {
     pub car_leaves: Vec<String>,
     pub auc_leaves: Vec<String>,
     pub bid_leaves: Vec<String>,
+    pub actor: Actor,
+    pub action: Option<StateAction>,
+    pub sequence: Option<u64>,
+    pub previous: Option<Digest>,
+    pub previous_leaves: Option<StateLeaves>,
 }
 impl OverallCommit {
     //  pub fn get_state() {}
@@ -33,23 +53,33 @@ impl OverallCommit {
     }
 }
 pub fn init_overall(
-    car_leaves: Vec<String>,
-    auc_leaves: Vec<String>,
-    bid_leaves: Vec<String>
-) -> Result<OverallCommit, String> {
+    leaves: StateLeaves,
+    actor: Actor,
+    action: Option<StateAction>,
+    sequence: Option<u64>,
+    previous: Option<Digest>,
+    previous_leaves: Option<StateLeaves>
+) -> Result<OverallCommit, ApiError> {
     let params = OverallParams {
-        car_leaves,
-        auc_leaves,
-        bid_leaves,
+        car_leaves: leaves.car,
+        auc_leaves: leaves.auction,
+        bid_leaves: leaves.bid,
+        actor,
+        action,
+        sequence,
+        previous,
+        previous_leaves,
     };
     let env = ExecutorEnv::builder()
         .write(&params)
         // .write()
-        .unwrap()
+        .map_err(|e| ApiError::Proving(e.to_string()))?
         .build()
-        .unwrap();
+        .map_err(|e| ApiError::Proving(e.to_string()))?;
     let prover = default_prover();
-    let prove_info = prover.prove(env, INIT_OVERALL_ELF).unwrap();
+    let prove_info = prover
+        .prove(env, INIT_OVERALL_ELF)
+        .map_err(|e| ApiError::Proving(e.to_string()))?;
     let auction_commit = OverallCommit {
         receipt: prove_info.receipt,
         stats: SessionStats {
@@ -66,26 +96,9 @@ pub fn init_overall(
 
 pub async fn init_overall_handler(axum::extract::State(
     db,
-): axum::extract::State<Arc<sea_orm::DatabaseConnection>>) -> Result<
-    Json<Value>,
-    (axum::http::StatusCode, String)
-> {
-    // car leaves
-    let cars = get_all_cars(&db).await.unwrap();
-    let car_leaves = get_car_leaves(&cars);
-
-    // auct leaves
-    let auc = get_all_auctions(&db).await.unwrap();
-    let auc_leaves = get_auction_leaves(&auc);
-
-    // bid leaves
-    let bids = get_all_bids(&db).await.unwrap();
-    let bid_leaves = get_bid_leaves(&bids);
-
-    let result = init_overall(car_leaves, auc_leaves, bid_leaves).map_err(|e| (
-        axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-        e.to_string(),
-    ))?;
+): axum::extract::State<Arc<sea_orm::DatabaseConnection>>) -> Result<Json<Value>, ApiError> {
+    let leaves = read_leaves(&*db).await?;
+    let result = init_overall(leaves, Actor::SYSTEM, None, None, None, None)?;
     Ok(Json(json!(result)))
 }
 
@@ -93,20 +106,27 @@ pub fn check_state(
     car_leaves: Vec<String>,
     auc_leaves: Vec<String>,
     bid_leaves: Vec<String>
-) -> Result<OverallCommit, String> {
+) -> Result<OverallCommit, ApiError> {
     let params = OverallParams {
         car_leaves,
         auc_leaves,
         bid_leaves,
+        actor: Actor::SYSTEM,
+        action: None,
+        sequence: None,
+        previous: None,
+        previous_leaves: None,
     };
     let env = ExecutorEnv::builder()
         .write(&params)
         // .write()
-        .unwrap()
+        .map_err(|e| ApiError::Proving(e.to_string()))?
         .build()
-        .unwrap();
+        .map_err(|e| ApiError::Proving(e.to_string()))?;
     let prover = default_prover();
-    let prove_info = prover.prove(env, INIT_OVERALL_ELF).unwrap();
+    let prove_info = prover
+        .prove(env, INIT_OVERALL_ELF)
+        .map_err(|e| ApiError::Proving(e.to_string()))?;
     let auction_commit = OverallCommit {
         receipt: prove_info.receipt,
         stats: SessionStats {
@@ -123,56 +143,71 @@ pub fn check_state(
 
 pub async fn get_overall_state_handler(axum::extract::State(
     db,
-): axum::extract::State<Arc<sea_orm::DatabaseConnection>>) -> Result<
-    Json<Value>,
-    (axum::http::StatusCode, String)
-> {
-    // car leaves
-    let cars = get_all_cars(&db).await.unwrap();
-    let car_leaves = get_car_leaves(&cars);
-
-    // auct leaves
-    let auc = get_all_auctions(&db).await.unwrap();
-    let auc_leaves = get_auction_leaves(&auc);
-
-    // bid leaves
-    let bids = get_all_bids(&db).await.unwrap();
-    let bid_leaves = get_bid_leaves(&bids);
-
-    let result = init_overall(car_leaves, auc_leaves, bid_leaves).map_err(|e| (
-        axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-        e.to_string(),
-    ))?;
+): axum::extract::State<Arc<sea_orm::DatabaseConnection>>) -> Result<Json<Value>, ApiError> {
+    let leaves = read_leaves(&*db).await?;
+    let result = init_overall(leaves, Actor::SYSTEM, None, None, None, None)?;
     Ok(Json(json!(result)))
 }
+// Commits the state as it is now, e.g. after changes made outside the API. Changes made by a
+// handler are queued with `submit` from their own transaction instead.
 pub async fn sync_overall_state(
-    db: Arc<DatabaseConnection>
-) -> Result<(String, OverallCommit), ()> {
-    // car leaves
-    let cars = get_all_cars(&db).await.unwrap();
-    let car_leaves = get_car_leaves(&cars);
-
-    // auct leaves
-    let auc = get_all_auctions(&db).await.unwrap();
-    let auc_leaves = get_auction_leaves(&auc);
-
-    // bid leaves
-    let bids = get_all_bids(&db).await.unwrap();
-    let bid_leaves = get_bid_leaves(&bids);
-
-    let result = init_overall(car_leaves, auc_leaves, bid_leaves)
-        .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
-        .unwrap();
-    let hash = sync_state(&result).await.unwrap();
-    Ok((hash, result))
+    db: Arc<DatabaseConnection>,
+    actor: Actor
+) -> Result<(String, OverallCommit), ApiError> {
+    let txn = db.begin().await?;
+    let sequence = submit(&txn, &actor, None).await?;
+    txn.commit().await?;
+    proven_commit(&db, sequence).await
+}
+// Waits for a queued commit and returns its cid and receipt once the sequencer proved it.
+pub async fn proven_commit(
+    db: &DatabaseConnection,
+    sequence: i64
+) -> Result<(String, OverallCommit), ApiError> {
+    let commit = wait_for(db, sequence).await?;
+    match (commit.cid, commit.receipt, commit.stats) {
+        (Some(cid), Some(receipt), Some(stats)) => {
+            let receipt = serde_json::from_value(receipt)
+                .map_err(|e| ApiError::Internal(e.to_string()))?;
+            let stats = serde_json::from_value(stats)
+                .map_err(|e| ApiError::Internal(e.to_string()))?;
+            Ok((cid, OverallCommit { receipt, stats }))
+        }
+        _ =>
+            Err(
+                ApiError::Proving(
+                    format!(
+                        "State commit {} failed: {}",
+                        sequence,
+                        commit.error.unwrap_or_default()
+                    )
+                )
+            ),
+    }
+}
+// Waits for the commit `head_commit` picked and returns the state it published. Action proofs
+// take it as input, so the roots they start from are ones the sequencer proved.
+pub async fn published_state(
+    db: &DatabaseConnection,
+    sequence: i64
+) -> Result<OverallState, ApiError> {
+    let (_, commit) = proven_commit(db, sequence).await?;
+    commit.get_commit().map_err(ApiError::Proving)
+}
+// Recomputes the current state root natively from the db, without proving.
+pub async fn get_state_root(db: &DatabaseConnection) -> Result<Digest, DbErr> {
+    let auc = get_all_auctions(db).await?;
+    let bids = get_all_bids(db).await?;
+
+    let car_state = CarState::init(get_car_state_leaves(db).await?);
+    let auc_state = AuctionState::init(get_auction_leaves(&auc));
+    let bid_state = BidState::init(get_bid_leaves(&bids));
+    Ok(state_root(&car_state, &auc_state, &bid_state))
 }
 pub async fn sync_state_handler(axum::extract::State(
     db,
-): axum::extract::State<Arc<sea_orm::DatabaseConnection>>) -> Result<
-    Json<Value>,
-    (axum::http::StatusCode, String)
-> {
-    let (hash, commit) = sync_overall_state(db).await.unwrap();
+): axum::extract::State<Arc<sea_orm::DatabaseConnection>>) -> Result<Json<Value>, ApiError> {
+    let (hash, commit) = sync_overall_state(db, Actor::SYSTEM).await?;
     Ok(
         Json(
             json!({
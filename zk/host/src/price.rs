@@ -0,0 +1,168 @@
+use std::env;
+use std::sync::Arc;
+
+use axum::Json;
+use dotenv::dotenv;
+use entity::{ auction, bid, car, CarModel, Status };
+use ethers::{
+    abi::{ encode_packed, Token },
+    signers::{ LocalWallet, Signer },
+    types::U256,
+    utils::keccak256,
+};
+use sea_orm::{ ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder };
+use serde::{ Deserialize, Serialize };
+use crate::error::ApiError;
+
+// CarOracle prices use 8 decimals (Chainlink standard); bid amounts are stored in cents.
+pub const PRICE_DECIMALS: u32 = 8;
+const CENTS_TO_PRICE: u64 = 1_000_000;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PriceSource {
+    WinningBid,
+    Comparables,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PriceReport {
+    pub token_id: i32,
+    pub car_id: i32,
+    // uint256 as a decimal string, ready to pass to `updatePrice`
+    pub price: String,
+    pub decimals: u32,
+    pub timestamp: i64,
+    pub source: PriceSource,
+    pub samples: usize,
+    // keccak256(abi.encodePacked(tokenId, price, timestamp))
+    pub digest: String,
+    // EIP-191 signature over `digest` from the oracle key
+    pub signature: String,
+    pub signer: String,
+}
+
+// Sale price of a completed auction: the settled winning amount, or the highest bid for
+// auctions completed before settlement recorded a winner.
+async fn get_winning_amount(db: &DatabaseConnection, auction_id: i32) -> Result<Option<i32>, DbErr> {
+    let completed = auction::Entity
+        ::find_by_id(auction_id)
+        .filter(auction::Column::Status.eq(Status::Completed))
+        .one(db).await?;
+    let Some(completed) = completed else {
+        return Ok(None);
+    };
+    if completed.winner.is_some() {
+        return Ok(Some(completed.current_bid));
+    }
+    let winner = bid::Entity
+        ::find()
+        .filter(bid::Column::AuctionId.eq(auction_id))
+        .filter(bid::Column::RetractedAt.is_null())
+        .order_by_desc(bid::Column::Amount)
+        .one(db).await?;
+    Ok(winner.map(|b| b.amount))
+}
+
+// Winning bids from completed sales of the same make, model and year.
+async fn get_comparable_amounts(db: &DatabaseConnection, car: &CarModel) -> Result<Vec<i32>, DbErr> {
+    let comparables = car::Entity
+        ::find()
+        .filter(car::Column::Make.eq(car.make.clone()))
+        .filter(car::Column::Model.eq(car.model.clone()))
+        .filter(car::Column::Year.eq(car.year))
+        .filter(car::Column::Id.ne(car.id))
+        .all(db).await?;
+
+    let mut amounts = vec![];
+    for comparable in comparables {
+        if let Some(amount) = get_winning_amount(db, comparable.auction_id).await? {
+            amounts.push(amount);
+        }
+    }
+    Ok(amounts)
+}
+
+fn median(values: &mut [i32]) -> Option<i64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_unstable();
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        Some(((values[mid - 1] as i64) + (values[mid] as i64)) / 2)
+    } else {
+        Some(values[mid] as i64)
+    }
+}
+
+// Fair value in cents, from the car's own sale or from comparable sales.
+pub async fn get_fair_value(
+    db: &DatabaseConnection,
+    car: &CarModel
+) -> Result<Option<(i64, PriceSource, usize)>, DbErr> {
+    if let Some(amount) = get_winning_amount(db, car.auction_id).await? {
+        return Ok(Some((amount as i64, PriceSource::WinningBid, 1)));
+    }
+    let mut amounts = get_comparable_amounts(db, car).await?;
+    let samples = amounts.len();
+    Ok(median(&mut amounts).map(|price| (price, PriceSource::Comparables, samples)))
+}
+
+fn get_oracle_wallet() -> Result<LocalWallet, String> {
+    dotenv().ok();
+    let key = env::var("ORACLE_PRIVATE_KEY").map_err(|_| "ORACLE_PRIVATE_KEY is not set".to_string())?;
+    key.parse::<LocalWallet>().map_err(|e| format!("Invalid ORACLE_PRIVATE_KEY: {}", e))
+}
+
+pub async fn sign_price(
+    car: &CarModel,
+    cents: i64,
+    source: PriceSource,
+    samples: usize
+) -> Result<PriceReport, String> {
+    let wallet = get_oracle_wallet()?;
+    let price = U256::from(cents as u64) * U256::from(CENTS_TO_PRICE);
+    let timestamp = chrono::Utc::now().timestamp();
+
+    let packed = encode_packed(
+        &[
+            Token::Uint(U256::from(car.token_id as u64)),
+            Token::Uint(price),
+            Token::Uint(U256::from(timestamp as u64)),
+        ]
+    ).map_err(|e| e.to_string())?;
+    let digest = keccak256(packed);
+    let signature = wallet.sign_message(digest).await.map_err(|e| e.to_string())?;
+
+    Ok(PriceReport {
+        token_id: car.token_id,
+        car_id: car.id,
+        price: price.to_string(),
+        decimals: PRICE_DECIMALS,
+        timestamp,
+        source,
+        samples,
+        digest: format!("0x{}", ethers::utils::hex::encode(digest)),
+        signature: format!("0x{}", signature),
+        signer: format!("{:?}", wallet.address()),
+    })
+}
+
+// GET /api/price/{token_id}
+pub async fn get_nft_price_handler(
+    axum::extract::Path(token_id): axum::extract::Path<i32>,
+    axum::extract::State(db): axum::extract::State<Arc<sea_orm::DatabaseConnection>>
+) -> Result<Json<PriceReport>, ApiError> {
+    let car = car::Entity
+        ::find()
+        .filter(car::Column::TokenId.eq(token_id))
+        .one(&*db).await?
+        .ok_or(ApiError::NotFound("Token not found".to_string()))?;
+
+    let (cents, source, samples) = get_fair_value(&db, &car).await?
+        .ok_or(ApiError::NotFound("No completed sales for this car".to_string()))?;
+
+    let report = sign_price(&car, cents, source, samples).await.map_err(ApiError::Internal)?;
+    Ok(Json(report))
+}
@@ -0,0 +1,263 @@
+use std::sync::Arc;
+
+use axum::Json;
+use car_auction_core::{ eip712::BidAuthorization, Actor, AuctionType };
+use chrono::Utc;
+use entity::{ auction, bid, proxy_bid, ProxyBidModel, Status };
+use sea_orm::{
+    ActiveModelTrait,
+    ColumnTrait,
+    DatabaseConnection,
+    DbErr,
+    EntityTrait,
+    QueryFilter,
+    QueryOrder,
+    Set,
+};
+use serde::Deserialize;
+use serde_json::{ json, Value };
+
+use crate::{
+    auction::{ auction_leaf, auction_type },
+    auth::AuthUser,
+    bid::{ min_increment, nonce_used, place_bid },
+    eip712::bid_domain,
+    error::ApiError,
+    overall::proven_commit,
+};
+
+#[derive(Deserialize)]
+pub struct ProxyBidPayload {
+    pub max_amount: i32,
+    pub nonce: u64,
+    pub deadline: u64,
+    // EIP-712 `Bid` signature with `amount` set to `max_amount`, see `/api/bids/typed-data`
+    pub signature: String,
+}
+
+// POST /api/auctions/{id}/proxy-bid
+// Registers (or raises) the caller's private maximum on an english auction. The system then
+// bids for them in minimum increments whenever they are outbid, up to the maximum.
+pub async fn register_proxy_bid_handler(
+    user: AuthUser,
+    axum::extract::Path(id): axum::extract::Path<i32>,
+    axum::extract::State(db): axum::extract::State<Arc<DatabaseConnection>>,
+    Json(payload): Json<ProxyBidPayload>
+) -> Result<Json<Value>, ApiError> {
+    if payload.max_amount <= 0 {
+        return Err(ApiError::Validation("Invalid maximum".to_string()));
+    }
+    let nonce = i64
+        ::try_from(payload.nonce)
+        .map_err(|_| ApiError::Validation("Invalid bid nonce".to_string()))?;
+    let deadline = i64
+        ::try_from(payload.deadline)
+        .map_err(|_| ApiError::Validation("Invalid bid deadline".to_string()))?;
+    let now = Utc::now();
+    if deadline < now.timestamp() {
+        return Err(ApiError::Validation("Bid signature expired".to_string()));
+    }
+
+    // the maximum is signed like a bid, so every proxy bid under it is the bidder's
+    let authorization = BidAuthorization {
+        auction_id: id as u64,
+        amount: payload.max_amount as u64,
+        nonce: payload.nonce,
+        deadline: payload.deadline,
+    };
+    let signer = authorization
+        .recover_signer(&bid_domain(), &payload.signature)
+        .map_err(|e| ApiError::Validation(e.to_string()))?;
+    if !signer.eq_ignore_ascii_case(&user.addr) {
+        return Err(ApiError::Unauthorized("Bid not signed by the bidder".to_string()));
+    }
+    let used = nonce_used(&db, &user.addr, nonce).await?;
+    if used {
+        return Err(ApiError::Conflict("Bid nonce already used".to_string()));
+    }
+
+    let auc = auction::Entity
+        ::find_by_id(id)
+        .one(&*db).await?
+        .ok_or(ApiError::NotFound("Auction not found".to_string()))?;
+    if auction_type(auc.auction_type) != AuctionType::English {
+        return Err(
+            ApiError::Validation("Proxy bidding only applies to english auctions".to_string())
+        );
+    }
+    let now_naive = now.naive_utc();
+    if auc.status != Some(Status::Active) || now_naive < auc.start_time || now_naive > auc.end_time {
+        return Err(ApiError::Validation("Auction is not open".to_string()));
+    }
+    if payload.max_amount <= auc.current_bid {
+        return Err(ApiError::Validation("Maximum must be above the current bid".to_string()));
+    }
+
+    let existing = proxy_bid::Entity
+        ::find()
+        .filter(proxy_bid::Column::AuctionId.eq(id))
+        .filter(proxy_bid::Column::Bidder.eq(user.addr.clone()))
+        .one(&*db).await?;
+    let mut proxy = match existing {
+        Some(existing) => existing.into(),
+        None =>
+            proxy_bid::ActiveModel {
+                auction_id: Set(id),
+                bidder: Set(user.addr.clone()),
+                created_at: Set(now_naive),
+                ..Default::default()
+            },
+    };
+    proxy.max_amount = Set(payload.max_amount);
+    proxy.nonce = Set(nonce);
+    proxy.deadline = Set(deadline);
+    proxy.signature = Set(payload.signature);
+    proxy.active = Set(true);
+    proxy.updated_at = Set(now_naive);
+    proxy.save(&*db).await?;
+
+    // bids right away if the caller is not leading
+    tokio::spawn(run_proxy_bids(db.clone(), id));
+    Ok(
+        Json(
+            json!({
+      "status": "success",
+      "message": "proxy bid registered",
+      "data": { "auction_id": id, "bidder": user.addr, "deadline": deadline, "active": true }
+    })
+        )
+    )
+}
+
+// DELETE /api/auctions/{id}/proxy-bid
+// Stops bidding for the caller. Bids already placed stay.
+pub async fn cancel_proxy_bid_handler(
+    user: AuthUser,
+    axum::extract::Path(id): axum::extract::Path<i32>,
+    axum::extract::State(db): axum::extract::State<Arc<DatabaseConnection>>
+) -> Result<Json<Value>, ApiError> {
+    let proxy = proxy_bid::Entity
+        ::find()
+        .filter(proxy_bid::Column::AuctionId.eq(id))
+        .filter(proxy_bid::Column::Bidder.eq(user.addr.clone()))
+        .filter(proxy_bid::Column::Active.eq(true))
+        .one(&*db).await?
+        .ok_or(ApiError::NotFound("No proxy bid on this auction".to_string()))?;
+    let mut proxy: proxy_bid::ActiveModel = proxy.into();
+    proxy.active = Set(false);
+    proxy.updated_at = Set(Utc::now().naive_utc());
+    proxy.update(&*db).await?;
+    Ok(Json(json!({
+      "status": "success",
+      "message": "proxy bid cancelled"
+    })))
+}
+
+// The next proxy bid on an auction, if any: the strongest proxy that is not leading and can
+// still beat the current bid bids one increment over the best rival maximum, up to its own.
+async fn next_proxy_bid(
+    db: &DatabaseConnection,
+    auction_id: i32
+) -> Result<Option<(ProxyBidModel, u64)>, DbErr> {
+    let Some(auc) = auction::Entity::find_by_id(auction_id).one(db).await? else {
+        return Ok(None);
+    };
+    let now = Utc::now();
+    let now_naive = now.naive_utc();
+    let leaf = auction_leaf(&auc);
+    if
+        auc.status != Some(Status::Active) ||
+        leaf.auction_type != AuctionType::English ||
+        leaf.bought() ||
+        now_naive < auc.start_time ||
+        now_naive > auc.end_time
+    {
+        return Ok(None);
+    }
+
+    let leader = bid::Entity
+        ::find()
+        .filter(bid::Column::AuctionId.eq(auction_id))
+        .filter(bid::Column::RetractedAt.is_null())
+        .order_by_desc(bid::Column::Amount)
+        .order_by_asc(bid::Column::CreatedAt)
+        .one(db).await?
+        .map(|bid| bid.bidder_id);
+    let proxies = proxy_bid::Entity
+        ::find()
+        .filter(proxy_bid::Column::AuctionId.eq(auction_id))
+        .filter(proxy_bid::Column::Active.eq(true))
+        .filter(proxy_bid::Column::Deadline.gte(now.timestamp()))
+        .order_by_desc(proxy_bid::Column::MaxAmount)
+        .order_by_asc(proxy_bid::Column::CreatedAt)
+        .all(db).await?;
+
+    let increment = auc.min_increment.map(|increment| increment as u64).unwrap_or_else(min_increment);
+    let minimum = (auc.current_bid as u64) + increment;
+    let leading = |proxy: &ProxyBidModel| {
+        leader.as_deref().is_some_and(|leader| leader.eq_ignore_ascii_case(&proxy.bidder))
+    };
+    let Some(top) = proxies
+        .iter()
+        .find(|proxy| !leading(proxy) && (proxy.max_amount as u64) >= minimum) else {
+        return Ok(None);
+    };
+    let rival = proxies
+        .iter()
+        .filter(|proxy| proxy.id != top.id)
+        .map(|proxy| proxy.max_amount as u64)
+        .max()
+        .unwrap_or(0)
+        .max(auc.current_bid as u64);
+    let amount = (rival + increment).min(top.max_amount as u64).max(minimum);
+    Ok(Some((top.clone(), amount)))
+}
+
+// Places proxy bids on an auction until no proxy is outbid with room left under its maximum.
+// Each bid goes through the create_bid guest like a signed bid, under the bidder's signed
+// maximum, with `SYSTEM` as the actor.
+pub async fn run_proxy_bids(db: Arc<DatabaseConnection>, auction_id: i32) {
+    let mut last = None;
+    loop {
+        let (proxy, amount) = match next_proxy_bid(&db, auction_id).await {
+            Ok(Some(next)) => next,
+            Ok(None) => {
+                break;
+            }
+            Err(e) => {
+                eprintln!("Proxy bidding on auction {} failed: {}", auction_id, e);
+                break;
+            }
+        };
+        let authorization = BidAuthorization {
+            auction_id: auction_id as u64,
+            amount: proxy.max_amount as u64,
+            nonce: proxy.nonce as u64,
+            deadline: proxy.deadline as u64,
+        };
+        let placed_bid = place_bid(
+            &db,
+            &proxy.bidder,
+            &authorization,
+            &proxy.signature,
+            Some(amount),
+            None,
+            Actor::SYSTEM
+        ).await;
+        match placed_bid {
+            Ok((_, _, sequence)) => {
+                last = Some(sequence);
+            }
+            Err(e) => {
+                eprintln!("Proxy bid for {} on auction {} failed: {}", proxy.bidder, auction_id, e);
+                break;
+            }
+        }
+    }
+    // each bid queued its own commit; they are proven in order, so the last one covers all
+    if let Some(sequence) = last {
+        if proven_commit(&db, sequence).await.is_err() {
+            eprintln!("Failed to prove state after proxy bids on auction {}", auction_id);
+        }
+    }
+}
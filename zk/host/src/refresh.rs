@@ -0,0 +1,141 @@
+use std::sync::Arc;
+
+use axum::Json;
+use chrono::Utc;
+use entity::refresh_token;
+use ethers::core::rand::{ thread_rng, RngCore };
+use sea_orm::{
+    ActiveModelTrait,
+    ColumnTrait,
+    DatabaseConnection,
+    DbErr,
+    EntityTrait,
+    QueryFilter,
+    Set,
+};
+use serde::Deserialize;
+use serde_json::{ json, Value };
+use sha2::{ Digest, Sha256 };
+
+use crate::{ error::ApiError, jwt::JwtKeys, role::login_roles };
+
+#[derive(Deserialize)]
+pub struct RefreshPayload {
+    pub refresh_token: String,
+}
+
+fn hash_token(token: &str) -> String {
+    ethers::utils::hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+// Opaque refresh token; only its hash is stored so a db leak cannot be replayed.
+pub async fn issue_refresh_token(
+    db: &DatabaseConnection,
+    keys: &JwtKeys,
+    address: &str,
+    username: &str
+) -> Result<String, DbErr> {
+    let mut bytes = [0u8; 32];
+    thread_rng().fill_bytes(&mut bytes);
+    let token = ethers::utils::hex::encode(bytes);
+
+    let now = Utc::now().naive_utc();
+    refresh_token::ActiveModel {
+        token_hash: Set(hash_token(&token)),
+        address: Set(address.to_string()),
+        username: Set(username.to_string()),
+        expires_at: Set(now + chrono::Duration::seconds(keys.refresh_ttl)),
+        revoked_at: Set(None),
+        created_at: Set(now),
+        ..Default::default()
+    }.insert(db).await?;
+    Ok(token)
+}
+
+async fn revoke_all(db: &DatabaseConnection, address: &str) -> Result<(), DbErr> {
+    refresh_token::Entity
+        ::update_many()
+        .col_expr(refresh_token::Column::RevokedAt, Utc::now().naive_utc().into())
+        .filter(refresh_token::Column::Address.eq(address))
+        .filter(refresh_token::Column::RevokedAt.is_null())
+        .exec(db).await?;
+    Ok(())
+}
+
+// POST /api/auth/refresh
+// Rotates the refresh token and issues a new access token. Presenting an already revoked
+// token, or the same token in two concurrent requests, means it leaked, so every session of
+// that address is revoked.
+pub async fn refresh_token_handler(
+    axum::extract::State(db): axum::extract::State<Arc<DatabaseConnection>>,
+    axum::extract::State(keys): axum::extract::State<Arc<JwtKeys>>,
+    Json(payload): Json<RefreshPayload>
+) -> Result<Json<Value>, ApiError> {
+    let stored = refresh_token::Entity
+        ::find()
+        .filter(refresh_token::Column::TokenHash.eq(hash_token(&payload.refresh_token)))
+        .one(&*db).await?
+        .ok_or(ApiError::Unauthorized("Invalid refresh token".to_string()))?;
+
+    if stored.revoked_at.is_some() {
+        revoke_all(&db, &stored.address).await?;
+        return Err(ApiError::Unauthorized("Refresh token revoked".to_string()));
+    }
+    let now = Utc::now().naive_utc();
+    if stored.expires_at <= now {
+        return Err(ApiError::Unauthorized("Refresh token expired".to_string()));
+    }
+
+    // only one request can revoke the token; one that loses the race presented it twice
+    let revoked = refresh_token::Entity
+        ::update_many()
+        .col_expr(refresh_token::Column::RevokedAt, Some(now).into())
+        .filter(refresh_token::Column::TokenHash.eq(stored.token_hash.clone()))
+        .filter(refresh_token::Column::RevokedAt.is_null())
+        .exec(&*db).await?;
+    if revoked.rows_affected != 1 {
+        revoke_all(&db, &stored.address).await?;
+        return Err(ApiError::Unauthorized("Refresh token revoked".to_string()));
+    }
+
+    // roles are reloaded so grants and revocations apply from the next refresh
+    let roles = login_roles(&db, &stored.address).await?;
+    let access_token = keys
+        .issue_token(&stored.address, &stored.username, &roles)
+        .map_err(ApiError::Internal)?;
+    let refresh_token = issue_refresh_token(&db, &keys, &stored.address, &stored.username).await?;
+    Ok(
+        Json(
+            json!({
+      "status": "success",
+      "access_token": access_token,
+      "refresh_token": refresh_token,
+      "expires_in": keys.access_ttl
+    })
+        )
+    )
+}
+
+// POST /api/auth/revoke
+pub async fn revoke_token_handler(
+    axum::extract::State(db): axum::extract::State<Arc<DatabaseConnection>>,
+    Json(payload): Json<RefreshPayload>
+) -> Result<Json<Value>, ApiError> {
+    refresh_token::Entity
+        ::update_many()
+        .col_expr(refresh_token::Column::RevokedAt, Utc::now().naive_utc().into())
+        .filter(refresh_token::Column::TokenHash.eq(hash_token(&payload.refresh_token)))
+        .filter(refresh_token::Column::RevokedAt.is_null())
+        .exec(&*db).await?;
+    Ok(Json(json!({
+      "status": "success",
+      "message": "Refresh token revoked"
+    })))
+}
+
+// GET /.well-known/jwks.json
+pub async fn jwks_handler(axum::extract::State(
+    keys,
+): axum::extract::State<Arc<JwtKeys>>) -> Json<Value> {
+    Json(json!(keys.jwks()))
+}
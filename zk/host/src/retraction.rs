@@ -0,0 +1,324 @@
+use std::{ env, sync::Arc };
+
+use axum::Json;
+use car_auction_core::{
+    merkle::{ merkle_root, MerklePath },
+    Actor,
+    AuctionType,
+    BidAction,
+    BidRetraction,
+    RetractionParams,
+    RetractionPolicy,
+    StateAction,
+};
+use chrono::{ Duration, Utc };
+use dotenv::dotenv;
+use entity::{ auction, bid, proxy_bid, BidModel, Status };
+use methods::{ RETRACT_BID_ELF, RETRACT_BID_ID };
+use risc0_zkvm::{ default_prover, ExecutorEnv, Receipt };
+use sea_orm::{
+    ColumnTrait,
+    DatabaseConnection,
+    EntityTrait,
+    PaginatorTrait,
+    QueryFilter,
+    QuerySelect,
+    TransactionTrait,
+};
+use serde::{ Deserialize, Serialize };
+use serde_json::{ json, Value };
+
+use crate::{
+    auction::{ auction_leaf, auction_type, get_all_auctions, get_auction_leaves },
+    auth::AuthUser,
+    bid::{ bid_leaf, get_all_bids, get_bid_leaves },
+    error::ApiError,
+    overall::{ proven_commit, published_state },
+    proxy::run_proxy_bids,
+    sequencer::{ head_commit, submit },
+    SessionStats,
+    PROVE_ATTEMPTS,
+};
+
+// Longest reason kept with a retraction.
+const MAX_REASON_LENGTH: usize = 500;
+// Reason kept with a bid its bidder cancelled.
+const CANCEL_REASON: &str = "Cancelled by the bidder";
+
+// BID_RETRACTION_CUTOFF: seconds before `end_time` after which bids can no longer be retracted.
+// BID_RETRACTION_LIMIT: retractions each bidder gets per auction.
+pub fn retraction_policy() -> RetractionPolicy {
+    dotenv().ok();
+    let cutoff = env
+        ::var("BID_RETRACTION_CUTOFF")
+        .ok()
+        .and_then(|cutoff| cutoff.parse().ok())
+        .unwrap_or(3600);
+    let limit = env
+        ::var("BID_RETRACTION_LIMIT")
+        .ok()
+        .and_then(|limit| limit.parse().ok())
+        .unwrap_or(1);
+    RetractionPolicy { cutoff, limit }
+}
+
+#[derive(Deserialize)]
+pub struct RetractBidPayload {
+    pub reason: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RetractionCommit {
+    pub receipt: Receipt,
+    pub stats: SessionStats,
+}
+impl RetractionCommit {
+    pub fn get_commit(&self) -> Result<BidRetraction, String> {
+        let state = self.receipt.journal.decode().map_err(|e| e.to_string())?;
+        Ok(state)
+    }
+    pub fn verify_and_get_commit(&self) -> Result<BidRetraction, String> {
+        self.receipt.verify(RETRACT_BID_ID).map_err(|e| e.to_string())?;
+        self.get_commit()
+    }
+}
+
+// Proves a bid leaving the auction it was placed on; fails if the policy does not allow it.
+pub fn prove_retraction(params: &RetractionParams) -> Result<RetractionCommit, ApiError> {
+    let env = ExecutorEnv::builder()
+        .write(params)
+        .map_err(|e| ApiError::Proving(e.to_string()))?
+        .build()
+        .map_err(|e| ApiError::Proving(e.to_string()))?;
+    let prover = default_prover();
+    let prove_info = prover
+        .prove(env, RETRACT_BID_ELF)
+        .map_err(|e| ApiError::Proving(e.to_string()))?;
+    Ok(RetractionCommit {
+        receipt: prove_info.receipt,
+        stats: SessionStats {
+            segments: prove_info.stats.segments,
+            total_cycles: prove_info.stats.total_cycles,
+            user_cycles: prove_info.stats.user_cycles,
+            paging_cycles: prove_info.stats.paging_cycles,
+            reserved_cycles: prove_info.stats.reserved_cycles,
+        },
+    })
+}
+
+// Reads the auction in a short transaction that holds its row locked and builds the guest's
+// input. The guest enforces the policy too; it is checked here to answer with a status instead
+// of a failed proof.
+async fn prepare_retraction(
+    db: &DatabaseConnection,
+    bid: &BidModel,
+    reason: &str,
+    actor: &Actor
+) -> Result<RetractionParams, ApiError> {
+    let txn = db.begin().await?;
+    auction::Entity
+        ::find_by_id(bid.auction_id)
+        .lock_exclusive()
+        .one(&txn).await?
+        .ok_or(ApiError::NotFound("Auction not found".to_string()))?;
+    // the state read from here on is the one this commit publishes
+    let head = head_commit(&txn).await?;
+
+    let policy = retraction_policy();
+    let auctions = get_all_auctions(&txn).await?;
+    let index = auctions
+        .iter()
+        .position(|auc| auc.id == bid.auction_id)
+        .ok_or(ApiError::NotFound("Auction not found".to_string()))?;
+    let auc = &auctions[index];
+    if auction_type(auc.auction_type) != AuctionType::English {
+        return Err(ApiError::Validation("Only english bids can be retracted".to_string()));
+    }
+    if auc.status != Some(Status::Active) {
+        return Err(ApiError::Conflict("Auction is not active".to_string()));
+    }
+    let leaf = auction_leaf(auc);
+    if leaf.bought() {
+        return Err(ApiError::Conflict("Auction already bought".to_string()));
+    }
+    let now = Utc::now();
+    if now.naive_utc() > auc.end_time - Duration::seconds(policy.cutoff) {
+        return Err(ApiError::Conflict("Retraction cutoff has passed".to_string()));
+    }
+    let retracted = bid::Entity
+        ::find()
+        .filter(bid::Column::AuctionId.eq(auc.id))
+        .filter(bid::Column::BidderId.eq(bid.bidder_id.clone()))
+        .filter(bid::Column::RetractedAt.is_not_null())
+        .count(&txn).await?;
+    if retracted >= (policy.limit as u64) {
+        return Err(ApiError::RateLimited("Retraction limit reached".to_string()));
+    }
+
+    let bids = get_all_bids(&txn).await?;
+    txn.commit().await?;
+    let published = published_state(db, head).await?;
+    let auc_leaves = get_auction_leaves(&auctions);
+    Ok(RetractionParams {
+        published,
+        auction: leaf,
+        auction_path: MerklePath::new(&auc_leaves, index).ok_or(
+            ApiError::Internal("Auction is not in the auction state".to_string())
+        )?,
+        auction_root: merkle_root(&auc_leaves),
+        bids: bids.iter().map(bid_leaf).collect(),
+        bid_id: bid.id,
+        bidder: bid.bidder_id.clone(),
+        reason: reason.to_string(),
+        policy,
+        timestamp: now.timestamp(),
+        actor: actor.clone(),
+    })
+}
+
+// Marks the bid retracted and moves the auction in one transaction, with the state commit.
+// Nothing is written when the auction or bid tree changed since the proof read them; the
+// caller proves the retraction again. Returns the commit's sequence number.
+async fn apply_retraction(
+    db: &DatabaseConnection,
+    params: &RetractionParams,
+    retraction: &BidRetraction
+) -> Result<Option<i64>, ApiError> {
+    let txn = db.begin().await?;
+    auction::Entity
+        ::find_by_id(retraction.auction_id)
+        .lock_exclusive()
+        .one(&txn).await?
+        .ok_or(ApiError::NotFound("Auction not found".to_string()))?;
+    let auc_leaves = get_auction_leaves(&get_all_auctions(&txn).await?);
+    let bid_leaves = get_bid_leaves(&get_all_bids(&txn).await?);
+    if
+        merkle_root(&auc_leaves) != retraction.auction_root.old ||
+        merkle_root(&bid_leaves) != retraction.bid_root.old
+    {
+        return Ok(None);
+    }
+
+    // the leaves store whole seconds, so the rows take the proof's timestamp
+    let retracted_at = chrono::DateTime
+        ::from_timestamp(retraction.retracted_at, 0)
+        .ok_or(ApiError::Internal("Invalid retraction time".to_string()))?
+        .naive_utc();
+    let marked = bid::Entity
+        ::update_many()
+        .col_expr(bid::Column::RetractedAt, Some(retracted_at).into())
+        .col_expr(bid::Column::RetractionReason, Some(params.reason.clone()).into())
+        .col_expr(bid::Column::UpdatedAt, retracted_at.into())
+        .filter(bid::Column::Id.eq(params.bid_id))
+        .filter(bid::Column::RetractedAt.is_null())
+        .exec(&txn).await?;
+    if marked.rows_affected == 0 {
+        return Err(ApiError::Conflict("Bid already retracted".to_string()));
+    }
+    auction::Entity
+        ::update_many()
+        .col_expr(auction::Column::CurrentBid, (retraction.current_bid as i32).into())
+        .col_expr(auction::Column::BidCount, retraction.bid_count.into())
+        .col_expr(auction::Column::UpdatedAt, retracted_at.into())
+        .filter(auction::Column::Id.eq(retraction.auction_id))
+        .exec(&txn).await?;
+
+    // the caller's proxy would otherwise bid straight back in
+    proxy_bid::Entity
+        ::update_many()
+        .col_expr(proxy_bid::Column::Active, false.into())
+        .col_expr(proxy_bid::Column::UpdatedAt, retracted_at.into())
+        .filter(proxy_bid::Column::AuctionId.eq(retraction.auction_id))
+        .filter(proxy_bid::Column::Bidder.eq(params.bidder.clone()))
+        .filter(proxy_bid::Column::Active.eq(true))
+        .exec(&txn).await?;
+    let action = Some(StateAction::Bid(BidAction::DELETE));
+    let sequence = submit(&txn, &params.actor, action.as_ref()).await?;
+    txn.commit().await?;
+    Ok(Some(sequence))
+}
+
+// Takes back one of the caller's bids on an active english auction, with a reason. The bid is
+// kept and marked retracted; the auction falls back to the highest bid left standing, or to its
+// opening price. Proxy bidding for the caller on that auction stops, and other proxies may
+// answer the lower price. The auction is only locked while it is read and written, not during
+// the proof, which is made again when the auction moved in between.
+async fn retract_bid(
+    db: Arc<DatabaseConnection>,
+    user: &AuthUser,
+    id: i32,
+    reason: &str,
+    message: &str
+) -> Result<Json<Value>, ApiError> {
+    let bid = bid::Entity
+        ::find_by_id(id)
+        .one(&*db).await?
+        .ok_or(ApiError::NotFound("Bid not found".to_string()))?;
+    if !bid.bidder_id.eq_ignore_ascii_case(&user.addr) {
+        return Err(ApiError::Forbidden("Not your bid".to_string()));
+    }
+    if bid.retracted_at.is_some() {
+        return Err(ApiError::Conflict("Bid already retracted".to_string()));
+    }
+
+    for _ in 0..PROVE_ATTEMPTS {
+        let params = prepare_retraction(&db, &bid, reason, &user.actor()).await?;
+        let input = params.clone();
+        let retraction_commit = tokio::task
+            ::spawn_blocking(move || prove_retraction(&input)).await
+            .map_err(|e| ApiError::Internal(e.to_string()))??;
+        let retraction = retraction_commit
+            .get_commit()
+            .map_err(ApiError::Proving)?;
+        let Some(sequence) = apply_retraction(&db, &params, &retraction).await? else {
+            continue;
+        };
+
+        let (hash, commit) = proven_commit(&db, sequence).await?;
+        tokio::spawn(run_proxy_bids(db.clone(), retraction.auction_id));
+        return Ok(
+            Json(
+                json!({
+          "status": "success",
+          "message": message,
+          "data": retraction,
+          "cid": hash,
+          "receipt": commit.receipt,
+          "stats": commit.stats,
+          "retraction_receipt": retraction_commit.receipt,
+          "retraction_stats": retraction_commit.stats
+        })
+            )
+        );
+    }
+    Err(ApiError::Conflict("Auction kept changing while the retraction was proven".to_string()))
+}
+
+// POST /api/bids/{id}/retract
+pub async fn retract_bid_handler(
+    user: AuthUser,
+    axum::extract::Path(id): axum::extract::Path<i32>,
+    axum::extract::State(db): axum::extract::State<Arc<sea_orm::DatabaseConnection>>,
+    Json(payload): Json<RetractBidPayload>
+) -> Result<Json<Value>, ApiError> {
+    let reason = payload.reason.trim().to_string();
+    if reason.is_empty() || reason.len() > MAX_REASON_LENGTH {
+        return Err(
+            ApiError::Validation(
+                format!("A reason of at most {} characters is required", MAX_REASON_LENGTH)
+            )
+        );
+    }
+    retract_bid(db, &user, id, &reason, "bid retracted").await
+}
+
+// DELETE /api/bids/{id}
+// Cancels one of the caller's bids. A cancelled bid is retracted under the same policy, with
+// `CANCEL_REASON`, so it counts towards the bidder's retraction limit.
+pub async fn cancel_bid_handler(
+    user: AuthUser,
+    axum::extract::Path(id): axum::extract::Path<i32>,
+    axum::extract::State(db): axum::extract::State<Arc<sea_orm::DatabaseConnection>>
+) -> Result<Json<Value>, ApiError> {
+    retract_bid(db, &user, id, CANCEL_REASON, "bid cancelled").await
+}
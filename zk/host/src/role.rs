@@ -0,0 +1,179 @@
+use std::env;
+use std::sync::Arc;
+
+use axum::{ extract::Request, middleware::Next, response::Response, Json };
+use car_auction_core::Actor;
+use chrono::Utc;
+use dotenv::dotenv;
+use entity::{ user_role, Role };
+use ethers::types::Address;
+use sea_orm::{
+    ActiveModelTrait,
+    ColumnTrait,
+    DatabaseConnection,
+    DbErr,
+    EntityTrait,
+    QueryFilter,
+    Set,
+};
+use serde::Deserialize;
+use serde_json::{ json, Value };
+
+use crate::auth::AuthUser;
+use crate::error::ApiError;
+
+// Role sets routes declare with `require_roles`. Earlier roles win when picking the acting role.
+pub const ADMINS: &[Role] = &[Role::Admin];
+pub const SELLERS: &[Role] = &[Role::Seller, Role::Admin];
+pub const BIDDERS: &[Role] = &[Role::Bidder];
+
+// The actor recorded in state commits for a request made under `role`.
+pub fn actor_for(role: Role) -> Actor {
+    match role {
+        Role::Admin => Actor::ADMIN,
+        Role::Seller => Actor::SELLER,
+        Role::Bidder => Actor::BIDDER,
+        Role::Inspector => Actor::INSPECTOR,
+    }
+}
+
+// Addresses are stored lowercase so lookups do not depend on checksum casing.
+fn normalize_address(address: &str) -> Result<String, String> {
+    let address = address.parse::<Address>().map_err(|_| "Invalid address".to_string())?;
+    Ok(format!("{:?}", address))
+}
+
+// ADMIN_ADDRESSES bootstraps the first admins, comma separated.
+fn admin_addresses() -> Vec<String> {
+    dotenv().ok();
+    env::var("ADMIN_ADDRESSES")
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|address| normalize_address(address.trim()).ok())
+        .collect()
+}
+
+pub async fn roles_for(db: &DatabaseConnection, address: &str) -> Result<Vec<Role>, DbErr> {
+    let roles = user_role::Entity
+        ::find()
+        .filter(user_role::Column::Address.eq(address.to_lowercase()))
+        .all(db).await?;
+    Ok(
+        roles
+            .into_iter()
+            .map(|r| r.role)
+            .collect()
+    )
+}
+
+async fn grant(
+    db: &DatabaseConnection,
+    address: &str,
+    role: Role,
+    granted_by: Option<String>
+) -> Result<(), DbErr> {
+    user_role::ActiveModel {
+        address: Set(address.to_string()),
+        role: Set(role),
+        granted_by: Set(granted_by),
+        created_at: Set(Utc::now().naive_utc()),
+        ..Default::default()
+    }.insert(db).await?;
+    Ok(())
+}
+
+// Roles to put in a new access token. First logins are granted `bidder`, and addresses in
+// ADMIN_ADDRESSES are granted `admin`.
+pub async fn login_roles(db: &DatabaseConnection, address: &str) -> Result<Vec<Role>, DbErr> {
+    let address = address.to_lowercase();
+    let mut roles = roles_for(db, &address).await?;
+    if roles.is_empty() {
+        grant(db, &address, Role::Bidder, None).await?;
+        roles.push(Role::Bidder);
+    }
+    if !roles.contains(&Role::Admin) && admin_addresses().contains(&address) {
+        grant(db, &address, Role::Admin, None).await?;
+        roles.push(Role::Admin);
+    }
+    Ok(roles)
+}
+
+// Route guard, used as `middleware::from_fn_with_state(SELLERS, require_roles)` after `auth`.
+// Rejects users holding none of the roles and records which one the request acts under.
+pub async fn require_roles(
+    axum::extract::State(allowed): axum::extract::State<&'static [Role]>,
+    mut req: Request,
+    next: Next
+) -> Result<Response, ApiError> {
+    let user = req
+        .extensions()
+        .get::<AuthUser>()
+        .cloned()
+        .ok_or(ApiError::Unauthorized("Missing access token".to_string()))?;
+    let role = allowed
+        .iter()
+        .find(|role| user.roles.contains(role))
+        .copied()
+        .ok_or(ApiError::Forbidden("Missing a required role".to_string()))?;
+    req.extensions_mut().insert(AuthUser { acting: Some(role), ..user });
+    Ok(next.run(req).await)
+}
+
+#[derive(Deserialize)]
+pub struct RolePayload {
+    pub address: String,
+    pub role: Role,
+}
+
+// GET /api/admin/roles/{address}
+pub async fn get_roles_handler(
+    axum::extract::Path(address): axum::extract::Path<String>,
+    axum::extract::State(db): axum::extract::State<Arc<DatabaseConnection>>
+) -> Result<Json<Value>, ApiError> {
+    let address = normalize_address(&address).map_err(ApiError::Validation)?;
+    let roles = roles_for(&db, &address).await?;
+    Ok(Json(json!({
+      "status": "success",
+      "data": { "address": address, "roles": roles }
+    })))
+}
+
+// POST /api/admin/roles
+// Takes effect on the user's next login or token refresh.
+pub async fn grant_role_handler(
+    user: AuthUser,
+    axum::extract::State(db): axum::extract::State<Arc<DatabaseConnection>>,
+    Json(payload): Json<RolePayload>
+) -> Result<Json<Value>, ApiError> {
+    let address = normalize_address(&payload.address).map_err(ApiError::Validation)?;
+    let roles = roles_for(&db, &address).await?;
+    if !roles.contains(&payload.role) {
+        grant(&db, &address, payload.role, Some(user.addr)).await?;
+    }
+    Ok(Json(json!({
+      "status": "success",
+      "message": "Role granted"
+    })))
+}
+
+// DELETE /api/admin/roles
+pub async fn revoke_role_handler(
+    user: AuthUser,
+    axum::extract::State(db): axum::extract::State<Arc<DatabaseConnection>>,
+    Json(payload): Json<RolePayload>
+) -> Result<Json<Value>, ApiError> {
+    let address = normalize_address(&payload.address).map_err(ApiError::Validation)?;
+    // keeps at least one admin able to manage roles
+    if payload.role == Role::Admin && address == user.addr.to_lowercase() {
+        return Err(ApiError::Validation("Cannot revoke your own admin role".to_string()));
+    }
+    user_role::Entity
+        ::delete_many()
+        .filter(user_role::Column::Address.eq(address))
+        .filter(user_role::Column::Role.eq(payload.role))
+        .exec(&*db).await?;
+    Ok(Json(json!({
+      "status": "success",
+      "message": "Role revoked"
+    })))
+}
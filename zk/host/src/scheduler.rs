@@ -0,0 +1,251 @@
+use std::{ env, sync::Arc, time::Duration };
+
+use car_auction_core::Actor;
+use chrono::Utc;
+use dotenv::dotenv;
+use entity::{ auction, auction_transition, car, car_ownership, AuctionOutcome, Status };
+use sea_orm::{
+    sea_query::{ Expr, OnConflict },
+    ColumnTrait,
+    ConnectionTrait,
+    DatabaseConnection,
+    DbErr,
+    EntityTrait,
+    QueryFilter,
+    QueryOrder,
+    QuerySelect,
+    Set,
+    Statement,
+    TransactionTrait,
+};
+
+use crate::{
+    error::ApiError,
+    offer::expire_offers,
+    overall::proven_commit,
+    sequencer::submit,
+    settlement::{ ready_to_settle, settle_auction, transfer_sold_car },
+};
+
+// AUCTION_SCHEDULER_INTERVAL: seconds between lifecycle passes.
+pub fn scheduler_interval() -> Duration {
+    dotenv().ok();
+    let seconds = env
+        ::var("AUCTION_SCHEDULER_INTERVAL")
+        .ok()
+        .and_then(|seconds| seconds.parse().ok())
+        .unwrap_or(30);
+    Duration::from_secs(seconds)
+}
+
+// Records an auction entering `to`, in the transaction that moved it and queued `sequence`.
+// The commit's cid is filled in by `fill_transition_cids` once it is proven. An auction enters
+// each status once, so a transition replayed after a restart is ignored instead of recorded
+// twice.
+pub async fn record_transition<C: ConnectionTrait>(
+    db: &C,
+    auction_id: i32,
+    from: Option<Status>,
+    to: Status,
+    actor: &Actor,
+    sequence: i64
+) -> Result<(), DbErr> {
+    auction_transition::Entity
+        ::insert(auction_transition::ActiveModel {
+            auction_id: Set(auction_id),
+            from_status: Set(from),
+            to_status: Set(to),
+            actor: Set(format!("{:?}", actor)),
+            sequence: Set(Some(sequence)),
+            cid: Set(None),
+            created_at: Set(Utc::now().naive_utc()),
+            ..Default::default()
+        })
+        .on_conflict(
+            OnConflict::columns([
+                auction_transition::Column::AuctionId,
+                auction_transition::Column::ToStatus,
+            ])
+                .do_nothing()
+                .to_owned()
+        )
+        .exec_without_returning(db).await?;
+    Ok(())
+}
+
+// Copies the cid of every proven state commit to the transitions queued with it. Runs after
+// each sequencer pass and when the scheduler starts, so a host that stopped between proving a
+// commit and filling its transitions leaves none without a cid.
+pub async fn fill_transition_cids(db: &DatabaseConnection) -> Result<u64, DbErr> {
+    let filled = db.execute(
+        Statement::from_string(
+            db.get_database_backend(),
+            "UPDATE auction_transition SET cid = state_sequence.cid FROM state_sequence \
+             WHERE auction_transition.cid IS NULL \
+             AND auction_transition.sequence = state_sequence.sequence \
+             AND state_sequence.cid IS NOT NULL".to_string()
+        )
+    ).await?;
+    Ok(filled.rows_affected())
+}
+
+// Moves pending auctions whose `start_time` has passed to active, with their cars. Each update
+// only applies to a still pending auction, so a pass that overlaps another activates nothing
+// twice.
+async fn activate_due(db: &Arc<DatabaseConnection>) -> Result<Vec<i32>, DbErr> {
+    let now = Utc::now().naive_utc();
+    let due = auction::Entity
+        ::find()
+        .filter(auction::Column::Status.eq(Status::Pending))
+        .filter(auction::Column::StartTime.lte(now))
+        .order_by_asc(auction::Column::Id)
+        .all(&**db).await?;
+    let mut activated = vec![];
+    let txn = db.begin().await?;
+    for auc in due {
+        let updated = auction::Entity
+            ::update_many()
+            .col_expr(
+                auction::Column::Status,
+                auction::Column::Status.save_as(Expr::val(Status::Active))
+            )
+            .col_expr(auction::Column::UpdatedAt, now.into())
+            .filter(auction::Column::Id.eq(auc.id))
+            .filter(auction::Column::Status.eq(Status::Pending))
+            .exec(&txn).await?;
+        if updated.rows_affected == 0 {
+            continue;
+        }
+        car::Entity
+            ::update_many()
+            .col_expr(
+                car::Column::AuctionStatus,
+                car::Column::AuctionStatus.save_as(Expr::val(Status::Active))
+            )
+            .filter(car::Column::Id.eq(auc.car_id))
+            .exec(&txn).await?;
+        activated.push(auc.id);
+    }
+    if activated.is_empty() {
+        return Ok(activated);
+    }
+
+    // one state commit covers every auction activated in this pass, and the transitions are
+    // recorded with it, so a status change is never left without its transition
+    let sequence = submit(&txn, &Actor::SYSTEM, None).await?;
+    for id in &activated {
+        let from = Some(Status::Pending);
+        record_transition(&txn, *id, from, Status::Active, &Actor::SYSTEM, sequence).await?;
+    }
+    txn.commit().await?;
+    if proven_commit(db, sequence).await.is_err() {
+        eprintln!("Failed to prove state after activating auctions {:?}", activated);
+    }
+    Ok(activated)
+}
+
+// Settles active auctions that have closed. A 409 means the seller settled it first.
+async fn settle_due(db: &Arc<DatabaseConnection>) -> Result<Vec<i32>, DbErr> {
+    let now = Utc::now().naive_utc();
+    let active = auction::Entity
+        ::find()
+        .filter(auction::Column::Status.eq(Status::Active))
+        .order_by_asc(auction::Column::Id)
+        .all(&**db).await?;
+    let mut settled = vec![];
+    for auc in active.iter().filter(|auc| ready_to_settle(auc, now)) {
+        match settle_auction(db.clone(), auc.id, Actor::SYSTEM).await {
+            Ok(_) => settled.push(auc.id),
+            Err(ApiError::Conflict(_)) => {}
+            Err(e) => eprintln!("Scheduler failed to settle auction {}: {}", auc.id, e),
+        }
+    }
+    Ok(settled)
+}
+
+// Transfers the cars of sold auctions that settled without one, e.g. when the host stopped
+// between settling and transferring.
+async fn transfer_due(db: &Arc<DatabaseConnection>) -> Result<Vec<i32>, DbErr> {
+    let sold: Vec<i32> = auction::Entity
+        ::find()
+        .select_only()
+        .column(auction::Column::Id)
+        .filter(auction::Column::Status.eq(Status::Completed))
+        .filter(auction::Column::Outcome.eq(AuctionOutcome::Sold))
+        .filter(auction::Column::Winner.is_not_null())
+        .into_tuple()
+        .all(&**db).await?;
+    let transferred: Vec<Option<i32>> = car_ownership::Entity
+        ::find()
+        .select_only()
+        .column(car_ownership::Column::AuctionId)
+        .filter(car_ownership::Column::AuctionId.is_in(sold.clone()))
+        .into_tuple()
+        .all(&**db).await?;
+    let mut done = vec![];
+    let mut last = None;
+    for id in sold.into_iter().filter(|id| !transferred.contains(&Some(*id))) {
+        match transfer_sold_car(db, id, Actor::SYSTEM).await {
+            Ok(transfer) => {
+                last = transfer.map(|(_, sequence)| sequence).or(last);
+                done.push(id);
+            }
+            Err(e) => eprintln!("Scheduler failed to transfer car of auction {}: {}", id, e),
+        }
+    }
+    if let Some(sequence) = last {
+        if proven_commit(db, sequence).await.is_err() {
+            eprintln!("Failed to prove state after transferring cars of auctions {:?}", done);
+        }
+    }
+    Ok(done)
+}
+
+// Expires offers past `expires_at`, with one state commit for all of them.
+async fn expire_due(db: &Arc<DatabaseConnection>) -> Result<u64, DbErr> {
+    let txn = db.begin().await?;
+    let expired = expire_offers(&txn).await?;
+    if expired == 0 {
+        return Ok(0);
+    }
+    let sequence = submit(&txn, &Actor::SYSTEM, None).await?;
+    txn.commit().await?;
+    if proven_commit(db, sequence).await.is_err() {
+        eprintln!("Failed to prove state after expiring {} offers", expired);
+    }
+    Ok(expired)
+}
+
+// Runs the auction lifecycle: activates auctions at `start_time`, settles them once closed and
+// hands sold cars to their winners. Offers past their expiry are expired along the way.
+// All progress is in the auction rows, so after a restart the first pass picks up whatever
+// came due while the host was down.
+pub async fn run_scheduler(db: Arc<DatabaseConnection>) {
+    let interval = scheduler_interval();
+    if let Err(e) = fill_transition_cids(&db).await {
+        eprintln!("Scheduler failed to fill transition cids: {}", e);
+    }
+    loop {
+        match activate_due(&db).await {
+            Ok(ids) if !ids.is_empty() => eprintln!("Scheduler activated auctions {:?}", ids),
+            Ok(_) => {}
+            Err(e) => eprintln!("Scheduler failed to activate auctions: {}", e),
+        }
+        match settle_due(&db).await {
+            Ok(ids) if !ids.is_empty() => eprintln!("Scheduler settled auctions {:?}", ids),
+            Ok(_) => {}
+            Err(e) => eprintln!("Scheduler failed to settle auctions: {}", e),
+        }
+        match transfer_due(&db).await {
+            Ok(ids) if !ids.is_empty() => eprintln!("Scheduler transferred cars for {:?}", ids),
+            Ok(_) => {}
+            Err(e) => eprintln!("Scheduler failed to transfer cars: {}", e),
+        }
+        match expire_due(&db).await {
+            Ok(0) => {}
+            Ok(expired) => eprintln!("Scheduler expired {} offers", expired),
+            Err(e) => eprintln!("Scheduler failed to expire offers: {}", e),
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
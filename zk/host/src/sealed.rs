@@ -0,0 +1,506 @@
+use std::sync::Arc;
+
+use axum::Json;
+use car_auction_core::{
+    merkle::{ merkle_root, MerklePath },
+    sealed::{
+        seal_commitment,
+        valid_commitment,
+        SealedBid,
+        SealedCommitment,
+        SealedCommitmentParams,
+        SealedReveal,
+        SealedSettlement,
+        SealedSettlementParams,
+    },
+    Actor,
+    AuctionAction,
+    OverallState,
+    StateAction,
+};
+use chrono::Utc;
+use entity::{ auction, sealed_bid, AuctionModel, SealedBidModel, Status };
+use methods::{ COMMIT_SEALED_ELF, COMMIT_SEALED_ID, SETTLE_SEALED_ELF, SETTLE_SEALED_ID };
+use risc0_zkvm::{ default_prover, ExecutorEnv, Receipt };
+use sea_orm::{
+    sea_query::Expr,
+    ActiveModelTrait,
+    ColumnTrait,
+    ConnectionTrait,
+    DatabaseConnection,
+    DatabaseTransaction,
+    EntityTrait,
+    QueryFilter,
+    QueryOrder,
+    QuerySelect,
+    Set,
+    TransactionTrait,
+};
+use serde::{ Deserialize, Serialize };
+use serde_json::{ json, Value };
+
+use crate::{
+    auction::{
+        auction_leaf,
+        auction_outcome,
+        auction_type,
+        check_auction_leaf,
+        get_all_auctions,
+        get_auction_leaves,
+    },
+    auth::AuthUser,
+    error::ApiError,
+    next_id,
+    overall::{ proven_commit, published_state },
+    sequencer::{ head_commit, lock_head, submit },
+    SessionStats,
+    PROVE_ATTEMPTS,
+};
+
+#[derive(Deserialize)]
+pub struct CommitmentPayload {
+    // keccak256(abi.encodePacked(uint256 amount, bytes32 salt))
+    pub commitment: String,
+}
+
+#[derive(Deserialize)]
+pub struct RevealPayload {
+    pub amount: i32,
+    pub salt: String,
+}
+
+fn sealed_bid(bid: &SealedBidModel) -> SealedBid {
+    SealedBid {
+        id: bid.id,
+        auction_id: bid.auction_id,
+        bidder: bid.bidder.clone(),
+        commitment: bid.commitment.clone(),
+        created_at: bid.created_at.and_utc().timestamp(),
+    }
+}
+
+fn sealed_reveal(bid: &SealedBidModel) -> Option<SealedReveal> {
+    Some(SealedReveal {
+        bid_id: bid.id,
+        amount: bid.amount? as u64,
+        salt: bid.salt.clone()?,
+        revealed_at: bid.revealed_at?.and_utc().timestamp(),
+    })
+}
+
+async fn find_sealed_auction(
+    db: &DatabaseConnection,
+    id: i32
+) -> Result<AuctionModel, ApiError> {
+    let auction = auction::Entity
+        ::find_by_id(id)
+        .one(db).await?
+        .ok_or(ApiError::NotFound("Auction not found".to_string()))?;
+    if !auction_type(auction.auction_type).is_sealed() {
+        return Err(ApiError::Validation("Auction is not sealed".to_string()));
+    }
+    if auction.status != Some(Status::Active) {
+        return Err(ApiError::Validation("Auction is not active".to_string()));
+    }
+    Ok(auction)
+}
+
+async fn find_commitments<C: ConnectionTrait>(
+    db: &C,
+    auction_id: i32
+) -> Result<Vec<SealedBidModel>, ApiError> {
+    sealed_bid::Entity
+        ::find()
+        .filter(sealed_bid::Column::AuctionId.eq(auction_id))
+        .order_by_asc(sealed_bid::Column::Id)
+        .all(db).await
+        .map_err(ApiError::Db)
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SealedCommitmentCommit {
+    pub receipt: Receipt,
+    pub stats: SessionStats,
+}
+impl SealedCommitmentCommit {
+    pub fn get_commit(&self) -> Result<SealedCommitment, String> {
+        let state = self.receipt.journal.decode().map_err(|e| e.to_string())?;
+        Ok(state)
+    }
+    pub fn verify_and_get_commit(&self) -> Result<SealedCommitment, String> {
+        self.receipt.verify(COMMIT_SEALED_ID).map_err(|e| e.to_string())?;
+        self.get_commit()
+    }
+}
+
+pub fn prove_commitment(
+    params: &SealedCommitmentParams
+) -> Result<SealedCommitmentCommit, ApiError> {
+    let env = ExecutorEnv::builder()
+        .write(params)
+        .map_err(|e| ApiError::Proving(e.to_string()))?
+        .build()
+        .map_err(|e| ApiError::Proving(e.to_string()))?;
+    let prover = default_prover();
+    let prove_info = prover
+        .prove(env, COMMIT_SEALED_ELF)
+        .map_err(|e| ApiError::Proving(e.to_string()))?;
+    Ok(SealedCommitmentCommit {
+        receipt: prove_info.receipt,
+        stats: SessionStats {
+            segments: prove_info.stats.segments,
+            total_cycles: prove_info.stats.total_cycles,
+            user_cycles: prove_info.stats.user_cycles,
+            paging_cycles: prove_info.stats.paging_cycles,
+            reserved_cycles: prove_info.stats.reserved_cycles,
+        },
+    })
+}
+
+// Checks a commitment against the auction under the submit lock and reads what the
+// commit_sealed guest proves it over: the auction, the commitments already made on it, and the
+// published state commit holding both.
+async fn prepare_commitment(
+    db: &DatabaseConnection,
+    id: i32,
+    bidder: &str,
+    commitment: &str,
+    actor: &Actor
+) -> Result<SealedCommitmentParams, ApiError> {
+    let txn = db.begin().await?;
+    // the state read from here on is the one this commit publishes
+    let head = head_commit(&txn).await?;
+    let auctions = get_all_auctions(&txn).await?;
+    let index = auctions
+        .iter()
+        .position(|auc| auc.id == id)
+        .ok_or(ApiError::NotFound("Auction not found".to_string()))?;
+    let auc = &auctions[index];
+    if !auction_type(auc.auction_type).is_sealed() {
+        return Err(ApiError::Validation("Auction is not sealed".to_string()));
+    }
+    if auc.status != Some(Status::Active) {
+        return Err(ApiError::Validation("Auction is not active".to_string()));
+    }
+    let now = Utc::now();
+    if now.naive_utc() < auc.start_time || now.naive_utc() > auc.end_time {
+        return Err(ApiError::Validation("Bidding window is closed".to_string()));
+    }
+    let commitments = find_commitments(&txn, id).await?;
+    if commitments.iter().any(|bid| bid.bidder.eq_ignore_ascii_case(bidder)) {
+        return Err(ApiError::Conflict("Already committed to this auction".to_string()));
+    }
+    // the proof commits the commitment's id, so it is taken from the sequence before proving
+    let bid_id = next_id(&txn, "sealed_bid").await?;
+    txn.commit().await?;
+    let published = published_state(db, head).await?;
+    let auc_leaves = get_auction_leaves(&auctions);
+    Ok(SealedCommitmentParams {
+        published,
+        auction: auction_leaf(auc),
+        auction_path: MerklePath::new(&auc_leaves, index).ok_or(
+            ApiError::Internal("Auction is not in the auction state".to_string())
+        )?,
+        auction_root: merkle_root(&auc_leaves),
+        commitments: commitments.iter().map(sealed_bid).collect(),
+        bid: SealedBid {
+            id: bid_id,
+            auction_id: id,
+            bidder: bidder.to_string(),
+            commitment: commitment.to_string(),
+            created_at: now.timestamp(),
+        },
+        timestamp: now.timestamp(),
+        actor: actor.clone(),
+    })
+}
+
+// Stores a proven commitment and moves its auction to the new count and commitment root in one
+// transaction, with its state commit. Nothing is written when the auction's leaf, which holds
+// its commitment count and root, changed while the proof ran, and the caller proves the
+// commitment again; changes to other auctions do not count. Returns the commit's sequence number.
+async fn apply_commitment(
+    db: &DatabaseConnection,
+    params: &SealedCommitmentParams,
+    commitment: &SealedCommitment
+) -> Result<Option<i64>, ApiError> {
+    let txn = db.begin().await?;
+    // the row first, as every writer locks it before queuing, then the submit lock, so nothing
+    // is queued between the check below and this commit
+    let current = auction::Entity
+        ::find_by_id(commitment.bid.auction_id)
+        .lock_exclusive()
+        .one(&txn).await?
+        .ok_or(ApiError::NotFound("Auction not found".to_string()))?;
+    lock_head(&txn).await?;
+    if auction_leaf(&current) != params.auction {
+        return Ok(None);
+    }
+
+    // the leaves store whole seconds, so the rows take the proof's timestamp
+    let now = chrono::DateTime
+        ::from_timestamp(params.timestamp, 0)
+        .ok_or(ApiError::Internal("Invalid commitment time".to_string()))?
+        .naive_utc();
+    sealed_bid::ActiveModel {
+        id: Set(commitment.bid.id),
+        auction_id: Set(commitment.bid.auction_id),
+        bidder: Set(commitment.bid.bidder.clone()),
+        commitment: Set(commitment.bid.commitment.clone()),
+        created_at: Set(now),
+        ..Default::default()
+    }
+        .insert(&txn).await?;
+    auction::Entity
+        ::update_many()
+        .col_expr(auction::Column::BidCount, (commitment.commitments as i32).into())
+        .col_expr(
+            auction::Column::CommitmentRoot,
+            Some(commitment.commitment_root.clone()).into()
+        )
+        .col_expr(auction::Column::UpdatedAt, now.into())
+        .filter(auction::Column::Id.eq(commitment.bid.auction_id))
+        .exec(&txn).await?;
+    check_auction_leaf(
+        &txn,
+        commitment.bid.auction_id,
+        &params.auction_path,
+        &commitment.auction_root
+    ).await?;
+    let action = Some(StateAction::Auction(AuctionAction::UPDATE));
+    let sequence = submit(&txn, &params.actor, action.as_ref()).await?;
+    txn.commit().await?;
+    Ok(Some(sequence))
+}
+
+// POST /api/auctions/{id}/sealed-bids
+// Takes one commitment per bidder while bidding is open. The amount is not sent. The
+// commit_sealed guest appends the commitment to the auction's commitment root, which the
+// settle_sealed guest later opens, so the host cannot add or drop commitments in between. An
+// auction that changed while the proof ran is proven again, up to `PROVE_ATTEMPTS` times.
+pub async fn commit_sealed_bid_handler(
+    user: AuthUser,
+    axum::extract::Path(id): axum::extract::Path<i32>,
+    axum::extract::State(db): axum::extract::State<Arc<DatabaseConnection>>,
+    Json(payload): Json<CommitmentPayload>
+) -> Result<Json<Value>, ApiError> {
+    let commitment = payload.commitment.to_lowercase();
+    let digits = commitment.strip_prefix("0x").unwrap_or(&commitment);
+    let commitment = format!("0x{}", digits);
+    if !valid_commitment(&commitment) {
+        return Err(ApiError::Validation("Invalid commitment".to_string()));
+    }
+
+    for _ in 0..PROVE_ATTEMPTS {
+        let params = prepare_commitment(&db, id, &user.addr, &commitment, &user.actor()).await?;
+        let proving = params.clone();
+        let commitment_commit = tokio::task
+            ::spawn_blocking(move || prove_commitment(&proving)).await
+            .map_err(|e| ApiError::Internal(e.to_string()))??;
+        let proven = commitment_commit
+            .get_commit()
+            .map_err(ApiError::Proving)?;
+        let Some(sequence) = apply_commitment(&db, &params, &proven).await? else {
+            continue;
+        };
+        let (hash, commit) = proven_commit(&db, sequence).await?;
+        return Ok(
+            Json(
+                json!({
+      "status": "success",
+      "message": "commitment received",
+      "data": { "id": proven.bid.id, "commitment": proven.bid.commitment },
+      "cid": hash,
+      "receipt": commit.receipt,
+      "stats": commit.stats,
+      "commitment_receipt": commitment_commit.receipt,
+      "commitment_stats": commitment_commit.stats,
+      "commitment_root": proven.commitment_root,
+      "auction_root": proven.auction_root
+    })
+            )
+        );
+    }
+    Err(ApiError::Conflict("Auction kept changing while the commitment was proven".to_string()))
+}
+
+// POST /api/auctions/{id}/sealed-bids/reveal
+// Opens the caller's commitment between `end_time` and `reveal_end_time`. The amount is kept
+// for the settle_sealed guest and never returned.
+pub async fn reveal_sealed_bid_handler(
+    user: AuthUser,
+    axum::extract::Path(id): axum::extract::Path<i32>,
+    axum::extract::State(db): axum::extract::State<Arc<DatabaseConnection>>,
+    Json(payload): Json<RevealPayload>
+) -> Result<Json<Value>, ApiError> {
+    let auction = find_sealed_auction(&db, id).await?;
+    let now = Utc::now().naive_utc();
+    let reveal_end = auction.reveal_end_time.unwrap_or(auction.end_time);
+    if now <= auction.end_time || now > reveal_end {
+        return Err(ApiError::Validation("Reveal window is closed".to_string()));
+    }
+    if payload.amount <= 0 {
+        return Err(ApiError::Validation("Invalid bid amount".to_string()));
+    }
+    let bid = sealed_bid::Entity
+        ::find()
+        .filter(sealed_bid::Column::AuctionId.eq(id))
+        .filter(sealed_bid::Column::Bidder.eq(user.addr.clone()))
+        .one(&*db).await?
+        .ok_or(ApiError::NotFound("No commitment for this auction".to_string()))?;
+    if bid.revealed_at.is_some() {
+        return Err(ApiError::Conflict("Bid already revealed".to_string()));
+    }
+    let commitment = seal_commitment(payload.amount as u64, &payload.salt)
+        .map_err(|e| ApiError::Validation(e.to_string()))?;
+    if !commitment.eq_ignore_ascii_case(&bid.commitment) {
+        return Err(ApiError::Validation("Reveal does not match the commitment".to_string()));
+    }
+
+    let mut revealed: sealed_bid::ActiveModel = bid.into();
+    revealed.amount = Set(Some(payload.amount));
+    revealed.salt = Set(Some(payload.salt));
+    revealed.revealed_at = Set(Some(now));
+    revealed.update(&*db).await?;
+    Ok(Json(json!({
+      "status": "success",
+      "message": "bid revealed"
+    })))
+}
+
+// GET /api/auctions/{id}/sealed-bids
+// Commitments on a sealed auction, without amounts or salts.
+pub async fn get_sealed_bids_handler(
+    axum::extract::Path(id): axum::extract::Path<i32>,
+    axum::extract::State(db): axum::extract::State<Arc<DatabaseConnection>>
+) -> Result<Json<Value>, ApiError> {
+    let bids: Vec<Value> = find_commitments(&*db, id).await?
+        .iter()
+        .map(|bid|
+            json!({
+          "id": bid.id,
+          "bidder": bid.bidder,
+          "commitment": bid.commitment,
+          "created_at": bid.created_at,
+          "revealed": bid.revealed_at.is_some()
+        })
+        )
+        .collect();
+    Ok(Json(json!({
+      "status": "success",
+      "data": bids
+    })))
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SealedSettlementCommit {
+    pub receipt: Receipt,
+    pub stats: SessionStats,
+}
+impl SealedSettlementCommit {
+    pub fn get_commit(&self) -> Result<SealedSettlement, String> {
+        let state = self.receipt.journal.decode().map_err(|e| e.to_string())?;
+        Ok(state)
+    }
+    pub fn verify_and_get_commit(&self) -> Result<SealedSettlement, String> {
+        self.receipt.verify(SETTLE_SEALED_ID).map_err(|e| e.to_string())?;
+        self.get_commit()
+    }
+}
+
+pub fn prove_sealed_settlement(
+    params: &SealedSettlementParams
+) -> Result<SealedSettlementCommit, ApiError> {
+    let env = ExecutorEnv::builder()
+        .write(params)
+        .map_err(|e| ApiError::Proving(e.to_string()))?
+        .build()
+        .map_err(|e| ApiError::Proving(e.to_string()))?;
+    let prover = default_prover();
+    let prove_info = prover
+        .prove(env, SETTLE_SEALED_ELF)
+        .map_err(|e| ApiError::Proving(e.to_string()))?;
+    Ok(SealedSettlementCommit {
+        receipt: prove_info.receipt,
+        stats: SessionStats {
+            segments: prove_info.stats.segments,
+            total_cycles: prove_info.stats.total_cycles,
+            user_cycles: prove_info.stats.user_cycles,
+            paging_cycles: prove_info.stats.paging_cycles,
+            reserved_cycles: prove_info.stats.reserved_cycles,
+        },
+    })
+}
+
+// Sealed settlement, called from `settle_auction_handler` once the reveal window closed. The
+// auction is completed in the returned transaction, for the caller to finish and commit.
+pub async fn settle_sealed(
+    actor: Actor,
+    db: &DatabaseConnection,
+    published: OverallState,
+    auctions: &[AuctionModel],
+    index: usize,
+    (car, car_path): (String, MerklePath)
+) -> Result<(Value, Receipt, SessionStats, DatabaseTransaction), ApiError> {
+    let auc = &auctions[index];
+    let bids = find_commitments(db, auc.id).await?;
+    let auc_leaves = get_auction_leaves(auctions);
+    let params = SealedSettlementParams {
+        published,
+        auction: auction_leaf(auc),
+        auction_path: MerklePath::new(&auc_leaves, index).ok_or(
+            ApiError::Internal("Auction is not in the auction state".to_string())
+        )?,
+        auction_root: merkle_root(&auc_leaves),
+        bids: bids.iter().map(sealed_bid).collect(),
+        reveals: bids.iter().filter_map(sealed_reveal).collect(),
+        car,
+        car_path,
+        timestamp: Utc::now().timestamp(),
+        actor,
+    };
+    let settlement_commit = prove_sealed_settlement(&params)?;
+    let settlement = settlement_commit
+        .get_commit()
+        .map_err(ApiError::Proving)?;
+
+    // the leaf stores whole seconds, so the row takes the proof's timestamp
+    let settled_at = chrono::DateTime
+        ::from_timestamp(params.timestamp, 0)
+        .ok_or(ApiError::Internal("Invalid settlement time".to_string()))?
+        .naive_utc();
+    // the winning amount becomes the auction's public price; losing ones stay in sealed_bid
+    let txn = db.begin().await?;
+    let current_bid = if settlement.winner.is_some() {
+        settlement.amount as i32
+    } else {
+        auc.current_bid
+    };
+    let updated = auction::Entity
+        ::update_many()
+        .col_expr(
+            auction::Column::Status,
+            auction::Column::Status.save_as(Expr::val(Status::Completed))
+        )
+        .col_expr(auction::Column::CurrentBid, current_bid.into())
+        .col_expr(auction::Column::BidCount, (settlement.commitments as i32).into())
+        .col_expr(auction::Column::Winner, settlement.winner.clone().into())
+        .col_expr(
+            auction::Column::Outcome,
+            auction::Column::Outcome.save_as(Expr::val(auction_outcome(settlement.outcome)))
+        )
+        .col_expr(auction::Column::SettledAt, Some(settled_at).into())
+        .col_expr(
+            auction::Column::SettlementReceipt,
+            serde_json::to_value(&settlement_commit.receipt).ok().into()
+        )
+        .col_expr(auction::Column::UpdatedAt, settled_at.into())
+        .filter(auction::Column::Id.eq(auc.id))
+        .filter(auction::Column::Status.eq(Status::Active))
+        .exec(&txn).await?;
+    if updated.rows_affected == 0 {
+        return Err(ApiError::Conflict("Auction changed while settling".to_string()));
+    }
+    check_auction_leaf(&txn, auc.id, &params.auction_path, &settlement.auction_root).await?;
+    Ok((json!(settlement), settlement_commit.receipt, settlement_commit.stats, txn))
+}
@@ -0,0 +1,305 @@
+use std::{ env, sync::Arc, time::Duration };
+
+use axum::Json;
+use car_auction_core::{ Actor, OverallState, StateAction, StateLeaves };
+use chrono::Utc;
+use dotenv::dotenv;
+use entity::{ state_sequence, SequenceStatus, StateSequenceModel };
+use risc0_zkvm::{ Digest, Receipt };
+use sea_orm::{
+    ActiveModelTrait,
+    ColumnTrait,
+    ConnectionTrait,
+    DatabaseConnection,
+    DatabaseTransaction,
+    DbErr,
+    EntityTrait,
+    QueryFilter,
+    QueryOrder,
+    QuerySelect,
+    Set,
+    Statement,
+    TransactionTrait,
+};
+use serde_json::{ json, Value };
+use tokio::{ sync::Notify, time::Instant };
+
+use crate::{
+    auction::{ get_all_auctions, get_auction_leaves },
+    bid::{ get_all_bids, get_bid_leaves },
+    car::get_car_state_leaves,
+    error::ApiError,
+    overall::{ init_overall, OverallCommit },
+    scheduler::fill_transition_cids,
+    sync_state,
+};
+
+// Postgres advisory lock held by the host proving the queue. Any number of hosts queue commits,
+// one at a time proves them.
+const SEQUENCER_LOCK: i64 = 0x7a6b_6361_7273;
+
+// Postgres advisory lock every transaction queuing a commit holds until it commits. A commit's
+// snapshot then holds every change queued before it and none queued after it.
+const SUBMIT_LOCK: i64 = 0x7a6b_7375_626d;
+
+// How often a caller checks whether its commit was proven.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+// Wakes this host's sequencer as soon as a commit is queued here.
+static QUEUED: Notify = Notify::const_new();
+
+// SEQUENCER_INTERVAL: seconds between passes over the queue, for commits queued by other hosts.
+pub fn sequencer_interval() -> Duration {
+    dotenv().ok();
+    let seconds = env
+        ::var("SEQUENCER_INTERVAL")
+        .ok()
+        .and_then(|seconds| seconds.parse().ok())
+        .unwrap_or(5);
+    Duration::from_secs(seconds)
+}
+
+// SEQUENCER_TIMEOUT: seconds a caller waits for its commit before giving up on it.
+pub fn sequencer_timeout() -> Duration {
+    dotenv().ok();
+    let seconds = env
+        ::var("SEQUENCER_TIMEOUT")
+        .ok()
+        .and_then(|seconds| seconds.parse().ok())
+        .unwrap_or(900);
+    Duration::from_secs(seconds)
+}
+
+// Reads the leaves of the three sub-states as they are now.
+pub async fn read_leaves<C: ConnectionTrait>(db: &C) -> Result<StateLeaves, DbErr> {
+    let auctions = get_all_auctions(db).await?;
+    let bids = get_all_bids(db).await?;
+    Ok(StateLeaves {
+        car: get_car_state_leaves(db).await?,
+        auction: get_auction_leaves(&auctions),
+        bid: get_bid_leaves(&bids),
+    })
+}
+
+// Takes the submit lock for the rest of `txn` and returns the last queued commit. While the
+// lock is held no other change can be queued, so the state `txn` reads is that commit's.
+pub async fn lock_head(txn: &DatabaseTransaction) -> Result<Option<StateSequenceModel>, DbErr> {
+    txn.execute(
+        Statement::from_string(
+            txn.get_database_backend(),
+            format!("SELECT pg_advisory_xact_lock({})", SUBMIT_LOCK)
+        )
+    ).await?;
+    state_sequence::Entity
+        ::find()
+        .order_by_desc(state_sequence::Column::Sequence)
+        .one(txn).await
+}
+
+// Queues a state commit from the transaction that made the change, after its last write, and
+// returns its sequence number. The commit proves the leaves as `txn` leaves them, so each
+// commit covers exactly the change it records. Numbers only grow, and commits are proven in
+// their order.
+pub async fn submit(
+    txn: &DatabaseTransaction,
+    actor: &Actor,
+    action: Option<&StateAction>
+) -> Result<i64, DbErr> {
+    lock_head(txn).await?;
+    let leaves = read_leaves(txn).await?;
+    let queued = state_sequence::ActiveModel {
+        actor: Set(json!(actor)),
+        action: Set(action.map(|action| json!(action))),
+        leaves: Set(json!(leaves)),
+        status: Set(SequenceStatus::Pending),
+        created_at: Set(Utc::now().naive_utc()),
+        ..Default::default()
+    }.insert(txn).await?;
+    Ok(queued.sequence)
+}
+
+// Takes the submit lock for the rest of `txn` and returns the commit that holds the state `txn`
+// reads, for an action to be proven against once it is published. That is the last queued
+// commit when it snapshotted exactly this state and did not fail; otherwise a SYSTEM commit of
+// the state is queued.
+pub async fn head_commit(txn: &DatabaseTransaction) -> Result<i64, DbErr> {
+    let head = lock_head(txn).await?;
+    let leaves = read_leaves(txn).await?;
+    if let Some(head) = head {
+        let snapshot = serde_json::from_value::<StateLeaves>(head.leaves).ok();
+        if head.status != SequenceStatus::Failed && snapshot.as_ref() == Some(&leaves) {
+            return Ok(head.sequence);
+        }
+    }
+    submit(txn, &Actor::SYSTEM, None).await
+}
+
+// Waits for a queued commit to be proven or to fail, whichever host proves it. Called once the
+// submitting transaction committed, so this host's sequencer is woken for it here.
+pub async fn wait_for(db: &DatabaseConnection, sequence: i64) -> Result<StateSequenceModel, DbErr> {
+    QUEUED.notify_one();
+    let deadline = Instant::now() + sequencer_timeout();
+    loop {
+        let commit = state_sequence::Entity
+            ::find_by_id(sequence)
+            .one(db).await?
+            .ok_or(DbErr::RecordNotFound(format!("State commit {} not found", sequence)))?;
+        if commit.status != SequenceStatus::Pending {
+            return Ok(commit);
+        }
+        if Instant::now() >= deadline {
+            return Err(DbErr::Custom(format!("State commit {} was not proven in time", sequence)));
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+// Root a proven commit moved the state to, read back from its receipt.
+fn new_root(commit: &StateSequenceModel) -> Result<Digest, String> {
+    let receipt = commit.receipt.clone().ok_or("Proven commit without a receipt".to_string())?;
+    let receipt: Receipt = serde_json::from_value(receipt).map_err(|e| e.to_string())?;
+    let state: OverallState = receipt.journal.decode().map_err(|e| e.to_string())?;
+    Ok(state.new_state)
+}
+
+// Proves a queued commit over the leaves it snapshotted, chained to the last proven commit.
+async fn prove_commit(
+    commit: &StateSequenceModel,
+    previous: Option<&StateSequenceModel>
+) -> Result<(OverallState, String, OverallCommit), String> {
+    let leaves: StateLeaves = serde_json
+        ::from_value(commit.leaves.clone())
+        .map_err(|e| e.to_string())?;
+    let actor: Actor = serde_json::from_value(commit.actor.clone()).map_err(|e| e.to_string())?;
+    let action: Option<StateAction> = commit.action
+        .clone()
+        .map(serde_json::from_value)
+        .transpose()
+        .map_err(|e| e.to_string())?;
+    // an in-place change is checked against the leaves the previous commit was proven over
+    let previous_leaves = match previous {
+        Some(previous) if action.as_ref().is_some_and(StateAction::in_place) =>
+            Some(serde_json::from_value(previous.leaves.clone()).map_err(|e| e.to_string())?),
+        _ => None,
+    };
+    let previous = previous.map(new_root).transpose()?;
+
+    let result = init_overall(
+        leaves,
+        actor,
+        action,
+        Some(commit.sequence as u64),
+        previous,
+        previous_leaves
+    ).map_err(|e| e.to_string())?;
+    let state = result.get_commit()?;
+    let cid = sync_state(&result).await.map_err(|e| e.to_string())?;
+    Ok((state, cid, result))
+}
+
+// One pass over the queue. The lock belongs to the pass's transaction, so it is released when
+// the pass ends, or when the host dies mid-pass and another one takes over.
+async fn drain(db: &DatabaseConnection) -> Result<usize, DbErr> {
+    let txn = db.begin().await?;
+    let locked = txn
+        .query_one(
+            Statement::from_string(
+                txn.get_database_backend(),
+                format!("SELECT pg_try_advisory_xact_lock({}) AS locked", SEQUENCER_LOCK)
+            )
+        ).await?
+        .map(|row| row.try_get::<bool>("", "locked"))
+        .transpose()?
+        .unwrap_or(false);
+    if !locked {
+        return Ok(0);
+    }
+
+    let mut done = 0;
+    loop {
+        let Some(commit) = state_sequence::Entity
+            ::find()
+            .filter(state_sequence::Column::Status.eq(SequenceStatus::Pending))
+            .order_by_asc(state_sequence::Column::Sequence)
+            .one(db).await? else {
+            break;
+        };
+        let previous = state_sequence::Entity
+            ::find()
+            .filter(state_sequence::Column::Status.eq(SequenceStatus::Proven))
+            .order_by_desc(state_sequence::Column::Sequence)
+            .one(db).await?;
+
+        // results are written outside the lock's transaction so waiting callers see them at once
+        let mut update: state_sequence::ActiveModel = commit.clone().into();
+        match prove_commit(&commit, previous.as_ref()).await {
+            Ok((state, cid, result)) => {
+                update.status = Set(SequenceStatus::Proven);
+                update.old_root = Set(previous.as_ref().map(|_| state.old_state.to_string()));
+                update.new_root = Set(Some(state.new_state.to_string()));
+                update.cid = Set(Some(cid));
+                update.receipt = Set(serde_json::to_value(&result.receipt).ok());
+                update.stats = Set(serde_json::to_value(result.stats).ok());
+            }
+            Err(e) => {
+                eprintln!("Sequencer failed to prove state commit {}: {}", commit.sequence, e);
+                update.status = Set(SequenceStatus::Failed);
+                update.error = Set(Some(e));
+            }
+        }
+        update.proven_at = Set(Some(Utc::now().naive_utc()));
+        update.update(db).await?;
+        done += 1;
+    }
+    if done > 0 {
+        fill_transition_cids(db).await?;
+    }
+    txn.commit().await?;
+    Ok(done)
+}
+
+// Proves queued state commits one after the other, each from the root the previous one left,
+// so the published commits form a single history. Every host runs this, and the advisory lock
+// lets only one of them prove at a time.
+pub async fn run_sequencer(db: Arc<DatabaseConnection>) {
+    let interval = sequencer_interval();
+    loop {
+        match drain(&db).await {
+            Ok(0) => {}
+            Ok(done) => eprintln!("Sequencer proved {} state commits", done),
+            Err(e) => eprintln!("Sequencer pass failed: {}", e),
+        }
+        // woken early by commits queued on this host
+        let _ = tokio::time::timeout(interval, QUEUED.notified()).await;
+    }
+}
+
+// GET /api/state/sequence
+// The latest state commits, newest first: each one's sequence number, status, the root it
+// chained from and the root it proved, and its cid. Receipts are at the cid.
+pub async fn get_state_sequence_handler(
+    axum::extract::State(db): axum::extract::State<Arc<sea_orm::DatabaseConnection>>
+) -> Result<Json<Value>, ApiError> {
+    let commits = state_sequence::Entity
+        ::find()
+        .select_only()
+        .columns([
+            state_sequence::Column::Sequence,
+            state_sequence::Column::Actor,
+            state_sequence::Column::Action,
+            state_sequence::Column::Status,
+            state_sequence::Column::OldRoot,
+            state_sequence::Column::NewRoot,
+            state_sequence::Column::Cid,
+            state_sequence::Column::CreatedAt,
+            state_sequence::Column::ProvenAt,
+        ])
+        .order_by_desc(state_sequence::Column::Sequence)
+        .limit(50)
+        .into_json()
+        .all(&*db).await?;
+    Ok(Json(json!({
+      "status": "success",
+      "data": commits
+    })))
+}
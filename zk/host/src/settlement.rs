@@ -0,0 +1,341 @@
+use std::sync::Arc;
+
+use axum::Json;
+use car_auction_core::{
+    merkle::{ merkle_root, MerklePath },
+    Actor,
+    AuctionSettlement,
+    AuctionType,
+    OverallState,
+    Settlement,
+    SettlementParams,
+};
+use chrono::{ NaiveDateTime, Utc };
+use entity::{
+    auction,
+    car,
+    AuctionModel,
+    AuctionOutcome,
+    BidModel,
+    CarOwnershipModel,
+    Role,
+    Status,
+};
+use methods::{ SETTLE_AUCTION_ELF, SETTLE_AUCTION_ID };
+use risc0_zkvm::{ default_prover, ExecutorEnv, Receipt };
+use sea_orm::{
+    sea_query::Expr,
+    ColumnTrait,
+    DatabaseConnection,
+    DatabaseTransaction,
+    EntityTrait,
+    QueryFilter,
+    TransactionTrait,
+};
+use serde::{ Deserialize, Serialize };
+use serde_json::{ json, Value };
+
+use crate::{
+    auction::{
+        auction_leaf,
+        auction_outcome,
+        auction_type,
+        check_auction_leaf,
+        get_all_auctions,
+        get_auction_leaves,
+    },
+    auth::AuthUser,
+    bid::{ bid_leaf, get_all_bids, get_bid_leaves },
+    car::{ get_all_cars, get_car_state_leaves },
+    error::ApiError,
+    overall::{ proven_commit, published_state },
+    scheduler::record_transition,
+    sealed::settle_sealed,
+    sequencer::{ head_commit, submit },
+    transfer::{ transfer_car, Sale },
+    SessionStats,
+};
+
+#[derive(Serialize, Deserialize)]
+pub struct SettlementCommit {
+    pub receipt: Receipt,
+    pub stats: SessionStats,
+}
+impl SettlementCommit {
+    pub fn get_commit(&self) -> Result<Settlement, String> {
+        let state = self.receipt.journal.decode().map_err(|e| e.to_string())?;
+        Ok(state)
+    }
+    pub fn verify_and_get_commit(&self) -> Result<Settlement, String> {
+        self.receipt.verify(SETTLE_AUCTION_ID).map_err(|e| e.to_string())?;
+        self.get_commit()
+    }
+}
+
+// Proves the winner of an auction over every bid in the bid state.
+pub fn prove_settlement(params: &SettlementParams) -> Result<SettlementCommit, ApiError> {
+    let env = ExecutorEnv::builder()
+        .write(params)
+        .map_err(|e| ApiError::Proving(e.to_string()))?
+        .build()
+        .map_err(|e| ApiError::Proving(e.to_string()))?;
+    let prover = default_prover();
+    let prove_info = prover
+        .prove(env, SETTLE_AUCTION_ELF)
+        .map_err(|e| ApiError::Proving(e.to_string()))?;
+    Ok(SettlementCommit {
+        receipt: prove_info.receipt,
+        stats: SessionStats {
+            segments: prove_info.stats.segments,
+            total_cycles: prove_info.stats.total_cycles,
+            user_cycles: prove_info.stats.user_cycles,
+            paging_cycles: prove_info.stats.paging_cycles,
+            reserved_cycles: prove_info.stats.reserved_cycles,
+        },
+    })
+}
+
+// English settlement: the highest bid in the bid tree wins. The auction is completed in the
+// returned transaction, for the caller to finish and commit.
+async fn settle_english(
+    actor: Actor,
+    db: &DatabaseConnection,
+    published: OverallState,
+    auctions: &[AuctionModel],
+    bids: &[BidModel],
+    index: usize,
+    (car, car_path): (String, MerklePath)
+) -> Result<(Value, Receipt, SessionStats, DatabaseTransaction), ApiError> {
+    let auc = &auctions[index];
+    let auc_leaves = get_auction_leaves(auctions);
+    let params = SettlementParams {
+        published,
+        auction: auction_leaf(auc),
+        auction_path: MerklePath::new(&auc_leaves, index).ok_or(
+            ApiError::Internal("Auction is not in the auction state".to_string())
+        )?,
+        auction_root: merkle_root(&auc_leaves),
+        bids: bids.iter().map(bid_leaf).collect(),
+        bid_root: merkle_root(&get_bid_leaves(bids)),
+        car,
+        car_path,
+        timestamp: Utc::now().timestamp(),
+        actor,
+    };
+    let settlement_commit = prove_settlement(&params)?;
+    let settlement = settlement_commit
+        .get_commit()
+        .map_err(ApiError::Proving)?;
+
+    // the leaf stores whole seconds, so the row takes the proof's timestamp
+    let settled_at = chrono::DateTime
+        ::from_timestamp(params.timestamp, 0)
+        .ok_or(ApiError::Internal("Invalid settlement time".to_string()))?
+        .naive_utc();
+    // only settles once, and not over a bid placed while the proof ran
+    let txn = db.begin().await?;
+    let updated = auction::Entity
+        ::update_many()
+        .col_expr(
+            auction::Column::Status,
+            auction::Column::Status.save_as(Expr::val(Status::Completed))
+        )
+        .col_expr(auction::Column::Winner, settlement.winner.clone().into())
+        .col_expr(auction::Column::WinningBidId, settlement.winning_bid.into())
+        .col_expr(
+            auction::Column::Outcome,
+            auction::Column::Outcome.save_as(Expr::val(auction_outcome(settlement.outcome)))
+        )
+        .col_expr(auction::Column::SettledAt, Some(settled_at).into())
+        .col_expr(
+            auction::Column::SettlementReceipt,
+            serde_json::to_value(&settlement_commit.receipt).ok().into()
+        )
+        .col_expr(auction::Column::UpdatedAt, settled_at.into())
+        .filter(auction::Column::Id.eq(auc.id))
+        .filter(auction::Column::Status.eq(Status::Active))
+        .filter(auction::Column::BidCount.eq(auc.bid_count))
+        .exec(&txn).await?;
+    if updated.rows_affected == 0 {
+        return Err(ApiError::Conflict("Auction changed while settling".to_string()));
+    }
+    check_auction_leaf(&txn, auc.id, &params.auction_path, &settlement.auction_root).await?;
+    Ok((json!(settlement), settlement_commit.receipt, settlement_commit.stats, txn))
+}
+
+// Whether an active auction can be settled at `now`: after `end_time`, after the reveal window
+// of a sealed auction, or as soon as a Dutch or buy-now auction is sold.
+pub fn ready_to_settle(auc: &AuctionModel, now: NaiveDateTime) -> bool {
+    let format = auction_type(auc.auction_type);
+    let sold = (format == AuctionType::Dutch && auc.bid_count > 0) || auction_leaf(auc).bought();
+    let closes_at = if format.is_sealed() {
+        auc.reveal_end_time.unwrap_or(auc.end_time)
+    } else {
+        auc.end_time
+    };
+    sold || now > closes_at
+}
+
+// The settlement receipt stored with a settled auction, and its journal for the BUY transfer
+// guest to verify it against.
+fn settlement_claim(auc: &AuctionModel) -> Result<(AuctionSettlement, Receipt), ApiError> {
+    let receipt = auc.settlement_receipt
+        .clone()
+        .ok_or(ApiError::Conflict("Auction has no settlement receipt".to_string()))?;
+    let receipt: Receipt = serde_json
+        ::from_value(receipt)
+        .map_err(|e| ApiError::Internal(e.to_string()))?;
+    let settlement = if auction_type(auc.auction_type).is_sealed() {
+        let settlement = receipt.journal.decode().map_err(|e| ApiError::Proving(e.to_string()))?;
+        AuctionSettlement::Sealed(settlement)
+    } else {
+        let settlement = receipt.journal.decode().map_err(|e| ApiError::Proving(e.to_string()))?;
+        AuctionSettlement::English(settlement)
+    };
+    Ok((settlement, receipt))
+}
+
+// Hands the car of a sold auction to the winner at the price paid, with a BUY transfer proof
+// that verifies the settlement's receipt, and returns the transfer with the sequence number of
+// its state commit. `None` when the auction did not sell.
+pub async fn transfer_sold_car(
+    db: &DatabaseConnection,
+    id: i32,
+    actor: Actor
+) -> Result<Option<(CarOwnershipModel, i64)>, ApiError> {
+    let auc = auction::Entity
+        ::find_by_id(id)
+        .one(db).await?
+        .ok_or(ApiError::NotFound("Auction not found".to_string()))?;
+    let (Some(winner), Some(AuctionOutcome::Sold)) = (auc.winner.clone(), auc.outcome) else {
+        return Ok(None);
+    };
+    // settlement leaves the price paid in `current_bid`
+    let (_, ownership, sequence) = transfer_car(
+        db,
+        auc.car_id,
+        &winner,
+        Sale::Auction(auc.id),
+        Some(settlement_claim(&auc)?),
+        auc.current_bid,
+        actor
+    ).await?;
+    Ok(Some((ownership, sequence)))
+}
+
+// Proves the outcome of a closed auction, marks it and its car completed, transfers a sold car
+// to the winner and records the transition with the state commit. Shared by the settle endpoint
+// and the lifecycle scheduler; the conditional updates make a second settlement of the same
+// auction fail with 409.
+pub async fn settle_auction(
+    db: Arc<DatabaseConnection>,
+    id: i32,
+    actor: Actor
+) -> Result<Value, ApiError> {
+    // read under the submit lock, so the auctions are the ones the published commit holds
+    let txn = db.begin().await?;
+    let head = head_commit(&txn).await?;
+    let auctions = get_all_auctions(&txn).await?;
+    let index = auctions
+        .iter()
+        .position(|auc| auc.id == id)
+        .ok_or(ApiError::NotFound("Auction not found".to_string()))?;
+    let auc = &auctions[index];
+    if auc.status != Some(Status::Active) {
+        return Err(ApiError::Conflict("Auction is not active".to_string()));
+    }
+    if !ready_to_settle(auc, Utc::now().naive_utc()) {
+        return Err(ApiError::TooEarly("Auction time not yet up".to_string()));
+    }
+    let car = car::Entity
+        ::find_by_id(auc.car_id)
+        .one(&txn).await?
+        .ok_or(ApiError::NotFound("Car not found".to_string()))?;
+    let bids = get_all_bids(&txn).await?;
+    // cars lead the car state, in the order `get_all_cars` returns them
+    let car_index = get_all_cars(&txn).await?
+        .iter()
+        .position(|listed| listed.id == car.id)
+        .ok_or(ApiError::NotFound("Car not found".to_string()))?;
+    let car_leaves = get_car_state_leaves(&txn).await?;
+    txn.commit().await?;
+    let published = published_state(&db, head).await?;
+    let car_leaf = (
+        car_leaves[car_index].clone(),
+        MerklePath::new(&car_leaves, car_index).ok_or(
+            ApiError::Internal("Car is not in the car state".to_string())
+        )?,
+    );
+
+    let (settlement, receipt, stats, txn) = if auction_type(auc.auction_type).is_sealed() {
+        settle_sealed(actor.clone(), &db, published, &auctions, index, car_leaf).await?
+    } else {
+        settle_english(
+            actor.clone(),
+            &db,
+            published,
+            &auctions,
+            &bids,
+            index,
+            car_leaf
+        ).await?
+    };
+    car::Entity
+        ::update_many()
+        .col_expr(
+            car::Column::AuctionStatus,
+            car::Column::AuctionStatus.save_as(Expr::val(Status::Completed))
+        )
+        .filter(car::Column::Id.eq(car.id))
+        .exec(&txn).await?;
+    let mut sequence = submit(&txn, &actor, None).await?;
+    record_transition(&txn, id, Some(Status::Active), Status::Completed, &actor, sequence).await?;
+    txn.commit().await?;
+
+    // the auction is settled either way; a failed transfer is retried by the scheduler
+    let transfer = match transfer_sold_car(&db, id, actor.clone()).await {
+        Ok(transfer) => transfer,
+        Err(e) => {
+            eprintln!("Failed to transfer the car of auction {}: {}", id, e);
+            None
+        }
+    };
+    let transfer = transfer.map(|(ownership, transferred)| {
+        sequence = transferred;
+        ownership
+    });
+    // commits are proven in order, so the transfer's covers the settlement too
+    let (hash, commit) = proven_commit(&db, sequence).await?;
+    Ok(
+        json!({
+      "status": "success",
+      "message": "auction settled",
+      "data": settlement,
+      "transfer": transfer,
+      "cid": hash,
+      "receipt": commit.receipt,
+      "stats": commit.stats,
+      "settlement_receipt": receipt,
+      "settlement_stats": stats
+    })
+    )
+}
+
+// POST /api/auctions/{id}/settle
+// Closes an ended auction: proves the winning bid, marks the auction completed and records the
+// winner. The journal is the input `auction.sol`'s `claimWin` needs. The lifecycle scheduler
+// settles ended auctions on its own; this lets the seller do it without waiting.
+pub async fn settle_auction_handler(
+    user: AuthUser,
+    axum::extract::Path(id): axum::extract::Path<i32>,
+    axum::extract::State(db): axum::extract::State<Arc<sea_orm::DatabaseConnection>>
+) -> Result<Json<Value>, ApiError> {
+    let auc = auction::Entity
+        ::find_by_id(id)
+        .one(&*db).await?
+        .ok_or(ApiError::NotFound("Auction not found".to_string()))?;
+    if !auc.seller.eq_ignore_ascii_case(&user.addr) && !user.has_role(Role::Admin) {
+        return Err(ApiError::Forbidden("Not owner".to_string()));
+    }
+    settle_auction(db, id, user.actor()).await.map(Json)
+}
@@ -0,0 +1,56 @@
+use std::env;
+
+use car_auction_core::siwe::{ to_rfc3339, SiweMessage };
+use dotenv::dotenv;
+use ethers::{ types::Address, utils::to_checksum };
+
+// How logins are scoped. Read from SIWE_DOMAIN, SIWE_URI, SIWE_CHAIN_ID, SIWE_STATEMENT and
+// SIWE_TTL_SECS, so a signature captured on another site or chain is rejected by the verify guest.
+#[derive(Clone, Debug)]
+pub struct SiweConfig {
+    pub domain: String,
+    pub uri: String,
+    pub chain_id: u64,
+    pub statement: String,
+    pub ttl: i64,
+}
+
+impl SiweConfig {
+    pub fn from_env() -> Self {
+        dotenv().ok();
+        let domain = env::var("SIWE_DOMAIN").unwrap_or_else(|_| "localhost:3000".to_string());
+        let uri = env::var("SIWE_URI").unwrap_or_else(|_| format!("http://{}", domain));
+        let chain_id = env
+            ::var("SIWE_CHAIN_ID")
+            .ok()
+            .and_then(|id| id.parse().ok())
+            .unwrap_or(1);
+        let statement = env
+            ::var("SIWE_STATEMENT")
+            .unwrap_or_else(|_| "Sign in to Zero car auctions.".to_string());
+        let ttl = env
+            ::var("SIWE_TTL_SECS")
+            .ok()
+            .and_then(|ttl| ttl.parse().ok())
+            .unwrap_or(300);
+        SiweConfig { domain, uri, chain_id, statement, ttl }
+    }
+
+    pub fn login_message(&self, address: &Address, nonce: &str) -> SiweMessage {
+        let now = chrono::Utc::now().timestamp();
+        SiweMessage {
+            domain: self.domain.clone(),
+            address: to_checksum(address, None),
+            statement: Some(self.statement.clone()),
+            uri: self.uri.clone(),
+            version: "1".to_string(),
+            chain_id: self.chain_id,
+            nonce: nonce.to_string(),
+            issued_at: to_rfc3339(now),
+            expiration_time: Some(to_rfc3339(now + self.ttl)),
+            not_before: None,
+            request_id: None,
+            resources: vec![],
+        }
+    }
+}
@@ -0,0 +1,321 @@
+use std::sync::Arc;
+
+use axum::Json;
+use car_auction_core::{
+    Actor,
+    AuctionSettlement,
+    CarAction,
+    CarTransfer,
+    CarTransferParams,
+    OfferLeaf,
+    StateAction,
+};
+use chrono::Utc;
+use entity::{ car, car_ownership, offer, CarOwnershipModel, OfferStatus };
+use methods::{ SETTLE_AUCTION_ID, SETTLE_SEALED_ID, TRANSFER_CAR_ELF, TRANSFER_CAR_ID };
+use risc0_zkvm::{ default_prover, Digest, ExecutorEnv, Receipt };
+use sea_orm::{
+    sea_query::Expr,
+    ActiveModelTrait,
+    ColumnTrait,
+    DatabaseConnection,
+    EntityTrait,
+    QueryFilter,
+    QueryOrder,
+    QuerySelect,
+    Set,
+    TransactionTrait,
+};
+use serde::{ Deserialize, Serialize };
+use serde_json::{ json, Value };
+
+use crate::{
+    car::{ get_all_cars, get_car_leaves },
+    error::ApiError,
+    offer::{ get_all_offers, get_offer_leaves },
+    overall::published_state,
+    sequencer::{ head_commit, lock_head, submit },
+    SessionStats,
+    PROVE_ATTEMPTS,
+};
+
+// How a car was sold: won at an auction (a BUY) or through an offer the owner accepted (a SELL).
+#[derive(Clone, Copy, Debug)]
+pub enum Sale {
+    Auction(i32),
+    Offer(i32),
+}
+impl Sale {
+    pub fn action(&self) -> CarAction {
+        match self {
+            Sale::Auction(_) => CarAction::BUY,
+            Sale::Offer(_) => CarAction::SELL,
+        }
+    }
+    pub fn auction_id(&self) -> Option<i32> {
+        match self {
+            Sale::Auction(id) => Some(*id),
+            Sale::Offer(_) => None,
+        }
+    }
+    pub fn offer_id(&self) -> Option<i32> {
+        match self {
+            Sale::Offer(id) => Some(*id),
+            Sale::Auction(_) => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CarTransferCommit {
+    pub receipt: Receipt,
+    pub stats: SessionStats,
+}
+impl CarTransferCommit {
+    pub fn get_commit(&self) -> Result<CarTransfer, String> {
+        let state = self.receipt.journal.decode().map_err(|e| e.to_string())?;
+        Ok(state)
+    }
+    pub fn verify_and_get_commit(&self) -> Result<CarTransfer, String> {
+        self.receipt.verify(TRANSFER_CAR_ID).map_err(|e| e.to_string())?;
+        let transfer = self.get_commit()?;
+        check_settlement_image(&transfer)?;
+        Ok(transfer)
+    }
+}
+
+// The guest verifies a BUY's settlement under the settle image ids it was built with and commits
+// the one it used; a transfer naming any other image was not built with these settle guests.
+pub fn check_settlement_image(transfer: &CarTransfer) -> Result<(), String> {
+    match transfer.settlement_image {
+        Some(image) if
+            image != Digest::from(SETTLE_AUCTION_ID) &&
+            image != Digest::from(SETTLE_SEALED_ID)
+        => Err("Settlement not proven by a settle guest".to_string()),
+        _ => Ok(()),
+    }
+}
+
+// Proves a car changing owner in the car state. A BUY passes the settlement receipt its guest
+// verifies, which the transfer receipt then carries as an assumption.
+pub fn prove_transfer(
+    params: &CarTransferParams,
+    settlement: Option<Receipt>
+) -> Result<CarTransferCommit, ApiError> {
+    let mut builder = ExecutorEnv::builder();
+    if let Some(receipt) = settlement {
+        builder.add_assumption(receipt);
+    }
+    let env = builder
+        .write(params)
+        .map_err(|e| ApiError::Proving(e.to_string()))?
+        .build()
+        .map_err(|e| ApiError::Proving(e.to_string()))?;
+    let prover = default_prover();
+    let prove_info = prover
+        .prove(env, TRANSFER_CAR_ELF)
+        .map_err(|e| ApiError::Proving(e.to_string()))?;
+    Ok(CarTransferCommit {
+        receipt: prove_info.receipt,
+        stats: SessionStats {
+            segments: prove_info.stats.segments,
+            total_cycles: prove_info.stats.total_cycles,
+            user_cycles: prove_info.stats.user_cycles,
+            paging_cycles: prove_info.stats.paging_cycles,
+            reserved_cycles: prove_info.stats.reserved_cycles,
+        },
+    })
+}
+
+// The leaves of the offers made on `car_id`, in the order they were read.
+fn car_offers(leaves: &[String], car_id: i32) -> Vec<String> {
+    leaves
+        .iter()
+        .filter(|leaf| OfferLeaf::parse(leaf).is_some_and(|offer| offer.car_id == car_id))
+        .cloned()
+        .collect()
+}
+
+// Moves the owner and, for a SELL, the car's offers as the proof did, records the transfer and
+// queues its state commit, in one transaction. Nothing is written when the car's leaf or its
+// offers changed since the proof read them, and the caller proves the transfer again; changes
+// to other cars do not count.
+async fn apply_transfer(
+    db: &DatabaseConnection,
+    params: &CarTransferParams,
+    sale: Sale,
+    amount: i32,
+    transfer_commit: &CarTransferCommit,
+    transfer: &CarTransfer
+) -> Result<Option<(CarOwnershipModel, i64)>, ApiError> {
+    let txn = db.begin().await?;
+    // the row first, as every writer locks it before queuing, then the submit lock, so nothing
+    // is queued between the checks below and this commit
+    let current = car::Entity
+        ::find_by_id(params.car_id)
+        .lock_exclusive()
+        .one(&txn).await?
+        .ok_or(ApiError::NotFound("Car not found".to_string()))?;
+    lock_head(&txn).await?;
+    let offers = offer::Entity
+        ::find()
+        .filter(offer::Column::CarId.eq(params.car_id))
+        .order_by_asc(offer::Column::Id)
+        .all(&txn).await?;
+    if
+        get_car_leaves(&vec![current]).remove(0) != params.cars[params.index] ||
+        get_offer_leaves(&offers) != car_offers(&params.cars, params.car_id)
+    {
+        return Ok(None);
+    }
+
+    // `updated_at` of the car stays as it was so the stored leaf is the one the guest produced
+    let updated = car::Entity
+        ::update_many()
+        .col_expr(car::Column::Owner, params.to.clone().into())
+        .filter(car::Column::Id.eq(params.car_id))
+        .filter(car::Column::Owner.eq(params.from.clone()))
+        .exec(&txn).await?;
+    if updated.rows_affected == 0 {
+        return Err(ApiError::Conflict("Car changed owner while transferring".to_string()));
+    }
+    if let Some(offer_id) = sale.offer_id() {
+        // the offers take the proof's time, which their leaves store in whole seconds
+        let now = chrono::DateTime
+            ::from_timestamp(params.timestamp, 0)
+            .ok_or(ApiError::Internal("Invalid transfer time".to_string()))?
+            .naive_utc();
+        let accepted = offer::Entity
+            ::update_many()
+            .col_expr(
+                offer::Column::Status,
+                offer::Column::Status.save_as(Expr::val(OfferStatus::Accepted))
+            )
+            .col_expr(offer::Column::UpdatedAt, now.into())
+            .filter(offer::Column::Id.eq(offer_id))
+            .filter(offer::Column::Status.eq(OfferStatus::Pending))
+            .exec(&txn).await?;
+        if accepted.rows_affected == 0 {
+            return Err(ApiError::Conflict("Offer is not pending".to_string()));
+        }
+        offer::Entity
+            ::update_many()
+            .col_expr(
+                offer::Column::Status,
+                offer::Column::Status.save_as(Expr::val(OfferStatus::Rejected))
+            )
+            .col_expr(offer::Column::UpdatedAt, now.into())
+            .filter(offer::Column::CarId.eq(params.car_id))
+            .filter(offer::Column::Status.eq(OfferStatus::Pending))
+            .exec(&txn).await?;
+    }
+    let ownership = car_ownership::ActiveModel {
+        car_id: Set(params.car_id),
+        from_owner: Set(params.from.clone()),
+        to_owner: Set(params.to.clone()),
+        action: Set(format!("{:?}", sale.action())),
+        auction_id: Set(sale.auction_id()),
+        offer_id: Set(sale.offer_id()),
+        amount: Set(amount),
+        old_car_root: Set(transfer.car_root.old.to_string()),
+        new_car_root: Set(transfer.car_root.new.to_string()),
+        receipt: Set(serde_json::to_value(&transfer_commit.receipt).ok()),
+        created_at: Set(Utc::now().naive_utc()),
+        ..Default::default()
+    }
+        .insert(&txn).await?;
+    let action = StateAction::Car(sale.action());
+    let sequence = submit(&txn, &params.actor, Some(&action)).await?;
+    txn.commit().await?;
+    Ok(Some((ownership, sequence)))
+}
+
+// Transfers a car from its current owner to `to`: proves the car state transition with the
+// sale's action (and, for a BUY, the auction's settlement), moves `car.owner` and records the
+// transfer in the ownership history. A sale through an offer accepts it and rejects the car's
+// other pending offers, in the proof and in the same transaction as the owner. The writes and
+// their state commit go in one transaction after the proof, which is made again when the car
+// state moved meanwhile; the caller waits for the returned sequence number to be proven.
+pub async fn transfer_car(
+    db: &DatabaseConnection,
+    car_id: i32,
+    to: &str,
+    sale: Sale,
+    settlement: Option<(AuctionSettlement, Receipt)>,
+    amount: i32,
+    actor: Actor
+) -> Result<(CarTransferCommit, CarOwnershipModel, i64), ApiError> {
+    for _ in 0..PROVE_ATTEMPTS {
+        // read under the submit lock, so the leaves are the ones the published commit holds
+        let txn = db.begin().await?;
+        let head = head_commit(&txn).await?;
+        let cars = get_all_cars(&txn).await?;
+        let offers = get_all_offers(&txn).await?;
+        txn.commit().await?;
+        let index = cars
+            .iter()
+            .position(|car| car.id == car_id)
+            .ok_or(ApiError::NotFound("Car not found".to_string()))?;
+        let from = cars[index].owner.clone();
+        if from.eq_ignore_ascii_case(to) {
+            return Err(ApiError::Conflict("Car already owned by the buyer".to_string()));
+        }
+
+        let params = CarTransferParams {
+            published: published_state(db, head).await?,
+            cars: [get_car_leaves(&cars), get_offer_leaves(&offers)].concat(),
+            index,
+            car_id,
+            from,
+            to: to.to_string(),
+            auction_id: sale.auction_id(),
+            settlement: settlement.as_ref().map(|(settlement, _)| settlement.clone()),
+            offer_id: sale.offer_id(),
+            amount: amount as u64,
+            action: sale.action(),
+            timestamp: Utc::now().timestamp(),
+            actor: actor.clone(),
+        };
+        let input = params.clone();
+        let receipt = settlement.as_ref().map(|(_, receipt)| receipt.clone());
+        let transfer_commit = tokio::task
+            ::spawn_blocking(move || prove_transfer(&input, receipt)).await
+            .map_err(|e| ApiError::Internal(e.to_string()))??;
+        let transfer = transfer_commit
+            .get_commit()
+            .map_err(ApiError::Proving)?;
+        check_settlement_image(&transfer).map_err(ApiError::Proving)?;
+
+        let applied = apply_transfer(db, &params, sale, amount, &transfer_commit, &transfer).await?;
+        if let Some((ownership, sequence)) = applied {
+            return Ok((transfer_commit, ownership, sequence));
+        }
+    }
+    Err(ApiError::Conflict("Car state kept changing while the transfer was proven".to_string()))
+}
+
+// GET /api/cars/{id}/owners
+// Chain of custody of a car, oldest transfer first. Each transfer's `from_owner` is the previous
+// one's `to_owner`, and its receipt proves the car state moved between its two car roots.
+pub async fn get_car_owners_handler(
+    axum::extract::Path(id): axum::extract::Path<i32>,
+    axum::extract::State(db): axum::extract::State<Arc<sea_orm::DatabaseConnection>>
+) -> Result<Json<Value>, ApiError> {
+    let car = car::Entity
+        ::find_by_id(id)
+        .one(&*db).await?
+        .ok_or(ApiError::NotFound("Car not found".to_string()))?;
+    let transfers = car_ownership::Entity
+        ::find()
+        .filter(car_ownership::Column::CarId.eq(id))
+        .order_by_asc(car_ownership::Column::Id)
+        .all(&*db).await?;
+    Ok(
+        Json(
+            json!({
+      "status": "success",
+      "data": { "car_id": car.id, "owner": car.owner, "transfers": transfers }
+    })
+        )
+    )
+}
@@ -1,3 +1,51 @@
+use std::{ collections::HashMap, env, fs::OpenOptions, io::Write, path::Path };
+
+use risc0_build::{ build_package, get_package, get_target_dir, GuestListEntry, GuestOptions };
+
+// transfer_car verifies settlement receipts under the image ids of the settle guests, so those
+// are built first and their ids handed to the build of the other guests as `<NAME>_ID`, in hex.
 fn main() {
-    risc0_build::embed_methods();
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+    let manifest_dir = Path::new(&manifest_dir);
+    let settle = get_package(manifest_dir.join("settle"));
+    let target_dir = get_target_dir(manifest_dir.join("Cargo.toml"))
+        .join("riscv-guest")
+        .join("methods")
+        .join("settle");
+    let settle_methods = build_package(&settle, target_dir, GuestOptions::default()).unwrap();
+    for method in &settle_methods {
+        env::set_var(constant(method, "ID"), method.image_id.to_string());
+    }
+
+    risc0_build::embed_methods_with_options(HashMap::new());
+    embed(&settle_methods);
+}
+
+fn constant(method: &GuestListEntry, suffix: &str) -> String {
+    format!("{}_{}", method.name.to_uppercase().replace('-', "_"), suffix)
+}
+
+// Appends the constants `embed_methods` generates to its methods.rs, for guests built here.
+fn embed(methods: &[GuestListEntry]) {
+    let out_dir = env::var("OUT_DIR").unwrap();
+    let mut file = OpenOptions::new()
+        .append(true)
+        .open(Path::new(&out_dir).join("methods.rs"))
+        .unwrap();
+    let skip_build = env::var("RISC0_SKIP_BUILD").is_ok_and(|skip| !skip.is_empty());
+    for method in methods {
+        let elf = if skip_build {
+            "&[]".to_string()
+        } else {
+            format!("include_bytes!({:?})", method.path)
+        };
+        writeln!(file, "pub const {}: &[u8] = {};", constant(method, "ELF"), elf).unwrap();
+        writeln!(file, "pub const {}: &str = {:?};", constant(method, "PATH"), method.path).unwrap();
+        writeln!(
+            file,
+            "pub const {}: [u32; 8] = {:?};",
+            constant(method, "ID"),
+            method.image_id.as_words()
+        ).unwrap();
+    }
 }
@@ -0,0 +1,85 @@
+#![no_main]
+// #![no_std]
+use risc0_zkvm::guest::env;
+use car_auction_core::{
+    merkle::RootTransition,
+    sealed::{ commitment_root, valid_commitment, SealedCommitment, SealedCommitmentParams },
+    AuctionLeaf,
+    AuctionStatus,
+};
+risc0_zkvm::guest::entry!(main);
+
+// proof of a sealed commitment: the auction is in the auction tree of the published state
+// commit, sealed, active and open at `timestamp`, and the commitments passed are exactly the
+// ones its leaf counts in `bid_count` and `commitment_root`. A bidder commits once per auction.
+// The new commitment is appended, and the auction leaf moves to the new count and root, so the
+// settle_sealed guest can check that no commitment was added or left out.
+fn main() {
+    let input: SealedCommitmentParams = env::read();
+    let published = input.published;
+    assert!(published.is_consistent(), "Published state does not match its roots");
+    assert_eq!(
+        input.auction_root,
+        published.auc_state.overall,
+        "Auction root is not the published one"
+    );
+
+    let auction = input.auction;
+    assert_eq!(
+        input.auction_path.root(&auction.encode()),
+        input.auction_root,
+        "Auction not in the auction state"
+    );
+    assert!(auction.auction_type.is_sealed(), "Auction is not sealed");
+    assert_eq!(auction.status, AuctionStatus::Active, "Auction is not active");
+    assert!(
+        input.timestamp >= auction.start_time && input.timestamp <= auction.end_time,
+        "Bidding window is closed"
+    );
+    assert_eq!(
+        commitment_root(&input.commitments),
+        auction.commitment_root,
+        "Commitments do not match the auction"
+    );
+    assert_eq!(
+        input.commitments.len(),
+        auction.bid_count as usize,
+        "Commitment count does not match the auction"
+    );
+
+    let bid = input.bid;
+    assert_eq!(bid.auction_id, auction.id, "Commitment for another auction");
+    assert_eq!(bid.created_at, input.timestamp, "Commitment not made at the proof's time");
+    assert!(valid_commitment(&bid.commitment), "Invalid commitment");
+    assert!(
+        input.commitments
+            .iter()
+            .all(|made| made.id != bid.id && !made.bidder.eq_ignore_ascii_case(&bid.bidder)),
+        "Already committed to this auction"
+    );
+
+    let mut commitments = input.commitments;
+    commitments.push(bid.clone());
+    let root = commitment_root(&commitments).expect("No commitments");
+    let updated = AuctionLeaf {
+        bid_count: commitments.len() as i32,
+        commitment_root: Some(root.clone()),
+        updated_at: input.timestamp,
+        ..auction
+    };
+
+    // write public output to the journal
+    env::commit(
+        &(SealedCommitment {
+            state_root: published.new_state,
+            bid,
+            commitments: commitments.len(),
+            commitment_root: root,
+            auction_root: RootTransition {
+                old: input.auction_root,
+                new: input.auction_path.root(&updated.encode()),
+            },
+            actor: input.actor,
+        })
+    );
+}
@@ -0,0 +1,199 @@
+#![no_main]
+// #![no_std]
+use risc0_zkvm::{ guest::env, sha::Digest };
+use car_auction_core::{
+    merkle::{ merkle_root, RootTransition },
+    starting_price,
+    Actor,
+    AuctionExtension,
+    AuctionLeaf,
+    AuctionStatus,
+    AuctionType,
+    BidAction,
+    BidLeaf,
+    BidParams,
+    BidState,
+    BidTransition,
+};
+risc0_zkvm::guest::entry!(main);
+
+// proof of a valid bid: the bid is only added to the bid state if its EIP-712 signature
+// recovers to the bidder, so the journal binds the amount to the bidder's key. The auction and
+// bid trees are the ones the published state commit holds, and the auction the bid targets must
+// be in the auction tree, active and open at `timestamp`. No bid is below its car's
+// `starting_price`. English auctions must be outbid by at least their own increment (or the
+// host's `min_increment`), and a bid at the buy-now price closes them at `timestamp`, and one in
+// their soft-close window extends `end_time`. Dutch auctions sell to the first bid at or above
+// the clock price. A proxy bid is placed by the system under the bidder's signed maximum and may
+// not exceed it. A raise replaces the bidder's own standing english bid with the new amount,
+// keeping its id and `created_at`, and leaves the auction's `bid_count` as it was.
+fn main() {
+    let input: BidParams = env::read();
+    let published = input.published;
+    assert!(published.is_consistent(), "Published state does not match its roots");
+    assert_eq!(
+        input.auction_root,
+        published.auc_state.overall,
+        "Auction root is not the published one"
+    );
+    assert_eq!(
+        merkle_root(&input.bid_leaves),
+        published.bid_state.overall,
+        "Bid leaves are not the published ones"
+    );
+
+    let digest = input.bid.signing_hash(&input.domain).expect("Invalid bid domain");
+    let signer = input.bid
+        .recover_signer(&input.domain, &input.signature)
+        .expect("Invalid bid signature");
+    assert!(signer.eq_ignore_ascii_case(&input.bidder), "Bid not signed by the bidder");
+    assert!(input.bid.deadline >= (input.timestamp as u64), "Bid signature expired");
+    let proxy = input.proxy_amount.is_some();
+    let amount = input.proxy_amount.unwrap_or(input.bid.amount);
+    assert!(amount <= input.bid.amount, "Proxy bid above the signed maximum");
+    assert!(!proxy || input.actor == Actor::SYSTEM, "Proxy bids are placed by the system");
+
+    let auction = input.auction;
+    assert_eq!(
+        input.auction_path.root(&auction.encode()),
+        input.auction_root,
+        "Auction not in the auction state"
+    );
+    assert_eq!(auction.id as u64, input.bid.auction_id, "Bid is for another auction");
+    assert_eq!(
+        input.car_path.root(&input.car),
+        published.car_state.overall,
+        "Car not in the car state"
+    );
+    let starting_price = starting_price(&input.car, auction.car_id).expect(
+        "Car is not the auction's"
+    );
+    assert!(amount >= starting_price, "Bid below the starting price");
+    assert_eq!(auction.status, AuctionStatus::Active, "Auction is not active");
+    assert!(
+        auction.start_time <= input.timestamp && input.timestamp <= auction.end_time,
+        "Auction is not open"
+    );
+    let increment = auction.min_increment
+        .map(|increment| increment as u64)
+        .unwrap_or(input.min_increment);
+    let mut buy_now = false;
+    match auction.auction_type {
+        AuctionType::English => {
+            assert!(!auction.bought(), "Auction already bought");
+            buy_now = auction.buy_now_price.is_some_and(|price| amount >= (price as u64));
+            let minimum = (auction.current_bid as u64) + increment;
+            assert!(buy_now || amount >= minimum, "Bid below the minimum increment");
+        }
+        AuctionType::Dutch => {
+            assert_eq!(auction.bid_count, 0, "Auction already sold");
+            let price = auction
+                .dutch_price(input.timestamp)
+                .expect("Dutch auction without a floor");
+            assert!(amount >= (price as u64), "Bid below the clock price");
+        }
+        AuctionType::Sealed | AuctionType::Vickrey => panic!("Auction takes sealed bids"),
+    }
+
+    // the raised bid must be the bidder's, standing on this auction, and in the bid tree
+    let raised = input.raises.as_ref().map(|raised| {
+        assert_eq!(auction.auction_type, AuctionType::English, "Only english bids can be raised");
+        assert!(!proxy, "Proxy bids are not raised");
+        assert_eq!(raised.id, input.bid_id, "Raise is for another bid");
+        assert_eq!(raised.auction_id, auction.id, "Raised bid is for another auction");
+        assert!(
+            raised.bidder.eq_ignore_ascii_case(&input.bidder),
+            "Raised bid is not the bidder's"
+        );
+        assert!(raised.retracted_at.is_none(), "Raised bid was retracted");
+        assert!(amount > (raised.amount as u64), "Raise is not above the bid");
+        let index = input.bid_leaves
+            .iter()
+            .position(|leaf| *leaf == raised.encode())
+            .expect("Raised bid not in the bid state");
+        (index, raised.created_at)
+    });
+
+    // a buy-now bid ends the auction, so only other english bids can extend it
+    let extension = match auction.auction_type {
+        AuctionType::English if !buy_now =>
+            auction.soft_close_end(input.timestamp).map(|end| AuctionExtension {
+                auction_id: auction.id,
+                bid_id: input.bid_id,
+                old_end_time: auction.end_time,
+                new_end_time: end,
+            }),
+        _ => None,
+    };
+    let end_time = match (&extension, buy_now) {
+        (Some(extension), _) => extension.new_end_time,
+        (None, true) => input.timestamp,
+        (None, false) => auction.end_time,
+    };
+
+    // the auction leaf moves to the new highest bid; its siblings are unchanged
+    let updated = AuctionLeaf {
+        current_bid: amount as i64,
+        bid_count: if raised.is_some() { auction.bid_count } else { auction.bid_count + 1 },
+        end_time,
+        updated_at: input.timestamp,
+        ..auction
+    };
+    let auction_root = RootTransition {
+        old: input.auction_root,
+        new: input.auction_path.root(&updated.encode()),
+    };
+
+    let leaf = BidLeaf {
+        id: input.bid_id,
+        auction_id: input.bid.auction_id as i32,
+        bidder: input.bidder.clone(),
+        amount: amount as i64,
+        created_at: raised.map_or(input.timestamp, |(_, created_at)| created_at),
+        updated_at: input.timestamp,
+        retracted_at: None,
+    }.encode();
+    let old = BidState::init(input.bid_leaves.clone());
+    let old_root = merkle_root(&input.bid_leaves);
+    let mut leaves = input.bid_leaves;
+    match raised {
+        Some((index, _)) => {
+            leaves[index] = leaf;
+        }
+        None => leaves.push(leaf),
+    }
+    let bid_root = RootTransition {
+        old: old_root,
+        new: merkle_root(&leaves),
+    };
+    let new = BidState::init(leaves);
+
+    let state = BidState {
+        old_state: old.new_state,
+        new_state: new.new_state,
+        overall: new.overall,
+        leaves: new.leaves,
+        action: if raised.is_some() { BidAction::UPDATE } else { BidAction::CREATE },
+        actor: input.actor,
+    };
+
+    // write public output to the journal
+    env::commit(
+        &(BidTransition {
+            state,
+            state_root: published.new_state,
+            bid_id: input.bid_id,
+            auction_id: input.bid.auction_id,
+            bidder: input.bidder,
+            amount,
+            nonce: (!proxy).then_some(input.bid.nonce),
+            digest: (!proxy).then(|| Digest::from_bytes(digest)),
+            proxy,
+            min_increment: increment,
+            buy_now,
+            extension,
+            auction_root,
+            bid_root,
+        })
+    );
+}
@@ -1,7 +1,16 @@
 #![no_main]
 // #![no_std]
 use risc0_zkvm::guest::env;
-use car_auction_core::{ AuctionState, OverallState, CarState, BidState, OverallParams };
+use car_auction_core::{
+    state_root,
+    AuctionState,
+    OverallState,
+    CarState,
+    BidState,
+    OverallParams,
+    StateAction,
+    StateLeaves,
+};
 
 risc0_zkvm::guest::entry!(main);
 fn main() {
@@ -10,16 +19,59 @@ fn main() {
     // read the input
     let input: OverallParams = env::read();
 
-    // create genesis zk block
-    let overall: OverallState = OverallState::new();
+    // an in-place change must lead from the leaves of the last proven commit to these ones
+    if let Some(action) = input.action.as_ref().filter(|action| action.in_place()) {
+        let previous = input.previous_leaves
+            .as_ref()
+            .expect("UPDATE and DELETE need the previous commit's leaves");
+        assert_eq!(
+            Some(previous.root()),
+            input.previous,
+            "Previous leaves are not the previous state"
+        );
+        let leaves = StateLeaves {
+            car: input.car_leaves.clone(),
+            auction: input.auc_leaves.clone(),
+            bid: input.bid_leaves.clone(),
+        };
+        assert!(action.follows(previous, &leaves), "State does not follow from the previous one");
+    }
+
     //do something
-    let car_state: CarState = CarState::init(input.car_leaves);
-    let auc_state: AuctionState = AuctionState::init(input.auc_leaves);
-    let bid_state: BidState = BidState::init(input.bid_leaves);
+    let mut car_state: CarState = CarState::init(input.car_leaves);
+    let mut auc_state: AuctionState = AuctionState::init(input.auc_leaves);
+    let mut bid_state: BidState = BidState::init(input.bid_leaves);
+    car_state.actor = input.actor.clone();
+    auc_state.actor = input.actor.clone();
+    bid_state.actor = input.actor;
+    match input.action {
+        Some(StateAction::Car(action)) => {
+            car_state.action = action;
+        }
+        Some(StateAction::Auction(action)) => {
+            auc_state.action = action;
+        }
+        Some(StateAction::Bid(action)) => {
+            bid_state.action = action;
+        }
+        None => {}
+    }
     // let overall: OverallState = overall
     // .sync(&car_state, &auc_state, &bid_state)
     // .expect("Sync failed");
 
+    // create a zk block over the current states, chained to the previous one when sequenced
+    let genesis = OverallState::new();
+    let overall: OverallState = OverallState {
+        new_state: state_root(&car_state, &auc_state, &bid_state),
+        old_state: input.previous.unwrap_or(genesis.old_state),
+        sequence: input.sequence,
+        car_state,
+        auc_state,
+        bid_state,
+        ..genesis
+    };
+
     env::commit(&overall);
 }
 
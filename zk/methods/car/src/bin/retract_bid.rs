@@ -0,0 +1,136 @@
+#![no_main]
+// #![no_std]
+use risc0_zkvm::guest::env;
+use car_auction_core::{
+    merkle::{ merkle_root, RootTransition },
+    winning_bid,
+    AuctionLeaf,
+    AuctionStatus,
+    AuctionType,
+    BidAction,
+    BidLeaf,
+    BidRetraction,
+    BidState,
+    RetractionParams,
+};
+risc0_zkvm::guest::entry!(main);
+
+// proof of a bid retraction: the bid stays in the bid tree, marked retracted, and the auction
+// leaf moves to what the remaining bids say: the highest one standing (or the opening price
+// when none is left) and one bid fewer. Only the bidder can retract, with a reason, on an
+// active english auction that was not bought, before the policy's cutoff and within its
+// per-auction limit. Every bid of the published state commit is read, so the new current bid
+// cannot skip a standing one.
+fn main() {
+    let input: RetractionParams = env::read();
+    let published = input.published;
+    assert!(published.is_consistent(), "Published state does not match its roots");
+    assert_eq!(
+        input.auction_root,
+        published.auc_state.overall,
+        "Auction root is not the published one"
+    );
+
+    let auction = input.auction;
+    assert_eq!(
+        input.auction_path.root(&auction.encode()),
+        input.auction_root,
+        "Auction not in the auction state"
+    );
+    assert_eq!(auction.status, AuctionStatus::Active, "Auction is not active");
+    assert_eq!(auction.auction_type, AuctionType::English, "Only english bids can be retracted");
+    assert!(!auction.bought(), "Auction already bought");
+    assert!(
+        input.timestamp <= auction.end_time - input.policy.cutoff,
+        "Retraction cutoff has passed"
+    );
+    assert!(!input.reason.trim().is_empty(), "Retraction needs a reason");
+
+    let old_leaves: Vec<String> = input.bids
+        .iter()
+        .map(BidLeaf::encode)
+        .collect();
+    assert_eq!(
+        merkle_root(&old_leaves),
+        published.bid_state.overall,
+        "Bids are not the published ones"
+    );
+    let index = input.bids
+        .iter()
+        .position(|bid| bid.id == input.bid_id)
+        .expect("Bid not in the bid state");
+    let bid = input.bids[index].clone();
+    assert_eq!(bid.auction_id, auction.id, "Bid is for another auction");
+    assert!(bid.bidder.eq_ignore_ascii_case(&input.bidder), "Bid is not the bidder's");
+    assert!(bid.retracted_at.is_none(), "Bid already retracted");
+    let retracted = input.bids
+        .iter()
+        .filter(|other| other.auction_id == auction.id && other.retracted_at.is_some())
+        .filter(|other| other.bidder.eq_ignore_ascii_case(&input.bidder))
+        .count();
+    assert!(retracted < (input.policy.limit as usize), "Retraction limit reached");
+
+    let mut bids = input.bids;
+    bids[index] = BidLeaf {
+        updated_at: input.timestamp,
+        retracted_at: Some(input.timestamp),
+        ..bid.clone()
+    };
+    let new_leaves: Vec<String> = bids
+        .iter()
+        .map(BidLeaf::encode)
+        .collect();
+    let bid_root = RootTransition {
+        old: merkle_root(&old_leaves),
+        new: merkle_root(&new_leaves),
+    };
+
+    // the auction leaf is recomputed from the bids left standing
+    let current_bid = winning_bid(auction.id, &bids)
+        .map(|highest| highest.amount)
+        .unwrap_or(auction.opening_price);
+    let bid_count = bids
+        .iter()
+        .filter(|other| other.auction_id == auction.id && other.retracted_at.is_none())
+        .count() as i32;
+    let updated = AuctionLeaf {
+        current_bid,
+        bid_count,
+        updated_at: input.timestamp,
+        ..auction
+    };
+    let auction_root = RootTransition {
+        old: input.auction_root,
+        new: input.auction_path.root(&updated.encode()),
+    };
+
+    let old = BidState::init(old_leaves);
+    let new = BidState::init(new_leaves);
+    let state = BidState {
+        old_state: old.new_state,
+        new_state: new.new_state,
+        overall: new.overall,
+        leaves: new.leaves,
+        action: BidAction::DELETE,
+        actor: input.actor,
+    };
+
+    // write public output to the journal
+    env::commit(
+        &(BidRetraction {
+            state,
+            state_root: published.new_state,
+            bid_id: bid.id,
+            auction_id: bid.auction_id,
+            bidder: bid.bidder,
+            amount: bid.amount as u64,
+            reason: input.reason,
+            policy: input.policy,
+            current_bid: current_bid as u64,
+            bid_count,
+            auction_root,
+            bid_root,
+            retracted_at: input.timestamp,
+        })
+    );
+}
@@ -0,0 +1,142 @@
+#![no_main]
+// #![no_std]
+use hex::FromHex;
+use risc0_zkvm::{ guest::env, serde::to_vec, sha::Digest };
+use car_auction_core::{
+    merkle::{ merkle_root, RootTransition },
+    transfer_leaf,
+    AuctionSettlement,
+    CarAction,
+    CarState,
+    CarTransfer,
+    CarTransferParams,
+    OfferLeaf,
+    OfferStatus,
+};
+risc0_zkvm::guest::entry!(main);
+
+// image ids of the settle guests, handed to this build by methods/build.rs
+const SETTLE_AUCTION_ID: &str = env!("SETTLE_AUCTION_ID");
+const SETTLE_SEALED_ID: &str = env!("SETTLE_SEALED_ID");
+
+// proof of transfer: the car state leaves are the ones of the published state commit, and the
+// car leaf changes only in its owner, from the current owner to the buyer. A BUY comes from an auction and verifies its settlement receipt under the image id of
+// the settle guest built with this one, and the seller, buyer and price must be its seller,
+// winner and the amount it settled at. A SELL comes from a pending, unexpired offer in the car
+// state between the same buyer and seller at the same price; the offer is accepted and the car's
+// other pending offers rejected in the same transition. Every other leaf of the car state stays
+// as it was.
+fn main() {
+    let input: CarTransferParams = env::read();
+    let published = input.published;
+    assert!(published.is_consistent(), "Published state does not match its roots");
+    assert_eq!(
+        merkle_root(&input.cars),
+        published.car_state.overall,
+        "Car leaves are not the published ones"
+    );
+
+    match input.action {
+        CarAction::BUY => {
+            let auction_id = input.auction_id.filter(|_| input.offer_id.is_none());
+            let auction_id = auction_id.expect("BUY needs an auction");
+            let settlement = input.settlement.as_ref().expect("BUY needs a settlement");
+            let journal = (match settlement {
+                AuctionSettlement::English(settlement) => to_vec(settlement),
+                AuctionSettlement::Sealed(settlement) => to_vec(settlement),
+            }).expect("Invalid settlement");
+            env::verify(settlement_image(settlement), &journal).expect("Settlement not proven");
+            assert_eq!(
+                settlement.auction(),
+                (auction_id, input.car_id),
+                "Settlement is for another auction"
+            );
+            assert!(
+                settlement.seller().eq_ignore_ascii_case(&input.from),
+                "Seller is not the auction's"
+            );
+            let (winner, amount) = settlement.sold_to().expect("Auction did not sell");
+            assert!(winner.eq_ignore_ascii_case(&input.to), "Buyer is not the winner");
+            assert_eq!(amount, input.amount, "Price is not the settled amount");
+        }
+        CarAction::SELL => {
+            let offer_id = input.offer_id.filter(|_| input.auction_id.is_none());
+            let offer_id = offer_id.expect("SELL needs an offer");
+            assert!(input.settlement.is_none(), "SELL is not settled by an auction");
+            let offer = input.cars
+                .iter()
+                .filter_map(|leaf| OfferLeaf::parse(leaf))
+                .find(|offer| offer.id == offer_id)
+                .expect("Offer not in the car state");
+            assert_eq!(offer.car_id, input.car_id, "Offer is for another car");
+            assert_eq!(offer.status, OfferStatus::Pending, "Offer is not pending");
+            assert!(offer.expires_at > input.timestamp, "Offer expired");
+            assert!(offer.buyer.eq_ignore_ascii_case(&input.to), "Buyer is not the offer's");
+            assert!(offer.seller.eq_ignore_ascii_case(&input.from), "Seller is not the offer's");
+            assert_eq!(offer.amount as u64, input.amount, "Price is not the offer's");
+        }
+        _ => panic!("Not a transfer action"),
+    }
+    assert!(!input.to.is_empty() && !input.to.contains(':'), "Invalid new owner");
+    assert_ne!(input.from, input.to, "Car already owned by the buyer");
+
+    let mut cars = input.cars.clone();
+    cars[input.index] = transfer_leaf(
+        &input.cars[input.index],
+        input.car_id,
+        &input.from,
+        &input.to
+    ).expect("Car not owned by the seller");
+    if input.action == CarAction::SELL {
+        // the sale accepts its offer and closes every other pending offer on the car
+        for leaf in cars.iter_mut() {
+            let Some(offer) = OfferLeaf::parse(leaf) else {
+                continue;
+            };
+            if offer.car_id != input.car_id || offer.status != OfferStatus::Pending {
+                continue;
+            }
+            let status = if Some(offer.id) == input.offer_id {
+                OfferStatus::Accepted
+            } else {
+                OfferStatus::Rejected
+            };
+            *leaf = (OfferLeaf { status, updated_at: input.timestamp, ..offer }).encode();
+        }
+    }
+
+    let before = CarState::init(input.cars);
+    let after = CarState::init(cars);
+
+    // write public output to the journal
+    env::commit(
+        &(CarTransfer {
+            state_root: published.new_state,
+            car_id: input.car_id,
+            from: input.from,
+            to: input.to,
+            auction_id: input.auction_id,
+            offer_id: input.offer_id,
+            amount: input.amount,
+            settlement_image: input.settlement.as_ref().map(settlement_image),
+            car_root: RootTransition { old: before.overall, new: after.overall },
+            state: CarState {
+                old_state: before.new_state,
+                new_state: after.new_state,
+                overall: after.overall,
+                leaves: after.leaves,
+                action: input.action,
+                actor: input.actor,
+            },
+        })
+    );
+}
+
+// Image id of the guest that proves this kind of settlement.
+fn settlement_image(settlement: &AuctionSettlement) -> Digest {
+    let image_id = match settlement {
+        AuctionSettlement::English(_) => SETTLE_AUCTION_ID,
+        AuctionSettlement::Sealed(_) => SETTLE_SEALED_ID,
+    };
+    Digest::from_hex(image_id).expect("Invalid settle image id")
+}
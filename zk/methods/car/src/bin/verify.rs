@@ -2,9 +2,8 @@
 #![no_std]
 
 use risc0_zkvm::guest::env;
-use k256::{ ecdsa::{ RecoveryId, Signature, VerifyingKey }, elliptic_curve::sec1::ToEncodedPoint };
 use sha3::{ Digest, Keccak256 };
-use car_auction_core::{ VerifyCommit, VerifyParams };
+use car_auction_core::{ eth::recover_address, siwe::SiweMessage, VerifyCommit, VerifyParams };
 use arrayvec::ArrayString;
 
 // #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -46,18 +45,6 @@ fn write_usize_to_buf(n: usize, buf: &mut [u8]) -> Option<&str> {
 }
 
 fn recover_ethereum_address(sig_hex: &str, msg: &str) -> Result<[u8; 20], &'static str> {
-    let sig_bytes = hex
-        ::decode(sig_hex.strip_prefix("0x").unwrap_or(sig_hex))
-        .map_err(|_| "Invalid signature hex")?;
-    if sig_bytes.len() != 65 {
-        return Err("Signature is not 65 bytes");
-    }
-    let sig = Signature::try_from(&sig_bytes[..64]).map_err(|_| "Bad signature format")?;
-    let v = sig_bytes[64];
-    let recid = RecoveryId::try_from(if v >= 27 { v - 27 } else { v % 2 }).map_err(
-        |_| "Invalid recovery id"
-    )?;
-
     let msg_bytes = msg.as_bytes();
 
     // Build prefix: "\x19Ethereum Signed Message:\n<length>"
@@ -75,18 +62,9 @@ fn recover_ethereum_address(sig_hex: &str, msg: &str) -> Result<[u8; 20], &'stat
     let mut hasher = Keccak256::new();
     hasher.update(prefix.as_bytes());
     hasher.update(msg_bytes);
-    let hash = hasher.finalize();
+    let hash: [u8; 32] = hasher.finalize().into();
 
-    let key = VerifyingKey::recover_from_prehash(&hash, &sig, recid).map_err(
-        |_| "Failed key recovery"
-    )?;
-    let enc = key.to_encoded_point(false);
-    let pub_bytes = enc.as_bytes();
-    let addr_hash = Keccak256::digest(&pub_bytes[1..]);
-    let mut addr = [0u8; 20];
-    addr.copy_from_slice(&addr_hash[12..]);
-
-    Ok(addr)
+    recover_address(&hash, sig_hex)
 }
 
 risc0_zkvm::guest::entry!(main);
@@ -113,13 +91,34 @@ fn main() {
         tmp
     };
 
-    let verified = expected.as_str() == eth_addr.as_str();
+    let signature_ok = expected.as_str() == eth_addr.as_str();
+
+    // the signed message must be an EIP-4361 login scoped to the host's domain and nonce
+    let siwe = SiweMessage::parse(&input.message).ok();
+    let scoped = siwe
+        .as_ref()
+        .map(|msg| {
+            msg.verify(&input.domain, &input.nonce, expected.as_str(), input.timestamp).is_ok()
+        })
+        .unwrap_or(false);
+    let issued_at = siwe
+        .as_ref()
+        .and_then(|msg| msg.issued_at_ts().ok())
+        .unwrap_or(0);
+    let expiration_time = siwe
+        .as_ref()
+        .and_then(|msg| msg.expiration_ts().ok().flatten())
+        .unwrap_or(0);
 
     let commit = VerifyCommit {
         timestamp: input.timestamp,
-        verified,
+        verified: signature_ok && scoped,
         address: expected.as_str().try_into().unwrap(),
         username: input.username,
+        domain: input.domain,
+        nonce: input.nonce,
+        issued_at,
+        expiration_time,
     };
     env::commit(&commit);
 }
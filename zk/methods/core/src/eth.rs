@@ -0,0 +1,41 @@
+// Ethereum signature recovery shared by the guests and the host.
+use k256::ecdsa::{ RecoveryId, Signature, VerifyingKey };
+use tiny_keccak::{ Hasher, Keccak };
+
+pub fn keccak256(data: &[u8]) -> [u8; 32] {
+    let mut hasher = Keccak::v256();
+    hasher.update(data);
+    let mut output = [0; 32];
+    hasher.finalize(&mut output);
+    output
+}
+
+// Recovers the address that produced a 65-byte `r || s || v` signature over a 32-byte hash.
+pub fn recover_address(prehash: &[u8; 32], sig_hex: &str) -> Result<[u8; 20], &'static str> {
+    let sig_bytes = hex
+        ::decode(sig_hex.strip_prefix("0x").unwrap_or(sig_hex))
+        .map_err(|_| "Invalid signature hex")?;
+    if sig_bytes.len() != 65 {
+        return Err("Signature is not 65 bytes");
+    }
+    let sig = Signature::try_from(&sig_bytes[..64]).map_err(|_| "Bad signature format")?;
+    let v = sig_bytes[64];
+    let recid = RecoveryId::try_from(if v >= 27 { v - 27 } else { v % 2 }).map_err(
+        |_| "Invalid recovery id"
+    )?;
+
+    let key = VerifyingKey::recover_from_prehash(prehash, &sig, recid).map_err(
+        |_| "Failed key recovery"
+    )?;
+    let enc = key.to_encoded_point(false);
+    let addr_hash = keccak256(&enc.as_bytes()[1..]);
+    let mut addr = [0u8; 20];
+    addr.copy_from_slice(&addr_hash[12..]);
+
+    Ok(addr)
+}
+
+// Lowercase 0x-prefixed hex, the form addresses are compared in.
+pub fn to_hex_address(addr: &[u8; 20]) -> String {
+    format!("0x{}", hex::encode(addr))
+}
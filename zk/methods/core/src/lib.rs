@@ -4,6 +4,15 @@ use risc0_zkp::core::digest::Digest;
 use tiny_keccak::{ Hasher, Keccak };
 use chrono::{ DateTime, Local };
 
+pub mod siwe;
+pub mod eth;
+pub mod eip712;
+pub mod merkle;
+pub mod sealed;
+
+use eip712::{ BidAuthorization, Eip712Domain };
+use merkle::{ MerklePath, RootTransition };
+
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct VerifyParams {
     pub message: String,
@@ -11,6 +20,9 @@ pub struct VerifyParams {
     pub expected_addr: String,
     pub timestamp: i64,
     pub username: String,
+    // the host's SIWE domain and the nonce it issued; the signed message must match both
+    pub domain: String,
+    pub nonce: String,
 }
 // pub struct VerifyParams {
 //     pub message: String,
@@ -26,6 +38,10 @@ pub struct VerifyCommit {
     pub address: String,
     pub timestamp: i64,
     pub username: String,
+    pub domain: String,
+    pub nonce: String,
+    pub issued_at: i64,
+    pub expiration_time: i64,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -33,6 +49,219 @@ pub struct OverallParams {
     pub car_leaves: Vec<String>,
     pub auc_leaves: Vec<String>,
     pub bid_leaves: Vec<String>,
+    // role the request was made under, committed on every sub-state
+    pub actor: Actor,
+    // change the commit records, on the sub-state it touched; the others stay INIT
+    pub action: Option<StateAction>,
+    // the sequencer's number for this commit and the root of the one before it, so the
+    // published commits chain into one history
+    pub sequence: Option<u64>,
+    pub previous: Option<Digest>,
+    // the leaves `previous` was proven over, given for actions that change records in place so
+    // the guest can check the new leaves follow from them
+    pub previous_leaves: Option<StateLeaves>,
+}
+
+// The leaves of every sub-state, as the sequencer snapshots them for a commit.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct StateLeaves {
+    pub car: Vec<String>,
+    pub auction: Vec<String>,
+    pub bid: Vec<String>,
+}
+
+impl StateLeaves {
+    // Root of the state these leaves hold, as `state_root` computes it.
+    pub fn root(&self) -> Digest {
+        state_root(
+            &CarState::init(self.car.clone()),
+            &AuctionState::init(self.auction.clone()),
+            &BidState::init(self.bid.clone())
+        )
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum StateAction {
+    Car(CarAction),
+    Auction(AuctionAction),
+    Bid(BidAction),
+}
+
+// The leaves that differ between two versions of a tree, as (old, new) pairs, or None when
+// leaves were added or removed.
+fn changed_leaves<'a>(old: &'a [String], new: &'a [String]) -> Option<Vec<(&'a str, &'a str)>> {
+    if old.len() != new.len() {
+        return None;
+    }
+    Some(
+        old
+            .iter()
+            .zip(new)
+            .filter(|(old, new)| old != new)
+            .map(|(old, new)| (old.as_str(), new.as_str()))
+            .collect()
+    )
+}
+
+// The id a car, auction or bid leaf starts with.
+fn leaf_id(leaf: &str) -> Option<i32> {
+    leaf.split(':').next()?.parse().ok()
+}
+
+// Whether a car UPDATE leads from car leaf `old` to `new`: the listing and `starting_price` may
+// change, and `updated_at` with them, but not the car, its auction, price, token or owner. Those
+// are read from the right, as free-text fields further left may hold colons.
+fn car_edited(old: &str, new: &str) -> bool {
+    // owner, token_id, created_at, auction_status, current_price and auction_id
+    let fixed = |leaf: &'_ str| -> Vec<String> {
+        leaf.rsplit(':')
+            .take(8)
+            .enumerate()
+            .filter(|(field, _)| *field != 2 && *field != 6)
+            .map(|(_, value)| value.to_string())
+            .collect()
+    };
+    leaf_id(old).is_some() && leaf_id(old) == leaf_id(new) && fixed(old) == fixed(new)
+}
+
+// Whether an auction UPDATE leads from `old` to `new`: either an edit before the first bid,
+// which may move the schedule, prices and status between pending and active, or a sealed
+// commitment, which only counts one more commitment under a new `commitment_root`.
+fn auction_updated(old: &AuctionLeaf, new: &AuctionLeaf) -> bool {
+    let edited = AuctionLeaf {
+        id: old.id,
+        car_id: old.car_id,
+        seller: old.seller.clone(),
+        bid_count: old.bid_count,
+        auction_type: old.auction_type,
+        commitment_root: old.commitment_root.clone(),
+        created_at: old.created_at,
+        ..new.clone()
+    };
+    let committed = AuctionLeaf {
+        bid_count: new.bid_count,
+        commitment_root: new.commitment_root.clone(),
+        updated_at: new.updated_at,
+        ..old.clone()
+    };
+    let edit =
+        old.bid_count == 0 &&
+        old.commitment_root.is_none() &&
+        matches!(new.status, AuctionStatus::Pending | AuctionStatus::Active) &&
+        edited == *new;
+    let commitment =
+        old.auction_type.is_sealed() &&
+        new.bid_count == old.bid_count + 1 &&
+        new.commitment_root.is_some() &&
+        committed == *new;
+    edit || commitment
+}
+
+// Whether an auction DELETE leads from `old` to `new`: an auction without bids is cancelled.
+fn auction_cancelled(old: &AuctionLeaf, new: &AuctionLeaf) -> bool {
+    let cancelled = AuctionLeaf {
+        status: AuctionStatus::Cancelled,
+        updated_at: new.updated_at,
+        ..old.clone()
+    };
+    old.bid_count == 0 && old.status != AuctionStatus::Cancelled && cancelled == *new
+}
+
+// Whether a bid UPDATE leads from `old` to `new`: a standing bid is raised, and only its
+// `amount` and `updated_at` move.
+fn bid_raised(old: &BidLeaf, new: &BidLeaf) -> bool {
+    let raised = BidLeaf { amount: new.amount, updated_at: new.updated_at, ..old.clone() };
+    old.retracted_at.is_none() && new.amount > old.amount && raised == *new
+}
+
+// Whether a bid DELETE leads from `old` to `new`: a standing bid is marked retracted, and only
+// `retracted_at` and `updated_at` move.
+fn bid_retracted(old: &BidLeaf, new: &BidLeaf) -> bool {
+    let retracted = BidLeaf {
+        retracted_at: new.retracted_at,
+        updated_at: new.updated_at,
+        ..old.clone()
+    };
+    old.retracted_at.is_none() && new.retracted_at.is_some() && retracted == *new
+}
+
+impl StateAction {
+    // UPDATE and DELETE change existing records; the other actions are proven by their own
+    // guests or add records.
+    pub fn in_place(&self) -> bool {
+        matches!(
+            self,
+            StateAction::Car(CarAction::UPDATE) |
+                StateAction::Auction(AuctionAction::UPDATE | AuctionAction::DELETE) |
+                StateAction::Bid(BidAction::UPDATE | BidAction::DELETE)
+        )
+    }
+
+    // Whether `new` follows from `old` by this in-place action: no tree gains or loses a leaf,
+    // the action's tree changes exactly one, the same record, and only in the fields the action
+    // may change. Besides it only the leaf it belongs to may change: the car of an auction, or
+    // the auction of a bid.
+    pub fn follows(&self, old: &StateLeaves, new: &StateLeaves) -> bool {
+        let (Some(cars), Some(auctions), Some(bids)) = (
+            changed_leaves(&old.car, &new.car),
+            changed_leaves(&old.auction, &new.auction),
+            changed_leaves(&old.bid, &new.bid),
+        ) else {
+            return false;
+        };
+        match self {
+            StateAction::Car(CarAction::UPDATE) =>
+                matches!(cars[..], [(old, new)] if car_edited(old, new)) &&
+                    auctions.is_empty() &&
+                    bids.is_empty(),
+            StateAction::Auction(action @ (AuctionAction::UPDATE | AuctionAction::DELETE)) => {
+                let [(old, new)] = auctions[..] else {
+                    return false;
+                };
+                let (Some(old), Some(new)) = (
+                    AuctionLeaf::parse(old),
+                    AuctionLeaf::parse(new),
+                ) else {
+                    return false;
+                };
+                let changed = if *action == AuctionAction::UPDATE {
+                    auction_updated(&old, &new)
+                } else {
+                    auction_cancelled(&old, &new)
+                };
+                let own_car = match cars[..] {
+                    [] => true,
+                    [(before, after)] =>
+                        leaf_id(before) == Some(new.car_id) && leaf_id(after) == Some(new.car_id),
+                    _ => false,
+                };
+                changed && own_car && bids.is_empty()
+            }
+            StateAction::Bid(action @ (BidAction::UPDATE | BidAction::DELETE)) => {
+                let [(old, new)] = bids[..] else {
+                    return false;
+                };
+                let (Some(old), Some(new)) = (BidLeaf::parse(old), BidLeaf::parse(new)) else {
+                    return false;
+                };
+                let changed = if *action == BidAction::UPDATE {
+                    bid_raised(&old, &new)
+                } else {
+                    bid_retracted(&old, &new)
+                };
+                let own_auction = match auctions[..] {
+                    [] => true,
+                    [(before, after)] =>
+                        leaf_id(before) == Some(new.auction_id) &&
+                            leaf_id(after) == Some(new.auction_id),
+                    _ => false,
+                };
+                changed && own_auction && cars.is_empty()
+            }
+            _ => false,
+        }
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -61,7 +290,7 @@ impl CarAuctionState {
         // update state and sync with remote state.
     }
 
-    fn sync() {
+    pub fn sync() {
         //sync state with smart contract
         //verify localState with remoteState (contract)
     }
@@ -78,6 +307,9 @@ pub enum AuctionAction {
 pub enum Actor {
     ADMIN,
     SYSTEM,
+    SELLER,
+    BIDDER,
+    INSPECTOR,
 }
 
 // actor rules - clearly stating what those actors does and their permissions.
@@ -97,19 +329,16 @@ impl AuctionState {
     pub fn init(db: Vec<String>) -> Self {
         let mut auction_state: Vec<Digest> = vec![];
         let mut hasher = Keccak::v256();
-        let mut hasherb = Keccak::v256();
         for x in &db {
-            hasher.update(&x.as_bytes());
-            hasherb.update(&x.as_bytes());
+            hasher.update(x.as_bytes());
             let mut output = [0; 32];
             hasher.clone().finalize(&mut output);
             let digest = Digest::from_bytes(output);
             // let xr = sha256(x);
             auction_state.push(digest);
         }
-        let mut output = [0; 32];
-        hasherb.finalize(&mut output);
-        let digest = Digest::from_bytes(output);
+        // the Merkle root, so a guest can check a single leaf against a published state
+        let digest = merkle::merkle_root(&db);
 
         AuctionState {
             old_state: auction_state.clone(),
@@ -126,16 +355,422 @@ impl AuctionState {
         self.new_state = new_state;
         self.overall = overall;
     }
-    pub fn process(action: AuctionAction) {
-        match action {
-            init => {
-                // call the function here and make sure it workes.
-                // each function call returns the new state which will be used here to update the state.
+    pub fn process(_action: AuctionAction) {
+        // call the function here and make sure it workes.
+        // each function call returns the new state which will be used here to update the
+        // state.
+    }
+
+    pub fn sync() {}
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum AuctionStatus {
+    Pending,
+    Active,
+    Completed,
+    Cancelled,
+}
+
+// English auctions take open ascending bids and Dutch auctions sell to the first bid at the
+// falling clock price. Sealed (first-price) and Vickrey (second-price) auctions take commitments
+// and are settled on reveal.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum AuctionType {
+    English,
+    Sealed,
+    Vickrey,
+    Dutch,
+}
+
+impl AuctionType {
+    pub fn is_sealed(&self) -> bool {
+        matches!(self, AuctionType::Sealed | AuctionType::Vickrey)
+    }
+}
+
+// A field the leaves encode with `{:?}` from an Option: None for `None`, else what `Some(..)`
+// holds. `None` outside when the field is neither.
+fn parse_option(field: &str) -> Option<Option<&str>> {
+    match field {
+        "None" => Some(None),
+        some => Some(Some(some.strip_prefix("Some(")?.strip_suffix(')')?)),
+    }
+}
+
+// An optional number field, as `parse_option` reads it.
+fn parse_number(field: &str) -> Option<Option<i64>> {
+    match parse_option(field)? {
+        None => Some(None),
+        Some(number) => Some(Some(number.parse().ok()?)),
+    }
+}
+
+// One auction record as hashed into the auction state; the host builds its leaves from it.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct AuctionLeaf {
+    pub id: i32,
+    pub car_id: i32,
+    pub seller: String,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub current_bid: i64,
+    pub bid_count: i32,
+    pub status: AuctionStatus,
+    pub auction_type: AuctionType,
+    // end of the reveal window of a sealed auction
+    pub reveal_end_time: Option<i64>,
+    // price a Dutch auction falls to at `end_time`
+    pub floor_price: Option<i64>,
+    // lowest winning amount the seller accepts
+    pub reserve_price: Option<i64>,
+    // a bid at or above this closes an english auction
+    pub buy_now_price: Option<i64>,
+    // overrides the host's minimum increment when set
+    pub min_increment: Option<i64>,
+    // soft close: a bid within `soft_close_window` seconds of `end_time` pushes it out by
+    // `soft_close_extension` seconds, never past `max_end_time`
+    pub soft_close_window: Option<i64>,
+    pub soft_close_extension: Option<i64>,
+    pub max_end_time: Option<i64>,
+    // `current_bid` the auction opened at, restored once every bid is retracted
+    pub opening_price: i64,
+    // hex root over a sealed auction's commitments, `bid_count` of them; None before the first
+    pub commitment_root: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl AuctionLeaf {
+    pub fn encode(&self) -> String {
+        format!(
+            "{}:{}:{}:{}:{}:{}:{}:{:?}:{:?}:{:?}:{:?}:{:?}:{:?}:{:?}:{:?}:{:?}:{:?}:{}:{:?}:{}:{}",
+            self.id,
+            self.car_id,
+            self.seller,
+            self.start_time,
+            self.end_time,
+            self.current_bid,
+            self.bid_count,
+            self.status,
+            self.auction_type,
+            self.reveal_end_time,
+            self.floor_price,
+            self.reserve_price,
+            self.buy_now_price,
+            self.min_increment,
+            self.soft_close_window,
+            self.soft_close_extension,
+            self.max_end_time,
+            self.opening_price,
+            self.commitment_root,
+            self.created_at,
+            self.updated_at
+        )
+    }
+
+    // `None` when the leaf is not an auction.
+    pub fn parse(leaf: &str) -> Option<Self> {
+        let fields: Vec<&str> = leaf.split(':').collect();
+        if fields.len() != 21 {
+            return None;
+        }
+        let status = match fields[7] {
+            "Pending" => AuctionStatus::Pending,
+            "Active" => AuctionStatus::Active,
+            "Completed" => AuctionStatus::Completed,
+            "Cancelled" => AuctionStatus::Cancelled,
+            _ => {
+                return None;
+            }
+        };
+        let auction_type = match fields[8] {
+            "English" => AuctionType::English,
+            "Sealed" => AuctionType::Sealed,
+            "Vickrey" => AuctionType::Vickrey,
+            "Dutch" => AuctionType::Dutch,
+            _ => {
+                return None;
             }
+        };
+        let commitment_root = match parse_option(fields[18])? {
+            None => None,
+            Some(root) => Some(root.strip_prefix('"')?.strip_suffix('"')?.to_string()),
+        };
+        Some(AuctionLeaf {
+            id: fields[0].parse().ok()?,
+            car_id: fields[1].parse().ok()?,
+            seller: fields[2].to_string(),
+            start_time: fields[3].parse().ok()?,
+            end_time: fields[4].parse().ok()?,
+            current_bid: fields[5].parse().ok()?,
+            bid_count: fields[6].parse().ok()?,
+            status,
+            auction_type,
+            reveal_end_time: parse_number(fields[9])?,
+            floor_price: parse_number(fields[10])?,
+            reserve_price: parse_number(fields[11])?,
+            buy_now_price: parse_number(fields[12])?,
+            min_increment: parse_number(fields[13])?,
+            soft_close_window: parse_number(fields[14])?,
+            soft_close_extension: parse_number(fields[15])?,
+            max_end_time: parse_number(fields[16])?,
+            opening_price: fields[17].parse().ok()?,
+            commitment_root,
+            created_at: fields[19].parse().ok()?,
+            updated_at: fields[20].parse().ok()?,
+        })
+    }
+
+    // Clock price of a Dutch auction: falls linearly from `current_bid` at `start_time` to
+    // `floor_price` at `end_time`. None for other auction types.
+    pub fn dutch_price(&self, timestamp: i64) -> Option<i64> {
+        if self.auction_type != AuctionType::Dutch {
+            return None;
+        }
+        let floor = self.floor_price?;
+        if timestamp <= self.start_time {
+            return Some(self.current_bid);
         }
+        if timestamp >= self.end_time {
+            return Some(floor);
+        }
+        let elapsed = (timestamp - self.start_time) as i128;
+        let duration = (self.end_time - self.start_time) as i128;
+        let drop = (((self.current_bid - floor) as i128) * elapsed) / duration;
+        Some(self.current_bid - (drop as i64))
     }
 
-    pub fn sync() {}
+    // The `end_time` a bid at `timestamp` extends the auction to, if it lands in the soft-close
+    // window and the cap leaves room.
+    pub fn soft_close_end(&self, timestamp: i64) -> Option<i64> {
+        let window = self.soft_close_window?;
+        let extension = self.soft_close_extension?;
+        if timestamp < self.end_time - window {
+            return None;
+        }
+        let end = (self.end_time + extension).min(self.max_end_time?);
+        (end > self.end_time).then_some(end)
+    }
+
+    // True once an english auction took a bid at its buy-now price.
+    pub fn bought(&self) -> bool {
+        self.bid_count > 0 && self.buy_now_price.is_some_and(|price| self.current_bid >= price)
+    }
+
+    // How a winning amount settles: it must meet the reserve to sell.
+    pub fn outcome(&self, winning_amount: Option<u64>) -> AuctionOutcome {
+        match winning_amount {
+            None => AuctionOutcome::NoBids,
+            Some(amount) if self.reserve_price.is_some_and(|reserve| (amount as i64) < reserve) =>
+                AuctionOutcome::ReserveNotMet,
+            Some(_) => AuctionOutcome::Sold,
+        }
+    }
+}
+
+// Result of a settlement. Only `Sold` has a winner; the seller can relist the others.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum AuctionOutcome {
+    Sold,
+    NoBids,
+    ReserveNotMet,
+}
+
+// One bid record as hashed into the bid state; the host builds its leaves from it.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct BidLeaf {
+    pub id: i32,
+    pub auction_id: i32,
+    pub bidder: String,
+    pub amount: i64,
+    pub created_at: i64,
+    pub updated_at: i64,
+    // a retracted bid stays in the bid tree but no longer counts for the auction
+    pub retracted_at: Option<i64>,
+}
+
+impl BidLeaf {
+    pub fn encode(&self) -> String {
+        format!(
+            "{}:{}:{}:{}:{}:{}:{:?}",
+            self.id,
+            self.auction_id,
+            self.bidder,
+            self.amount,
+            self.created_at,
+            self.updated_at,
+            self.retracted_at
+        )
+    }
+
+    // `None` when the leaf is not a bid.
+    pub fn parse(leaf: &str) -> Option<Self> {
+        let fields: Vec<&str> = leaf.split(':').collect();
+        if fields.len() != 7 {
+            return None;
+        }
+        Some(BidLeaf {
+            id: fields[0].parse().ok()?,
+            auction_id: fields[1].parse().ok()?,
+            bidder: fields[2].to_string(),
+            amount: fields[3].parse().ok()?,
+            created_at: fields[4].parse().ok()?,
+            updated_at: fields[5].parse().ok()?,
+            retracted_at: parse_number(fields[6])?,
+        })
+    }
+}
+
+// An `end_time` moved by a bid in the soft-close window.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct AuctionExtension {
+    pub auction_id: i32,
+    pub bid_id: i32,
+    pub old_end_time: i64,
+    pub new_end_time: i64,
+}
+
+// Input of the create_bid guest: a new bid signed by its bidder, the auction it targets with
+// its Merkle path in the auction tree, the auction's car with its path in the car tree, and the
+// current bid leaves, all under `published`, the last proven state commit. For a proxy bid
+// `bid` is the bidder's signed maximum and `proxy_amount` the amount placed under it. A bidder
+// raising their standing english bid passes it as `raises`, and its leaf is replaced instead of
+// a new one added.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct BidParams {
+    pub published: OverallState,
+    pub auction: AuctionLeaf,
+    pub auction_path: MerklePath,
+    pub auction_root: Digest,
+    pub car: String,
+    pub car_path: MerklePath,
+    pub min_increment: u64,
+    pub bid_leaves: Vec<String>,
+    pub bid_id: i32,
+    pub bidder: String,
+    pub bid: BidAuthorization,
+    pub proxy_amount: Option<u64>,
+    pub raises: Option<BidLeaf>,
+    pub signature: String,
+    pub domain: Eip712Domain,
+    pub timestamp: i64,
+    pub actor: Actor,
+}
+
+// Journal of the create_bid guest. `digest` is the EIP-712 hash the bidder signed, and the
+// roots are the auction and bid Merkle roots before and after the bid. `state_root` is the
+// published root the old auction and bid roots belong to. A proxy bid commits no digest or
+// nonce, as they would let anyone search for the private maximum.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct BidTransition {
+    pub state: BidState,
+    pub state_root: Digest,
+    pub bid_id: i32,
+    pub auction_id: u64,
+    pub bidder: String,
+    pub amount: u64,
+    pub nonce: Option<u64>,
+    pub digest: Option<Digest>,
+    pub proxy: bool,
+    pub min_increment: u64,
+    // the bid met the buy-now price and closed the auction
+    pub buy_now: bool,
+    // the bid landed in the soft-close window and pushed out `end_time`
+    pub extension: Option<AuctionExtension>,
+    pub auction_root: RootTransition,
+    pub bid_root: RootTransition,
+}
+
+// Input of the settle_auction guest: the auction with its Merkle path, every bid in the bid
+// tree, and the leaf of the car sold with its path in the car state, under `published`, the last
+// proven state commit. All bids are passed so the guest can check none was left out.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SettlementParams {
+    pub published: OverallState,
+    pub auction: AuctionLeaf,
+    pub auction_path: MerklePath,
+    pub auction_root: Digest,
+    pub bids: Vec<BidLeaf>,
+    pub bid_root: Digest,
+    pub car: String,
+    pub car_path: MerklePath,
+    pub timestamp: i64,
+    pub actor: Actor,
+}
+
+// Journal of the settle_auction guest: what `claimWin` needs, plus the auction root moving to
+// the completed auction leaf and the published `state_root` it moved from. `winner` is None
+// unless the outcome is `Sold`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Settlement {
+    pub state_root: Digest,
+    pub auction_id: i32,
+    pub car_id: i32,
+    pub token_id: i32,
+    pub seller: String,
+    pub winner: Option<String>,
+    pub winning_bid: Option<i32>,
+    pub outcome: AuctionOutcome,
+    pub amount: u64,
+    pub auction_root: RootTransition,
+    pub bid_root: Digest,
+    pub settled_at: i64,
+    pub actor: Actor,
+}
+
+// The auction's rules for picking a winner: the highest bid still standing, and on a tie the
+// earlier one.
+pub fn winning_bid(auction_id: i32, bids: &[BidLeaf]) -> Option<&BidLeaf> {
+    bids.iter()
+        .filter(|bid| bid.auction_id == auction_id && bid.retracted_at.is_none())
+        .min_by_key(|bid| (std::cmp::Reverse(bid.amount), bid.created_at, bid.id))
+}
+
+// When a bidder may take a bid back: up to `cutoff` seconds before the auction ends, at most
+// `limit` times per auction, and always with a reason.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RetractionPolicy {
+    pub cutoff: i64,
+    pub limit: u32,
+}
+
+// Input of the retract_bid guest: the auction with its Merkle path, every bid in the bid tree
+// and the bid to retract, under `published`, the last proven state commit. All bids are passed
+// so the guest can recompute the auction from the ones left standing.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RetractionParams {
+    pub published: OverallState,
+    pub auction: AuctionLeaf,
+    pub auction_path: MerklePath,
+    pub auction_root: Digest,
+    pub bids: Vec<BidLeaf>,
+    pub bid_id: i32,
+    pub bidder: String,
+    pub reason: String,
+    pub policy: RetractionPolicy,
+    pub timestamp: i64,
+    pub actor: Actor,
+}
+
+// Journal of the retract_bid guest: the retracted bid, the policy it was checked against, and
+// the auction leaf moving to the highest remaining bid (or its opening price) with one bid
+// fewer. `state_root` is the published root the old auction and bid roots belong to.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct BidRetraction {
+    pub state: BidState,
+    pub state_root: Digest,
+    pub bid_id: i32,
+    pub auction_id: i32,
+    pub bidder: String,
+    pub amount: u64,
+    pub reason: String,
+    pub policy: RetractionPolicy,
+    pub current_bid: u64,
+    pub bid_count: i32,
+    pub auction_root: RootTransition,
+    pub bid_root: RootTransition,
+    pub retracted_at: i64,
 }
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -160,19 +795,15 @@ impl BidState {
     pub fn init(db: Vec<String>) -> Self {
         let mut auction_state: Vec<Digest> = vec![];
         let mut hasher = Keccak::v256();
-        let mut hasherb = Keccak::v256();
         for x in &db {
-            hasher.update(&x.as_bytes());
-            hasherb.update(&x.as_bytes());
+            hasher.update(x.as_bytes());
             let mut output = [0; 32];
             hasher.clone().finalize(&mut output);
             let digest = Digest::from_bytes(output);
             // let xr = sha256(x);
             auction_state.push(digest);
         }
-        let mut output = [0; 32];
-        hasherb.finalize(&mut output);
-        let digest = Digest::from_bytes(output);
+        let digest = merkle::merkle_root(&db);
 
         BidState {
             old_state: auction_state.clone(),
@@ -189,19 +820,16 @@ impl BidState {
         self.new_state = new_state;
         self.overall = overall;
     }
-    pub fn process(action: AuctionAction) {
-        match action {
-            init => {
-                // call the function here and make sure it workes.
-                // each function call returns the new state which will be used here to update the state.
-            }
-        }
+    pub fn process(_action: AuctionAction) {
+        // call the function here and make sure it workes.
+        // each function call returns the new state which will be used here to update the
+        // state.
     }
 
     pub fn sync() {}
 }
 
-#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct OverallState {
     pub car_state: CarState,
     pub auc_state: AuctionState,
@@ -209,6 +837,13 @@ pub struct OverallState {
     pub old_state: Digest,
     pub new_state: Digest,
     pub updated_at: String,
+    pub sequence: Option<u64>,
+}
+
+impl Default for OverallState {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl OverallState {
@@ -243,12 +878,13 @@ impl OverallState {
             actor: Actor::SYSTEM,
         };
         OverallState {
-            car_state: car_state,
-            auc_state: auc_state,
-            bid_state: bid_state,
+            car_state,
+            auc_state,
+            bid_state,
             old_state: digest,
             new_state: digest,
             updated_at: "date".to_string(),
+            sequence: None,
         }
     }
     pub fn sync(
@@ -261,14 +897,7 @@ impl OverallState {
         // checks
         // new_state == old_state
         // let mut auction_state: Vec<Digest> = vec![];
-        let mut hasher = Keccak::v256();
-
-        hasher.update(auc.overall.as_bytes());
-        hasher.update(bid.overall.as_bytes());
-        hasher.update(car.overall.as_bytes());
-        let mut output = [0; 32];
-        hasher.finalize(&mut output);
-        let digest = Digest::from_bytes(output);
+        let digest = state_root(car, auc, bid);
         if car.old_state != self.car_state.new_state {
             return Err("Car state sync failed".into());
             // Err::<T, E>("some error message");
@@ -286,8 +915,35 @@ impl OverallState {
             old_state: self.new_state,
             new_state: digest,
             updated_at: dt.to_string(),
+            sequence: self.sequence.map(|sequence| sequence + 1),
         })
     }
+
+    // Whether the sub-state roots hash to `new_state`. A guest proving an action against a
+    // published commit checks this before trusting the sub-state roots it carries.
+    pub fn is_consistent(&self) -> bool {
+        self.new_state == state_root(&self.car_state, &self.auc_state, &self.bid_state)
+    }
+}
+
+// keccak256 of a single record, e.g. one car leaf referenced from NFT metadata.
+pub fn leaf_hash(leaf: &str) -> Digest {
+    let mut hasher = Keccak::v256();
+    hasher.update(leaf.as_bytes());
+    let mut output = [0; 32];
+    hasher.finalize(&mut output);
+    Digest::from_bytes(output)
+}
+
+// root over the three sub-states, hashed in the same order `OverallState::sync` commits.
+pub fn state_root(car: &CarState, auc: &AuctionState, bid: &BidState) -> Digest {
+    let mut hasher = Keccak::v256();
+    hasher.update(auc.overall.as_bytes());
+    hasher.update(bid.overall.as_bytes());
+    hasher.update(car.overall.as_bytes());
+    let mut output = [0; 32];
+    hasher.finalize(&mut output);
+    Digest::from_bytes(output)
 }
 
 // car state
@@ -316,19 +972,15 @@ impl CarState {
     pub fn init(db: Vec<String>) -> Self {
         let mut auction_state: Vec<Digest> = vec![];
         let mut hasher = Keccak::v256();
-        let mut hasherb = Keccak::v256();
         for x in &db {
-            hasher.update(&x.as_bytes());
-            hasherb.update(&x.as_bytes());
+            hasher.update(x.as_bytes());
             let mut output = [0; 32];
             hasher.clone().finalize(&mut output);
             let digest = Digest::from_bytes(output);
             // let xr = sha256(x);
             auction_state.push(digest);
         }
-        let mut output = [0; 32];
-        hasherb.finalize(&mut output);
-        let digest = Digest::from_bytes(output);
+        let digest = merkle::merkle_root(&db);
 
         CarState {
             old_state: auction_state.clone(),
@@ -345,16 +997,439 @@ impl CarState {
         self.new_state = new_state;
         self.overall = overall;
     }
-    pub fn process(action: AuctionAction) {
-        match action {
-            init => {
-                // call the function here and make sure it workes.
-                // each function call returns the new state which will be used here to update the state.
+    pub fn process(_action: AuctionAction) {
+        // call the function here and make sure it workes.
+        // each function call returns the new state which will be used here to update the
+        // state.
+    }
+
+    pub fn sync() {}
+}
+
+// offers
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum OfferStatus {
+    Pending,
+    Accepted,
+    Rejected,
+    Countered,
+    Expired,
+}
+
+// One offer record as hashed into the car state after the cars, prefixed so it never reads as
+// a car. The host builds its leaves from it, and the transfer guest reads offers back with
+// `parse`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct OfferLeaf {
+    pub id: i32,
+    pub car_id: i32,
+    pub buyer: String,
+    pub seller: String,
+    pub proposer: String,
+    pub amount: i64,
+    pub expires_at: i64,
+    pub status: OfferStatus,
+    pub counter_of: Option<i32>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl OfferLeaf {
+    pub fn encode(&self) -> String {
+        format!(
+            "offer:{}:{}:{}:{}:{}:{}:{}:{:?}:{:?}:{}:{}",
+            self.id,
+            self.car_id,
+            self.buyer,
+            self.seller,
+            self.proposer,
+            self.amount,
+            self.expires_at,
+            self.status,
+            self.counter_of,
+            self.created_at,
+            self.updated_at
+        )
+    }
+
+    // `None` when the leaf is not an offer.
+    pub fn parse(leaf: &str) -> Option<Self> {
+        let fields: Vec<&str> = leaf.strip_prefix("offer:")?.split(':').collect();
+        if fields.len() != 11 {
+            return None;
+        }
+        let status = match fields[7] {
+            "Pending" => OfferStatus::Pending,
+            "Accepted" => OfferStatus::Accepted,
+            "Rejected" => OfferStatus::Rejected,
+            "Countered" => OfferStatus::Countered,
+            "Expired" => OfferStatus::Expired,
+            _ => {
+                return None;
             }
+        };
+        let counter_of = match fields[8] {
+            "None" => None,
+            counter => Some(counter.strip_prefix("Some(")?.strip_suffix(')')?.parse().ok()?),
+        };
+        Some(OfferLeaf {
+            id: fields[0].parse().ok()?,
+            car_id: fields[1].parse().ok()?,
+            buyer: fields[2].to_string(),
+            seller: fields[3].to_string(),
+            proposer: fields[4].to_string(),
+            amount: fields[5].parse().ok()?,
+            expires_at: fields[6].parse().ok()?,
+            status,
+            counter_of,
+            created_at: fields[9].parse().ok()?,
+            updated_at: fields[10].parse().ok()?,
+        })
+    }
+}
+
+// car ownership
+
+// Moves the owner, the last field of a car leaf, from `from` to `to`. `None` when the leaf is
+// not car `car_id` or `from` does not own it.
+pub fn transfer_leaf(leaf: &str, car_id: i32, from: &str, to: &str) -> Option<String> {
+    if !leaf.starts_with(&format!("{}:", car_id)) {
+        return None;
+    }
+    let head = leaf.strip_suffix(&format!(":{}", from))?;
+    Some(format!("{}:{}", head, to))
+}
+
+// The `starting_price` of car `car_id` from its leaf. It is read from the right, where it is the
+// seventh field, since free-text fields further left may hold colons. `None` when the leaf is not
+// car `car_id`.
+pub fn starting_price(leaf: &str, car_id: i32) -> Option<u64> {
+    if !leaf.starts_with(&format!("{}:", car_id)) {
+        return None;
+    }
+    leaf.rsplit(':').nth(6)?.parse().ok()
+}
+
+// The NFT `token_id` of car `car_id` from its leaf, the field before the owner. `None` when the
+// leaf is not car `car_id`.
+pub fn token_id(leaf: &str, car_id: i32) -> Option<i32> {
+    if !leaf.starts_with(&format!("{}:", car_id)) {
+        return None;
+    }
+    leaf.rsplit(':').nth(1)?.parse().ok()
+}
+
+// The settlement of the auction a BUY comes from, as its guest committed it.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum AuctionSettlement {
+    English(Settlement),
+    Sealed(sealed::SealedSettlement),
+}
+
+impl AuctionSettlement {
+    // auction and car the settlement is for
+    pub fn auction(&self) -> (i32, i32) {
+        match self {
+            AuctionSettlement::English(settlement) => (settlement.auction_id, settlement.car_id),
+            AuctionSettlement::Sealed(settlement) => (settlement.auction_id, settlement.car_id),
         }
     }
 
-    pub fn sync() {}
+    // seller who listed the auction, who must still own the car it transfers
+    pub fn seller(&self) -> &str {
+        match self {
+            AuctionSettlement::English(settlement) => &settlement.seller,
+            AuctionSettlement::Sealed(settlement) => &settlement.seller,
+        }
+    }
+
+    // The winner and the price paid, when the auction sold.
+    pub fn sold_to(&self) -> Option<(&str, u64)> {
+        let (winner, outcome, amount) = match self {
+            AuctionSettlement::English(settlement) =>
+                (&settlement.winner, settlement.outcome, settlement.amount),
+            AuctionSettlement::Sealed(settlement) =>
+                (&settlement.winner, settlement.outcome, settlement.amount),
+        };
+        match (winner, outcome) {
+            (Some(winner), AuctionOutcome::Sold) => Some((winner.as_str(), amount)),
+            _ => None,
+        }
+    }
+}
+
+// Input of the transfer_car guest. `cars` must be the car state leaves of `published`, the last
+// proven state commit.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct CarTransferParams {
+    pub published: OverallState,
+    pub cars: Vec<String>, // car state leaves before the transfer, cars first
+    pub index: usize,
+    pub car_id: i32,
+    pub from: String,
+    pub to: String,
+    pub auction_id: Option<i32>, // auction that sold the car, for BUY
+    pub settlement: Option<AuctionSettlement>, // that auction's proven settlement, for BUY
+    pub offer_id: Option<i32>, // offer the owner accepts, for SELL
+    pub amount: u64,
+    pub action: CarAction,
+    pub timestamp: i64,
+    pub actor: Actor,
+}
+
+// Journal of the transfer_car guest: one link in a car's chain of custody.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct CarTransfer {
+    // the published root the old car root belongs to
+    pub state_root: Digest,
+    pub car_id: i32,
+    pub from: String,
+    pub to: String,
+    pub auction_id: Option<i32>,
+    pub offer_id: Option<i32>,
+    pub amount: u64,
+    // image id of the settle guest whose receipt a BUY verified
+    pub settlement_image: Option<Digest>,
+    pub car_root: RootTransition,
+    pub state: CarState,
 }
 
 // message: &[u8], signature_bytes: &[u8; 65]
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SELLER: &str = "0x1111111111111111111111111111111111111111";
+    const BIDDER: &str = "0x2222222222222222222222222222222222222222";
+
+    fn auction() -> AuctionLeaf {
+        AuctionLeaf {
+            id: 3,
+            car_id: 1,
+            seller: SELLER.to_string(),
+            start_time: 1_000,
+            end_time: 2_000,
+            current_bid: 10_000,
+            bid_count: 0,
+            status: AuctionStatus::Active,
+            auction_type: AuctionType::English,
+            reveal_end_time: None,
+            floor_price: None,
+            reserve_price: Some(12_000),
+            buy_now_price: None,
+            min_increment: Some(100),
+            soft_close_window: None,
+            soft_close_extension: None,
+            max_end_time: None,
+            opening_price: 10_000,
+            commitment_root: None,
+            created_at: 900,
+            updated_at: 900,
+        }
+    }
+
+    fn bid() -> BidLeaf {
+        BidLeaf {
+            id: 5,
+            auction_id: 3,
+            bidder: BIDDER.to_string(),
+            amount: 10_500,
+            created_at: 1_500,
+            updated_at: 1_500,
+            retracted_at: None,
+        }
+    }
+
+    // a car leaf as the host encodes it, with a colon in its description
+    fn car(description: &str, owner: &str) -> String {
+        format!(
+            concat!(
+                "1:Toyota:Corolla:2020:Blue:30000:VIN1:Automatic:Petrol:1.8:Blue:Black:30000:{}:",
+                "None:3:9000:9000:active:800:800:42:{}"
+            ),
+            description,
+            owner
+        )
+    }
+
+    fn leaves(car: String, auction: &AuctionLeaf, bid: &BidLeaf) -> StateLeaves {
+        let other = AuctionLeaf { id: 4, car_id: 2, ..self::auction() };
+        StateLeaves {
+            car: vec![car],
+            auction: vec![auction.encode(), other.encode()],
+            bid: vec![bid.encode()],
+        }
+    }
+
+    fn offer(counter_of: Option<i32>) -> OfferLeaf {
+        OfferLeaf {
+            id: 8,
+            car_id: 1,
+            buyer: BIDDER.to_string(),
+            seller: SELLER.to_string(),
+            proposer: BIDDER.to_string(),
+            amount: 9_500,
+            expires_at: 5_000,
+            status: OfferStatus::Countered,
+            counter_of,
+            created_at: 1_200,
+            updated_at: 1_300,
+        }
+    }
+
+    #[test]
+    fn offer_leaf_parses_what_it_encodes() {
+        for leaf in [offer(None), offer(Some(7))] {
+            assert_eq!(OfferLeaf::parse(&leaf.encode()), Some(leaf));
+        }
+    }
+
+    #[test]
+    fn offer_leaf_rejects_other_leaves() {
+        let leaf = offer(None).encode();
+        assert_eq!(OfferLeaf::parse(&car("Clean", SELLER)), None);
+        assert_eq!(OfferLeaf::parse(leaf.strip_prefix("offer:").unwrap()), None);
+        assert_eq!(OfferLeaf::parse(&leaf.replace("Countered", "Open")), None);
+        assert_eq!(OfferLeaf::parse(&format!("{}:0", leaf)), None);
+    }
+
+    #[test]
+    fn auction_and_bid_leaves_parse_what_they_encode() {
+        let sealed = AuctionLeaf {
+            auction_type: AuctionType::Vickrey,
+            reveal_end_time: Some(3_000),
+            bid_count: 2,
+            commitment_root: Some(format!("{}", Digest::new([7; 8]))),
+            ..auction()
+        };
+        for leaf in [auction(), sealed] {
+            assert_eq!(AuctionLeaf::parse(&leaf.encode()), Some(leaf));
+        }
+        let retracted = BidLeaf { retracted_at: Some(1_600), ..bid() };
+        for leaf in [bid(), retracted] {
+            assert_eq!(BidLeaf::parse(&leaf.encode()), Some(leaf));
+        }
+        assert_eq!(AuctionLeaf::parse(&bid().encode()), None);
+        assert_eq!(BidLeaf::parse(&auction().encode()), None);
+    }
+
+    #[test]
+    fn dutch_price_falls_linearly_to_the_floor() {
+        let dutch = AuctionLeaf {
+            auction_type: AuctionType::Dutch,
+            floor_price: Some(6_000),
+            ..auction()
+        };
+        assert_eq!(dutch.dutch_price(500), Some(10_000));
+        assert_eq!(dutch.dutch_price(1_000), Some(10_000));
+        assert_eq!(dutch.dutch_price(1_250), Some(9_000));
+        assert_eq!(dutch.dutch_price(1_500), Some(8_000));
+        assert_eq!(dutch.dutch_price(2_000), Some(6_000));
+        assert_eq!(dutch.dutch_price(2_500), Some(6_000));
+        assert_eq!(auction().dutch_price(1_500), None);
+        assert_eq!(AuctionLeaf { floor_price: None, ..dutch }.dutch_price(1_500), None);
+    }
+
+    #[test]
+    fn bid_raise_and_retraction_follow() {
+        let old = leaves(car("Clean", SELLER), &auction(), &bid());
+        let raised = BidLeaf { amount: 11_000, updated_at: 1_700, ..bid() };
+        let moved = AuctionLeaf { current_bid: 11_000, updated_at: 1_700, ..auction() };
+        let new = leaves(car("Clean", SELLER), &moved, &raised);
+        assert!(StateAction::Bid(BidAction::UPDATE).follows(&old, &new));
+        assert!(!StateAction::Bid(BidAction::DELETE).follows(&old, &new));
+
+        let retracted = BidLeaf { retracted_at: Some(1_700), updated_at: 1_700, ..bid() };
+        let new = leaves(car("Clean", SELLER), &auction(), &retracted);
+        assert!(StateAction::Bid(BidAction::DELETE).follows(&old, &new));
+        assert!(!StateAction::Bid(BidAction::UPDATE).follows(&old, &new));
+    }
+
+    #[test]
+    fn bid_changes_outside_the_action_do_not_follow() {
+        let old = leaves(car("Clean", SELLER), &auction(), &bid());
+        let action = StateAction::Bid(BidAction::UPDATE);
+        for changed in [
+            BidLeaf { amount: 11_000, bidder: SELLER.to_string(), ..bid() },
+            BidLeaf { amount: 11_000, id: 6, ..bid() },
+            BidLeaf { amount: 10_000, ..bid() },
+            BidLeaf { amount: 11_000, retracted_at: Some(1_700), ..bid() },
+        ] {
+            let new = leaves(car("Clean", SELLER), &auction(), &changed);
+            assert!(!action.follows(&old, &new), "{:?}", changed);
+        }
+
+        // only the bid's own auction may move with it
+        let raised = BidLeaf { amount: 11_000, ..bid() };
+        let mut new = leaves(car("Clean", SELLER), &auction(), &raised);
+        let other = AuctionLeaf { id: 4, car_id: 2, current_bid: 11_000, ..auction() };
+        new.auction[1] = other.encode();
+        assert!(!action.follows(&old, &new));
+        // nor the car
+        let new = leaves(car("Clean", BIDDER), &auction(), &raised);
+        assert!(!action.follows(&old, &new));
+        // nor a leaf added
+        let mut new = leaves(car("Clean", SELLER), &auction(), &raised);
+        new.bid.push(BidLeaf { id: 6, ..bid() }.encode());
+        assert!(!action.follows(&old, &new));
+    }
+
+    #[test]
+    fn auction_edit_commitment_and_cancel_follow() {
+        let old = leaves(car("Clean", SELLER), &auction(), &bid());
+        let update = StateAction::Auction(AuctionAction::UPDATE);
+        let cancel = StateAction::Auction(AuctionAction::DELETE);
+
+        let edited = AuctionLeaf { end_time: 2_500, reserve_price: None, ..auction() };
+        assert!(update.follows(&old, &leaves(car("Clean", SELLER), &edited, &bid())));
+        let moved = AuctionLeaf { seller: BIDDER.to_string(), ..edited.clone() };
+        assert!(!update.follows(&old, &leaves(car("Clean", SELLER), &moved, &bid())));
+        let closed = AuctionLeaf { status: AuctionStatus::Completed, ..edited };
+        assert!(!update.follows(&old, &leaves(car("Clean", SELLER), &closed, &bid())));
+
+        let sealed = AuctionLeaf { auction_type: AuctionType::Sealed, ..auction() };
+        let old_sealed = leaves(car("Clean", SELLER), &sealed, &bid());
+        let committed = AuctionLeaf {
+            bid_count: 1,
+            commitment_root: Some(format!("{}", Digest::new([7; 8]))),
+            updated_at: 1_500,
+            ..sealed.clone()
+        };
+        let new = leaves(car("Clean", SELLER), &committed, &bid());
+        assert!(update.follows(&old_sealed, &new));
+        let skipped = AuctionLeaf { bid_count: 2, ..committed };
+        assert!(!update.follows(&old_sealed, &leaves(car("Clean", SELLER), &skipped, &bid())));
+
+        let cancelled = AuctionLeaf {
+            status: AuctionStatus::Cancelled,
+            updated_at: 1_100,
+            ..auction()
+        };
+        let car_cancelled = car("Clean", SELLER).replace(":active:", ":cancelled:");
+        let new = leaves(car_cancelled, &cancelled, &bid());
+        assert!(cancel.follows(&old, &new));
+        assert!(!update.follows(&old, &new));
+        let bid_on = AuctionLeaf { bid_count: 1, ..auction() };
+        let old_bid_on = leaves(car("Clean", SELLER), &bid_on, &bid());
+        let cancelled = AuctionLeaf { status: AuctionStatus::Cancelled, ..bid_on };
+        assert!(!cancel.follows(&old_bid_on, &leaves(car("Clean", SELLER), &cancelled, &bid())));
+    }
+
+    #[test]
+    fn car_edit_follows_only_without_moving_ownership() {
+        let old = leaves(car("Clean", SELLER), &auction(), &bid());
+        let update = StateAction::Car(CarAction::UPDATE);
+        let edited = car("Clean: one owner", SELLER).replace(":800:800:", ":800:900:");
+        assert!(update.follows(&old, &leaves(edited, &auction(), &bid())));
+        assert!(!update.follows(&old, &leaves(car("Clean", BIDDER), &auction(), &bid())));
+        let other = car("Clean", SELLER).replacen("1:", "2:", 1);
+        assert!(!update.follows(&old, &leaves(other, &auction(), &bid())));
+        let ended = AuctionLeaf { status: AuctionStatus::Completed, ..auction() };
+        let new = leaves(car("Clean, repainted", SELLER), &ended, &bid());
+        assert!(!update.follows(&old, &new));
+        let sold = leaves(car("Clean", BIDDER), &auction(), &bid());
+        assert!(!StateAction::Car(CarAction::BUY).follows(&old, &sold));
+    }
+}
@@ -0,0 +1,133 @@
+// Binary keccak Merkle tree over state leaves. Leaves are hashed with `leaf_hash`, and a level
+// with an odd number of nodes pairs its last node with a zero digest, so a sibling never depends
+// on the node it is paired with. The empty tree has a zero root.
+use risc0_zkp::core::digest::Digest;
+use serde::{ Deserialize, Serialize };
+use tiny_keccak::{ Hasher, Keccak };
+
+use crate::leaf_hash;
+
+fn node_hash(left: &Digest, right: &Digest) -> Digest {
+    let mut hasher = Keccak::v256();
+    hasher.update(left.as_bytes());
+    hasher.update(right.as_bytes());
+    let mut output = [0; 32];
+    hasher.finalize(&mut output);
+    Digest::from_bytes(output)
+}
+
+const ZERO: Digest = Digest::new([0; 8]);
+
+fn next_level(level: &[Digest]) -> Vec<Digest> {
+    level
+        .chunks(2)
+        .map(|pair| node_hash(&pair[0], pair.get(1).unwrap_or(&ZERO)))
+        .collect()
+}
+
+pub fn merkle_root(leaves: &[String]) -> Digest {
+    if leaves.is_empty() {
+        return ZERO;
+    }
+    let mut level: Vec<Digest> = leaves
+        .iter()
+        .map(|leaf| leaf_hash(leaf))
+        .collect();
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    level[0]
+}
+
+// Siblings from the leaf up to the root. Bit `i` of `index` says whether the node at height `i`
+// is a right child.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct MerklePath {
+    pub index: usize,
+    pub siblings: Vec<Digest>,
+}
+
+impl MerklePath {
+    pub fn new(leaves: &[String], index: usize) -> Option<Self> {
+        if index >= leaves.len() {
+            return None;
+        }
+        let mut level: Vec<Digest> = leaves
+            .iter()
+            .map(|leaf| leaf_hash(leaf))
+            .collect();
+        let mut position = index;
+        let mut siblings = vec![];
+        while level.len() > 1 {
+            let sibling = position ^ 1;
+            siblings.push(*level.get(sibling).unwrap_or(&ZERO));
+            level = next_level(&level);
+            position /= 2;
+        }
+        Some(MerklePath { index, siblings })
+    }
+
+    // Root of the tree with `leaf` at this path. Since the siblings do not change, this gives
+    // both the current root and the root after the leaf is replaced.
+    pub fn root(&self, leaf: &str) -> Digest {
+        let mut node = leaf_hash(leaf);
+        for (height, sibling) in self.siblings.iter().enumerate() {
+            node = if (self.index >> height) & 1 == 1 {
+                node_hash(sibling, &node)
+            } else {
+                node_hash(&node, sibling)
+            };
+        }
+        node
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RootTransition {
+    pub old: Digest,
+    pub new: Digest,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaves(count: usize) -> Vec<String> {
+        (0..count).map(|leaf| format!("leaf-{}", leaf)).collect()
+    }
+
+    #[test]
+    fn odd_levels_pair_with_zero() {
+        let leaves = leaves(3);
+        let hashes: Vec<Digest> = leaves
+            .iter()
+            .map(|leaf| leaf_hash(leaf))
+            .collect();
+        let root = node_hash(&node_hash(&hashes[0], &hashes[1]), &node_hash(&hashes[2], &ZERO));
+        assert_eq!(merkle_root(&leaves), root);
+        assert_eq!(merkle_root(&leaves[..1]), hashes[0]);
+        assert_eq!(merkle_root(&[]), ZERO);
+    }
+
+    #[test]
+    fn paths_verify_every_leaf_of_odd_trees() {
+        for count in [1, 3, 5, 6, 7] {
+            let leaves = leaves(count);
+            let root = merkle_root(&leaves);
+            for (index, leaf) in leaves.iter().enumerate() {
+                let path = MerklePath::new(&leaves, index).unwrap();
+                assert_eq!(path.root(leaf), root, "leaf {} of {}", index, count);
+                assert_ne!(path.root("other"), root);
+            }
+            assert_eq!(MerklePath::new(&leaves, count), None);
+        }
+    }
+
+    #[test]
+    fn path_gives_the_root_after_a_replacement() {
+        let mut leaves = leaves(5);
+        let path = MerklePath::new(&leaves, 4).unwrap();
+        leaves[4] = "replaced".to_string();
+        assert_eq!(path.root("replaced"), merkle_root(&leaves));
+    }
+}
@@ -0,0 +1,270 @@
+// Sealed-bid (commit-reveal) auctions. During bidding only `keccak256(uint256 amount, bytes32 salt)`
+// is published; amounts are revealed to the host after `end_time` and only the winning one
+// leaves the settle_sealed guest.
+use risc0_zkp::core::digest::Digest;
+use serde::{ Deserialize, Serialize };
+
+use crate::{
+    eth::keccak256,
+    merkle::{ merkle_root, MerklePath, RootTransition },
+    Actor,
+    AuctionLeaf,
+    AuctionOutcome,
+    AuctionType,
+    OverallState,
+};
+
+// `keccak256(abi.encodePacked(uint256(amount), bytes32(salt)))` as 0x-prefixed hex, the same
+// value `ethers.solidityPackedKeccak256(["uint256", "bytes32"], [amount, salt])` gives.
+pub fn seal_commitment(amount: u64, salt: &str) -> Result<String, &'static str> {
+    let salt = hex
+        ::decode(salt.strip_prefix("0x").unwrap_or(salt))
+        .map_err(|_| "Invalid salt hex")?;
+    if salt.len() != 32 {
+        return Err("Salt is not 32 bytes");
+    }
+    let mut packed = [0u8; 64];
+    packed[24..32].copy_from_slice(&amount.to_be_bytes());
+    packed[32..].copy_from_slice(&salt);
+    Ok(format!("0x{}", hex::encode(keccak256(&packed))))
+}
+
+// One commitment as hashed into the auction's commitment root. Holds no amount.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SealedBid {
+    pub id: i32,
+    pub auction_id: i32,
+    pub bidder: String,
+    pub commitment: String,
+    pub created_at: i64,
+}
+
+impl SealedBid {
+    pub fn encode(&self) -> String {
+        format!(
+            "{}:{}:{}:{}:{}",
+            self.id,
+            self.auction_id,
+            self.bidder,
+            self.commitment.to_lowercase(),
+            self.created_at
+        )
+    }
+}
+
+// Whether `commitment` is 0x-prefixed lowercase hex of 32 bytes, as the host stores it.
+pub fn valid_commitment(commitment: &str) -> bool {
+    commitment
+        .strip_prefix("0x")
+        .is_some_and(|digits| {
+            digits.len() == 64 && digits.chars().all(|c| matches!(c, '0'..='9' | 'a'..='f'))
+        })
+}
+
+// Hex root over a sealed auction's commitments in the order they were made, as its auction leaf
+// holds it. None before the first commitment.
+pub fn commitment_root(bids: &[SealedBid]) -> Option<String> {
+    if bids.is_empty() {
+        return None;
+    }
+    let leaves: Vec<String> = bids.iter().map(SealedBid::encode).collect();
+    Some(merkle_root(&leaves).to_string())
+}
+
+// Input of the commit_sealed guest: the sealed auction with its Merkle path under `published`,
+// the last proven state commit, the commitments already made on it, and the new one.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SealedCommitmentParams {
+    pub published: OverallState,
+    pub auction: AuctionLeaf,
+    pub auction_path: MerklePath,
+    pub auction_root: Digest,
+    pub commitments: Vec<SealedBid>,
+    pub bid: SealedBid,
+    pub timestamp: i64,
+    pub actor: Actor,
+}
+
+// Journal of the commit_sealed guest: the commitment taken, and the auction root moving to the
+// leaf that counts it in `bid_count` and `commitment_root`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SealedCommitment {
+    pub state_root: Digest,
+    pub bid: SealedBid,
+    pub commitments: usize,
+    pub commitment_root: String,
+    pub auction_root: RootTransition,
+    pub actor: Actor,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SealedReveal {
+    pub bid_id: i32,
+    pub amount: u64,
+    pub salt: String,
+    pub revealed_at: i64,
+}
+
+// Input of the settle_sealed guest: the auction with its Merkle path under `published`, the last
+// proven state commit, every commitment made on it, the reveals the host received, and the leaf
+// of the car sold with its path in the car state. Reveals stay private to the guest.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SealedSettlementParams {
+    pub published: OverallState,
+    pub auction: AuctionLeaf,
+    pub auction_path: MerklePath,
+    pub auction_root: Digest,
+    pub bids: Vec<SealedBid>,
+    pub reveals: Vec<SealedReveal>,
+    pub car: String,
+    pub car_path: MerklePath,
+    pub timestamp: i64,
+    pub actor: Actor,
+}
+
+// Journal of the settle_sealed guest. Only the price paid is public: the winning amount for a
+// sealed auction, the second-highest one (or the reserve, if higher) for a Vickrey auction.
+// `commitment_root` lets bidders check their commitment was counted, and the counts show how
+// many were excluded for being late, unrevealed, not matching their commitment or below the
+// opening price.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SealedSettlement {
+    // the published root the old auction root belongs to
+    pub state_root: Digest,
+    pub auction_id: i32,
+    pub car_id: i32,
+    pub token_id: i32,
+    pub seller: String,
+    pub winner: Option<String>,
+    pub winning_commitment: Option<String>,
+    pub auction_type: AuctionType,
+    pub outcome: AuctionOutcome,
+    pub amount: u64,
+    pub commitment_root: Digest,
+    pub commitments: usize,
+    pub valid_reveals: usize,
+    pub auction_root: RootTransition,
+    pub settled_at: i64,
+    pub actor: Actor,
+}
+
+// The amount a commitment counts for: committed inside the bidding window, revealed inside the
+// reveal window, matching the commitment, and at least the opening price.
+pub fn valid_reveal(
+    auction: &AuctionLeaf,
+    bid: &SealedBid,
+    reveals: &[SealedReveal]
+) -> Option<u64> {
+    let reveal_end = auction.reveal_end_time?;
+    if bid.created_at < auction.start_time || bid.created_at > auction.end_time {
+        return None;
+    }
+    let reveal = reveals.iter().find(|reveal| reveal.bid_id == bid.id)?;
+    if reveal.revealed_at <= auction.end_time || reveal.revealed_at > reveal_end {
+        return None;
+    }
+    let commitment = seal_commitment(reveal.amount, &reveal.salt).ok()?;
+    if !commitment.eq_ignore_ascii_case(&bid.commitment) {
+        return None;
+    }
+    if reveal.amount < (auction.current_bid as u64) {
+        return None;
+    }
+    Some(reveal.amount)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AuctionStatus;
+
+    const SALT: &str = "0x1111111111111111111111111111111111111111111111111111111111111111";
+
+    fn auction() -> AuctionLeaf {
+        AuctionLeaf {
+            id: 3,
+            car_id: 1,
+            seller: "0x1111111111111111111111111111111111111111".to_string(),
+            start_time: 1_000,
+            end_time: 2_000,
+            current_bid: 10_000,
+            bid_count: 1,
+            status: AuctionStatus::Active,
+            auction_type: AuctionType::Sealed,
+            reveal_end_time: Some(3_000),
+            floor_price: None,
+            reserve_price: None,
+            buy_now_price: None,
+            min_increment: None,
+            soft_close_window: None,
+            soft_close_extension: None,
+            max_end_time: None,
+            opening_price: 10_000,
+            commitment_root: None,
+            created_at: 900,
+            updated_at: 900,
+        }
+    }
+
+    fn bid(amount: u64) -> SealedBid {
+        SealedBid {
+            id: 5,
+            auction_id: 3,
+            bidder: "0x2222222222222222222222222222222222222222".to_string(),
+            commitment: seal_commitment(amount, SALT).unwrap(),
+            created_at: 1_500,
+        }
+    }
+
+    fn reveal(amount: u64, revealed_at: i64) -> SealedReveal {
+        SealedReveal { bid_id: 5, amount, salt: SALT.to_string(), revealed_at }
+    }
+
+    #[test]
+    fn seal_commitment_matches_solidity_packed_keccak() {
+        // solidityPackedKeccak256(["uint256", "bytes32"], [12000, SALT])
+        assert_eq!(
+            seal_commitment(12_000, SALT),
+            Ok("0x5fbde895c2029a65ddfd94e6cc7403c2b09b159da2353ff63fca89225645d090".to_string())
+        );
+    }
+
+    #[test]
+    fn seal_commitment_checks_the_salt() {
+        let commitment = seal_commitment(12_000, SALT).unwrap();
+        assert!(valid_commitment(&commitment));
+        assert_eq!(seal_commitment(12_000, SALT.strip_prefix("0x").unwrap()), Ok(commitment));
+        assert_eq!(seal_commitment(12_000, "0x11"), Err("Salt is not 32 bytes"));
+        assert_eq!(seal_commitment(12_000, "0xzz"), Err("Invalid salt hex"));
+        assert!(!valid_commitment(&seal_commitment(12_000, SALT).unwrap().to_uppercase()));
+    }
+
+    #[test]
+    fn valid_reveal_counts_a_matching_reveal_in_its_window() {
+        let auction = auction();
+        assert_eq!(valid_reveal(&auction, &bid(12_000), &[reveal(12_000, 2_500)]), Some(12_000));
+        assert_eq!(valid_reveal(&auction, &bid(12_000), &[reveal(12_000, 3_000)]), Some(12_000));
+        assert_eq!(valid_reveal(&auction, &bid(10_000), &[reveal(10_000, 2_001)]), Some(10_000));
+    }
+
+    #[test]
+    fn valid_reveal_excludes_the_rest() {
+        let auction = auction();
+        let committed = bid(12_000);
+        // revealed during bidding, or after the reveal window
+        assert_eq!(valid_reveal(&auction, &committed, &[reveal(12_000, 2_000)]), None);
+        assert_eq!(valid_reveal(&auction, &committed, &[reveal(12_000, 3_001)]), None);
+        // not the committed amount, or not revealed at all
+        assert_eq!(valid_reveal(&auction, &committed, &[reveal(13_000, 2_500)]), None);
+        assert_eq!(valid_reveal(&auction, &committed, &[]), None);
+        // committed after bidding closed
+        let late = SealedBid { created_at: 2_001, ..committed.clone() };
+        assert_eq!(valid_reveal(&auction, &late, &[reveal(12_000, 2_500)]), None);
+        // below the opening price
+        let low = bid(9_000);
+        assert_eq!(valid_reveal(&auction, &low, &[reveal(9_000, 2_500)]), None);
+        // no reveal window
+        let open = AuctionLeaf { reveal_end_time: None, ..auction };
+        assert_eq!(valid_reveal(&open, &committed, &[reveal(12_000, 2_500)]), None);
+    }
+}
@@ -0,0 +1,347 @@
+// Sign-In with Ethereum (EIP-4361) messages.
+// Shared by the host, which builds the message handed to the wallet, and the verify guest,
+// which parses the signed message and checks it is scoped to our domain and nonce.
+use core::fmt;
+
+use chrono::{ DateTime, SecondsFormat, TimeZone, Utc };
+use serde::{ Deserialize, Serialize };
+
+const PREAMBLE: &str = " wants you to sign in with your Ethereum account:";
+const URI_TAG: &str = "URI: ";
+const VERSION_TAG: &str = "Version: ";
+const CHAIN_TAG: &str = "Chain ID: ";
+const NONCE_TAG: &str = "Nonce: ";
+const IAT_TAG: &str = "Issued At: ";
+const EXP_TAG: &str = "Expiration Time: ";
+const NBF_TAG: &str = "Not Before: ";
+const RID_TAG: &str = "Request ID: ";
+const RES_TAG: &str = "Resources:";
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SiweMessage {
+    pub domain: String,
+    pub address: String,
+    pub statement: Option<String>,
+    pub uri: String,
+    pub version: String,
+    pub chain_id: u64,
+    pub nonce: String,
+    pub issued_at: String,
+    pub expiration_time: Option<String>,
+    pub not_before: Option<String>,
+    pub request_id: Option<String>,
+    pub resources: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum SiweError {
+    Format(&'static str),
+    Timestamp(&'static str),
+    DomainMismatch,
+    AddressMismatch,
+    NonceMismatch,
+    NotYetValid,
+    Expired,
+    MissingExpiration,
+}
+
+impl fmt::Display for SiweError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SiweError::Format(field) => write!(f, "Malformed SIWE message: {}", field),
+            SiweError::Timestamp(field) => write!(f, "Invalid SIWE timestamp: {}", field),
+            SiweError::DomainMismatch => f.write_str("SIWE domain mismatch"),
+            SiweError::AddressMismatch => f.write_str("SIWE address mismatch"),
+            SiweError::NonceMismatch => f.write_str("SIWE nonce mismatch"),
+            SiweError::NotYetValid => f.write_str("SIWE message is not yet valid"),
+            SiweError::Expired => f.write_str("SIWE message has expired"),
+            SiweError::MissingExpiration => f.write_str("SIWE message has no expiration time"),
+        }
+    }
+}
+
+impl std::error::Error for SiweError {}
+
+pub fn to_rfc3339(ts: i64) -> String {
+    Utc.timestamp_opt(ts, 0)
+        .single()
+        .unwrap_or_default()
+        .to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+fn parse_time(value: &str, field: &'static str) -> Result<i64, SiweError> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.timestamp())
+        .map_err(|_| SiweError::Timestamp(field))
+}
+
+fn tagged<'a>(line: Option<&'a str>, tag: &str, field: &'static str) -> Result<&'a str, SiweError> {
+    line.and_then(|l| l.strip_prefix(tag)).ok_or(SiweError::Format(field))
+}
+
+impl SiweMessage {
+    pub fn issued_at_ts(&self) -> Result<i64, SiweError> {
+        parse_time(&self.issued_at, "issued-at")
+    }
+
+    pub fn expiration_ts(&self) -> Result<Option<i64>, SiweError> {
+        self.expiration_time
+            .as_deref()
+            .map(|exp| parse_time(exp, "expiration-time"))
+            .transpose()
+    }
+
+    pub fn not_before_ts(&self) -> Result<Option<i64>, SiweError> {
+        self.not_before
+            .as_deref()
+            .map(|nbf| parse_time(nbf, "not-before"))
+            .transpose()
+    }
+
+    pub fn parse(message: &str) -> Result<Self, SiweError> {
+        let mut lines = message.split('\n').peekable();
+
+        let domain = lines
+            .next()
+            .and_then(|l| l.strip_suffix(PREAMBLE))
+            .ok_or(SiweError::Format("preamble"))?;
+        let address = lines.next().ok_or(SiweError::Format("address"))?;
+        if !address.starts_with("0x") || address.len() != 42 {
+            return Err(SiweError::Format("address"));
+        }
+        if lines.next() != Some("") {
+            return Err(SiweError::Format("address"));
+        }
+        // an optional statement is followed by a blank line; without one the blank line stays
+        let statement = match lines.next() {
+            Some("") => None,
+            Some(statement) => {
+                if lines.next() != Some("") {
+                    return Err(SiweError::Format("statement"));
+                }
+                Some(statement.to_string())
+            }
+            None => {
+                return Err(SiweError::Format("statement"));
+            }
+        };
+
+        let uri = tagged(lines.next(), URI_TAG, "uri")?;
+        let version = tagged(lines.next(), VERSION_TAG, "version")?;
+        if version != "1" {
+            return Err(SiweError::Format("version"));
+        }
+        let chain_id = tagged(lines.next(), CHAIN_TAG, "chain-id")?
+            .parse::<u64>()
+            .map_err(|_| SiweError::Format("chain-id"))?;
+        let nonce = tagged(lines.next(), NONCE_TAG, "nonce")?;
+        if nonce.len() < 8 || !nonce.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(SiweError::Format("nonce"));
+        }
+        let issued_at = tagged(lines.next(), IAT_TAG, "issued-at")?;
+        parse_time(issued_at, "issued-at")?;
+
+        let mut optional = |tag: &str| -> Option<String> {
+            let value = lines.peek().and_then(|l| l.strip_prefix(tag)).map(|v| v.to_string());
+            if value.is_some() {
+                lines.next();
+            }
+            value
+        };
+        let expiration_time = optional(EXP_TAG);
+        let not_before = optional(NBF_TAG);
+        let request_id = optional(RID_TAG);
+
+        let mut resources = vec![];
+        if let Some(line) = lines.next() {
+            if line != RES_TAG {
+                return Err(SiweError::Format("resources"));
+            }
+            for resource in lines.by_ref() {
+                let resource = resource.strip_prefix("- ").ok_or(SiweError::Format("resources"))?;
+                resources.push(resource.to_string());
+            }
+        }
+
+        let message = SiweMessage {
+            domain: domain.to_string(),
+            address: address.to_string(),
+            statement,
+            uri: uri.to_string(),
+            version: version.to_string(),
+            chain_id,
+            nonce: nonce.to_string(),
+            issued_at: issued_at.to_string(),
+            expiration_time,
+            not_before,
+            request_id,
+            resources,
+        };
+        message.expiration_ts()?;
+        message.not_before_ts()?;
+        Ok(message)
+    }
+
+    // Checks the message is scoped to `domain`, `nonce` and `address` and valid at `now`.
+    // Logins must carry an expiration time so a captured signature cannot be reused forever.
+    pub fn verify(
+        &self,
+        domain: &str,
+        nonce: &str,
+        address: &str,
+        now: i64
+    ) -> Result<(), SiweError> {
+        if self.domain != domain {
+            return Err(SiweError::DomainMismatch);
+        }
+        if self.nonce != nonce {
+            return Err(SiweError::NonceMismatch);
+        }
+        if !self.address.eq_ignore_ascii_case(address) {
+            return Err(SiweError::AddressMismatch);
+        }
+        if self.issued_at_ts()? > now {
+            return Err(SiweError::NotYetValid);
+        }
+        if let Some(nbf) = self.not_before_ts()? {
+            if nbf > now {
+                return Err(SiweError::NotYetValid);
+            }
+        }
+        match self.expiration_ts()? {
+            Some(exp) if exp <= now => Err(SiweError::Expired),
+            Some(_) => Ok(()),
+            None => Err(SiweError::MissingExpiration),
+        }
+    }
+}
+
+impl fmt::Display for SiweMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}{}", self.domain, PREAMBLE)?;
+        writeln!(f, "{}", self.address)?;
+        writeln!(f)?;
+        if let Some(statement) = &self.statement {
+            writeln!(f, "{}", statement)?;
+        }
+        writeln!(f)?;
+        writeln!(f, "{}{}", URI_TAG, self.uri)?;
+        writeln!(f, "{}{}", VERSION_TAG, self.version)?;
+        writeln!(f, "{}{}", CHAIN_TAG, self.chain_id)?;
+        writeln!(f, "{}{}", NONCE_TAG, self.nonce)?;
+        write!(f, "{}{}", IAT_TAG, self.issued_at)?;
+        if let Some(exp) = &self.expiration_time {
+            write!(f, "\n{}{}", EXP_TAG, exp)?;
+        }
+        if let Some(nbf) = &self.not_before {
+            write!(f, "\n{}{}", NBF_TAG, nbf)?;
+        }
+        if let Some(rid) = &self.request_id {
+            write!(f, "\n{}{}", RID_TAG, rid)?;
+        }
+        if !self.resources.is_empty() {
+            write!(f, "\n{}", RES_TAG)?;
+            for resource in &self.resources {
+                write!(f, "\n- {}", resource)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message() -> SiweMessage {
+        SiweMessage {
+            domain: "auction.example.com".to_string(),
+            address: "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266".to_string(),
+            statement: Some("Sign in to the car auction.".to_string()),
+            uri: "https://auction.example.com/login".to_string(),
+            version: "1".to_string(),
+            chain_id: 1,
+            nonce: "a1b2c3d4e5f6".to_string(),
+            issued_at: to_rfc3339(1_700_000_000),
+            expiration_time: Some(to_rfc3339(1_700_000_600)),
+            not_before: Some(to_rfc3339(1_700_000_000)),
+            request_id: Some("login-1".to_string()),
+            resources: vec![
+                "https://auction.example.com/terms".to_string(),
+                "ipfs://bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi".to_string()
+            ],
+        }
+    }
+
+    #[test]
+    fn parses_what_it_displays() {
+        let full = message();
+        let bare = SiweMessage {
+            statement: None,
+            expiration_time: None,
+            not_before: None,
+            request_id: None,
+            resources: vec![],
+            ..message()
+        };
+        for message in [full, bare] {
+            assert_eq!(SiweMessage::parse(&message.to_string()), Ok(message));
+        }
+    }
+
+    #[test]
+    fn displays_the_eip4361_layout() {
+        let text = message().to_string();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(
+            lines[0],
+            "auction.example.com wants you to sign in with your Ethereum account:"
+        );
+        assert_eq!(lines[2], "");
+        assert_eq!(lines[3], "Sign in to the car auction.");
+        assert_eq!(lines[8], "Nonce: a1b2c3d4e5f6");
+        assert_eq!(lines[9], "Issued At: 2023-11-14T22:13:20Z");
+        assert_eq!(lines[13], "Resources:");
+        assert!(!text.ends_with('\n'));
+    }
+
+    #[test]
+    fn rejects_malformed_messages() {
+        let text = message().to_string();
+        let cases = [
+            (text.replace("Version: 1", "Version: 2"), "version"),
+            (text.replace("Nonce: a1b2c3d4e5f6", "Nonce: short"), "nonce"),
+            (text.replace("Chain ID: 1", "Chain ID: one"), "chain-id"),
+            (text.replace("Resources:", "Links:"), "resources"),
+        ];
+        for (text, field) in cases {
+            assert_eq!(SiweMessage::parse(&text), Err(SiweError::Format(field)));
+        }
+        let text = text.replace("2023-11-14T22:23:20Z", "soon");
+        assert_eq!(SiweMessage::parse(&text), Err(SiweError::Timestamp("expiration-time")));
+    }
+
+    #[test]
+    fn verifies_scope_and_validity() {
+        let message = message();
+        let address = "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266";
+        let verify = |domain, nonce, now| message.verify(domain, nonce, address, now);
+        assert_eq!(verify("auction.example.com", "a1b2c3d4e5f6", 1_700_000_100), Ok(()));
+        assert_eq!(
+            verify("evil.example.com", "a1b2c3d4e5f6", 1_700_000_100),
+            Err(SiweError::DomainMismatch)
+        );
+        assert_eq!(
+            verify("auction.example.com", "f6e5d4c3b2a1", 1_700_000_100),
+            Err(SiweError::NonceMismatch)
+        );
+        assert_eq!(
+            verify("auction.example.com", "a1b2c3d4e5f6", 1_699_999_999),
+            Err(SiweError::NotYetValid)
+        );
+        assert_eq!(
+            verify("auction.example.com", "a1b2c3d4e5f6", 1_700_000_600),
+            Err(SiweError::Expired)
+        );
+    }
+}
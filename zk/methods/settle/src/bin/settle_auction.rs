@@ -0,0 +1,94 @@
+#![no_main]
+// #![no_std]
+use risc0_zkvm::guest::env;
+use car_auction_core::{
+    merkle::{ merkle_root, RootTransition },
+    token_id,
+    winning_bid,
+    AuctionLeaf,
+    AuctionOutcome,
+    AuctionStatus,
+    AuctionType,
+    BidLeaf,
+    Settlement,
+    SettlementParams,
+};
+risc0_zkvm::guest::entry!(main);
+
+// proof of settlement: every bid in the bid tree of the published state commit is read, so the
+// winner is the highest bid of the auction and not just the highest one the host chose to show.
+// The auction must be active and past its end time (or sold at a Dutch clock or buy-now price),
+// and leaves the auction tree as completed. A highest bid below the reserve settles as
+// `ReserveNotMet`, without a winner. The `token_id` comes from the car's leaf in the published
+// car state.
+fn main() {
+    let input: SettlementParams = env::read();
+    let published = input.published;
+    assert!(published.is_consistent(), "Published state does not match its roots");
+    assert_eq!(
+        input.auction_root,
+        published.auc_state.overall,
+        "Auction root is not the published one"
+    );
+    assert_eq!(input.bid_root, published.bid_state.overall, "Bid root is not the published one");
+
+    let auction = input.auction;
+    assert_eq!(
+        input.auction_path.root(&auction.encode()),
+        input.auction_root,
+        "Auction not in the auction state"
+    );
+    assert!(!auction.auction_type.is_sealed(), "Sealed auctions settle on reveal");
+    assert_eq!(auction.status, AuctionStatus::Active, "Auction is not active");
+    let sold =
+        (auction.auction_type == AuctionType::Dutch && auction.bid_count > 0) || auction.bought();
+    assert!(sold || input.timestamp > auction.end_time, "Auction has not ended");
+    assert_eq!(
+        input.car_path.root(&input.car),
+        published.car_state.overall,
+        "Car not in the car state"
+    );
+    let token_id = token_id(&input.car, auction.car_id).expect("Car is not the auction's");
+
+    let leaves: Vec<String> = input.bids
+        .iter()
+        .map(BidLeaf::encode)
+        .collect();
+    assert_eq!(merkle_root(&leaves), input.bid_root, "Bids do not match the bid state");
+
+    let highest = winning_bid(auction.id, &input.bids);
+    // the auction leaf tracks the highest bid, so the two must agree
+    if let Some(bid) = highest {
+        assert_eq!(bid.amount, auction.current_bid, "Winning bid is not the current bid");
+    }
+    let outcome = auction.outcome(highest.map(|bid| bid.amount as u64));
+    let winner = highest.filter(|_| outcome == AuctionOutcome::Sold);
+
+    let completed = AuctionLeaf {
+        status: AuctionStatus::Completed,
+        updated_at: input.timestamp,
+        ..auction.clone()
+    };
+
+    // write public output to the journal
+    env::commit(
+        &(Settlement {
+            state_root: published.new_state,
+            auction_id: auction.id,
+            car_id: auction.car_id,
+            token_id,
+            seller: auction.seller.clone(),
+            winner: winner.map(|bid| bid.bidder.clone()),
+            winning_bid: winner.map(|bid| bid.id),
+            outcome,
+            amount: winner.map(|bid| bid.amount as u64).unwrap_or(0),
+            auction_root: RootTransition {
+                old: input.auction_root,
+                new: input.auction_path.root(&completed.encode()),
+            },
+            bid_root: input.bid_root,
+            settled_at: input.timestamp,
+            actor: input.actor,
+        })
+    );
+}
@@ -0,0 +1,121 @@
+#![no_main]
+// #![no_std]
+use risc0_zkvm::guest::env;
+use car_auction_core::{
+    merkle::{ merkle_root, RootTransition },
+    sealed::{
+        commitment_root,
+        valid_reveal,
+        SealedBid,
+        SealedSettlement,
+        SealedSettlementParams,
+    },
+    token_id,
+    AuctionLeaf,
+    AuctionOutcome,
+    AuctionStatus,
+    AuctionType,
+};
+risc0_zkvm::guest::entry!(main);
+
+// proof of a sealed-bid settlement against the auction tree of the published state commit:
+// the commitments are exactly the ones the auction leaf's `commitment_root` holds. Every
+// commitment on the auction is opened against its reveal, late, unrevealed and mismatched
+// ones are dropped, and the highest remaining amount wins (earliest commitment on a tie). A
+// sealed auction's winner pays their own amount, a Vickrey auction's winner pays the
+// second-highest amount (or the opening price when alone), raised to the reserve. A winning
+// amount below the reserve sells nothing. Only the price paid is committed.
+fn main() {
+    let input: SealedSettlementParams = env::read();
+    let published = input.published;
+    assert!(published.is_consistent(), "Published state does not match its roots");
+    assert_eq!(
+        input.auction_root,
+        published.auc_state.overall,
+        "Auction root is not the published one"
+    );
+
+    let auction = input.auction;
+    assert_eq!(
+        input.auction_path.root(&auction.encode()),
+        input.auction_root,
+        "Auction not in the auction state"
+    );
+    assert!(auction.auction_type.is_sealed(), "Auction is not sealed");
+    assert_eq!(auction.status, AuctionStatus::Active, "Auction is not active");
+    let reveal_end = auction.reveal_end_time.expect("Sealed auction without a reveal window");
+    assert!(input.timestamp > reveal_end, "Reveal window has not closed");
+    assert_eq!(
+        input.car_path.root(&input.car),
+        published.car_state.overall,
+        "Car not in the car state"
+    );
+    let token_id = token_id(&input.car, auction.car_id).expect("Car is not the auction's");
+    assert!(
+        input.bids.iter().all(|bid| bid.auction_id == auction.id),
+        "Commitment for another auction"
+    );
+    // the auction leaf counts every proven commitment, so none can be added or left out here
+    assert_eq!(
+        commitment_root(&input.bids),
+        auction.commitment_root,
+        "Commitments do not match the auction"
+    );
+    assert_eq!(input.bids.len(), auction.bid_count as usize, "Commitment count does not match");
+
+    let leaves: Vec<String> = input.bids
+        .iter()
+        .map(SealedBid::encode)
+        .collect();
+    let mut valid: Vec<(&SealedBid, u64)> = input.bids
+        .iter()
+        .filter_map(|bid| valid_reveal(&auction, bid, &input.reveals).map(|amount| (bid, amount)))
+        .collect();
+    valid.sort_by_key(|(bid, amount)| (std::cmp::Reverse(*amount), bid.created_at, bid.id));
+    let outcome = auction.outcome(valid.first().map(|(_, amount)| *amount));
+    let winner = valid.first().filter(|_| outcome == AuctionOutcome::Sold);
+
+    let reserve = auction.reserve_price.unwrap_or(0) as u64;
+    let amount = match (auction.auction_type, winner) {
+        (_, None) => 0,
+        (AuctionType::Vickrey, Some(_)) =>
+            valid
+                .get(1)
+                .map(|(_, amount)| *amount)
+                .unwrap_or(auction.current_bid as u64)
+                .max(reserve),
+        (_, Some((_, amount))) => *amount,
+    };
+    let completed = AuctionLeaf {
+        status: AuctionStatus::Completed,
+        current_bid: if winner.is_some() { amount as i64 } else { auction.current_bid },
+        bid_count: input.bids.len() as i32,
+        updated_at: input.timestamp,
+        ..auction.clone()
+    };
+
+    // write public output to the journal
+    env::commit(
+        &(SealedSettlement {
+            state_root: published.new_state,
+            auction_id: auction.id,
+            car_id: auction.car_id,
+            token_id,
+            seller: auction.seller.clone(),
+            winner: winner.map(|(bid, _)| bid.bidder.clone()),
+            winning_commitment: winner.map(|(bid, _)| bid.commitment.clone()),
+            auction_type: auction.auction_type,
+            outcome,
+            amount,
+            commitment_root: merkle_root(&leaves),
+            commitments: input.bids.len(),
+            valid_reveals: valid.len(),
+            auction_root: RootTransition {
+                old: input.auction_root,
+                new: input.auction_path.root(&completed.encode()),
+            },
+            settled_at: input.timestamp,
+            actor: input.actor,
+        })
+    );
+}